@@ -0,0 +1,290 @@
+use chrono::{DateTime, Utc};
+use reqwest::blocking::Client;
+use serde_json::{self, json, Value};
+
+use crate::error::Error;
+use crate::feed;
+use crate::source::TweetKind;
+use crate::twitter::{self, USER_AGENT};
+
+pub const DEFAULT_API_BASE_URL: &'static str = "https://public.api.bsky.app";
+
+pub const MAX_FEED_COUNT: u32 = 100;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    pub did: String,
+    pub seen_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+pub struct BlueskyClient {
+    pub client: Client,
+    pub api_base_url: String,
+}
+
+impl Position {
+    pub fn from_cursor(cursor: Option<&str>) -> Option<Position> {
+        let value: Value = serde_json::from_str(cursor?).ok()?;
+        Some(Position {
+            did: String::from(value["did"].as_str()?),
+            seen_at: value["seen_at"].as_str().and_then(|date| DateTime::parse_from_rfc3339(date).ok()).map(|date| date.with_timezone(&Utc)),
+        })
+    }
+
+    pub fn to_cursor(&self) -> String {
+        json!({ "did": self.did, "seen_at": self.seen_at.map(|date| date.to_rfc3339()) }).to_string()
+    }
+}
+
+impl BlueskyClient {
+    pub fn new(client: Client, api_base_url: &str) -> BlueskyClient {
+        BlueskyClient { client: client, api_base_url: String::from(api_base_url) }
+    }
+
+    fn get(&self, method: &str, query: &[(&str, String)]) -> Result<Value, Error> {
+        let url = format!("{}/xrpc/{}", self.api_base_url, method);
+        let res = self.client.get(&url)
+            .header(reqwest::header::USER_AGENT, USER_AGENT)
+            .query(query)
+            .send()?;
+        if !res.status().is_success() {
+            return Err(Error::from(res));
+        }
+        Ok(res.json()?)
+    }
+
+    pub fn resolve_handle(&self, handle: &str) -> Result<String, Error> {
+        let value = self.get("com.atproto.identity.resolveHandle", &[("handle", String::from(handle.trim_start_matches('@')))])?;
+        value["did"].as_str().map(String::from).ok_or_else(|| Error::BlueskyError(format!("{} has no DID", handle)))
+    }
+
+    pub fn get_profile(&self, did: &str) -> Result<twitter::User, Error> {
+        let profile = self.get("app.bsky.actor.getProfile", &[("actor", String::from(did))])?;
+        Ok(parse_profile(&profile))
+    }
+
+    // without the time of the newest item seen, only the latest page is fetched.
+    pub fn get_author_feed(&self, position: &Position, count: Option<u32>) -> Result<(Vec<twitter::Tweet>, Position), Error> {
+        let count = count.unwrap_or(50) as usize;
+        let mut tweets: Vec<twitter::Tweet> = Vec::new();
+        let mut newest = position.seen_at;
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut query = vec![
+                ("actor", position.did.clone()),
+                ("limit", (count - tweets.len()).min(MAX_FEED_COUNT as usize).to_string()),
+            ];
+            if let Some(cursor) = cursor {
+                query.push(("cursor", cursor));
+            }
+            let value = self.get("app.bsky.feed.getAuthorFeed", &query)?;
+            let items = value["feed"].as_array().ok_or_else(|| Error::BlueskyError(String::from("feed is not an array")))?;
+
+            let mut reached = items.is_empty();
+            for item in items {
+                let sort_at = sort_at(item)?;
+                if position.seen_at.map(|seen_at| sort_at <= seen_at).unwrap_or(false) {
+                    reached = true;
+                    break;
+                }
+                newest = newest.max(Some(sort_at));
+                tweets.push(parse_feed_item(item)?);
+            }
+
+            cursor = value["cursor"].as_str().map(String::from);
+            if reached || position.seen_at.is_none() || tweets.len() >= count || cursor.is_none() {
+                break;
+            }
+        }
+
+        Ok((tweets, Position { did: position.did.clone(), seen_at: newest }))
+    }
+}
+
+// a repost is stored apart from the post.
+pub fn item_id(uri: &str, reposted_by: &str) -> u64 {
+    feed::item_id(uri, reposted_by)
+}
+
+pub fn record_key(uri: &str) -> &str {
+    uri.rsplit('/').next().unwrap_or("")
+}
+
+fn author_did(uri: &str) -> Option<&str> {
+    uri.strip_prefix("at://")?.split('/').next()
+}
+
+pub fn post_url(item: &Value) -> Option<String> {
+    let post = &item["post"];
+    let handle = post["author"]["handle"].as_str()?;
+    let uri = post["uri"].as_str()?;
+    Some(format!("https://bsky.app/profile/{}/post/{}", handle, record_key(uri)))
+}
+
+fn parse_profile(profile: &Value) -> twitter::User {
+    let did = profile["did"].as_str().unwrap_or("");
+    let handle = String::from(profile["handle"].as_str().unwrap_or(""));
+    let name = match profile["displayName"].as_str() {
+        Some(name) if !name.is_empty() => String::from(name),
+        _ => handle.clone(),
+    };
+    twitter::User {
+        id: item_id(did, ""),
+        screen_name: handle,
+        name: name,
+        protected: false,
+        verified: false,
+    }
+}
+
+fn parse_date(value: &Value) -> Result<DateTime<Utc>, Error> {
+    Ok(DateTime::parse_from_rfc3339(value.as_str().unwrap_or(""))?.with_timezone(&Utc))
+}
+
+// the time of the repost for a repost
+fn sort_at(item: &Value) -> Result<DateTime<Utc>, Error> {
+    match item["reason"]["indexedAt"] {
+        Value::String(_) => parse_date(&item["reason"]["indexedAt"]),
+        _ => parse_date(&item["post"]["indexedAt"]),
+    }
+}
+
+fn embedded_images(post: &Value) -> &[Value] {
+    let embed = &post["embed"];
+    embed["images"].as_array().or_else(|| embed["media"]["images"].as_array()).map(|images| images.as_slice()).unwrap_or(&[])
+}
+
+fn embedded_record(post: &Value) -> &Value {
+    let embed = &post["embed"];
+    match embed["$type"].as_str() {
+        Some("app.bsky.embed.record#view") => &embed["record"],
+        Some("app.bsky.embed.recordWithMedia#view") => &embed["record"]["record"],
+        _ => &Value::Null,
+    }
+}
+
+fn parse_post(post: &Value) -> Result<twitter::Tweet, Error> {
+    let uri = post["uri"].as_str().ok_or_else(|| Error::BlueskyError(String::from("post without uri")))?;
+    let author = parse_profile(&post["author"]);
+    // a post view has the record, while the record view of a quote has its value.
+    let record = if post["record"].is_object() { &post["record"] } else { &post["value"] };
+
+    let media = embedded_images(post).iter().filter_map(|image| {
+        let url = image["fullsize"].as_str()?;
+        Some(twitter::Media {
+            media_key: String::from(url.rsplit('/').next().unwrap_or(url)),
+            media_type: String::from("photo"),
+            url: String::from(url),
+        })
+    }).collect();
+
+    Ok(twitter::Tweet {
+        id: item_id(uri, ""),
+        created_at: parse_date(&record["createdAt"])?,
+        user_id: author.id,
+        user_name: author.name,
+        screen_name: author.screen_name,
+        text: String::from(record["text"].as_str().unwrap_or("")),
+        kind: TweetKind::Original,
+        retweeted_status_id: 0,
+        entities: twitter::Entities::default(),
+        references: twitter::References {
+            quoted_status_id: embedded_record(post)["uri"].as_str().map(|uri| item_id(uri, "")).unwrap_or(0),
+            in_reply_to_status_id: record["reply"]["parent"]["uri"].as_str().map(|uri| item_id(uri, "")).unwrap_or(0),
+            in_reply_to_screen_name: String::new(),
+        },
+        referenced_tweets: Vec::new(),
+        media: media,
+        raw_json: post.to_string(),
+    })
+}
+
+pub fn parse_feed_item(item: &Value) -> Result<twitter::Tweet, Error> {
+    let mut tweet = parse_post(&item["post"])?;
+
+    // the post a reply replies to and the one a post quotes come with the item.
+    let parent = &item["reply"]["parent"];
+    if parent["uri"].is_string() && parent["author"].is_object() {
+        let parent = parse_post(parent)?;
+        tweet.references.in_reply_to_screen_name = parent.screen_name.clone();
+        tweet.referenced_tweets.push(parent);
+    }
+    let quoted = embedded_record(&item["post"]);
+    if quoted["uri"].is_string() && quoted["author"].is_object() {
+        tweet.referenced_tweets.push(parse_post(quoted)?);
+    }
+
+    let reason = &item["reason"];
+    if reason["$type"] == "app.bsky.feed.defs#reasonRepost" {
+        let reposter = parse_profile(&reason["by"]);
+        tweet.retweeted_status_id = tweet.id;
+        tweet.id = item_id(item["post"]["uri"].as_str().unwrap_or(""), reason["by"]["did"].as_str().unwrap_or(""));
+        tweet.created_at = sort_at(item)?;
+        tweet.text = format!("RT @{}: {}", tweet.screen_name, tweet.text);
+        tweet.user_id = reposter.id;
+        tweet.user_name = reposter.name;
+        tweet.screen_name = reposter.screen_name;
+    }
+    tweet.kind = feed_item_kind(item);
+    tweet.raw_json = item.to_string();
+    Ok(tweet)
+}
+
+pub fn feed_item_kind(item: &Value) -> TweetKind {
+    let post = &item["post"];
+    let parent_uri = post["record"]["reply"]["parent"]["uri"].as_str();
+    let references = twitter::References {
+        quoted_status_id: embedded_record(post)["uri"].as_str().map(|uri| item_id(uri, "")).unwrap_or(0),
+        in_reply_to_status_id: parent_uri.map(|uri| item_id(uri, "")).unwrap_or(0),
+        in_reply_to_screen_name: String::new(),
+    };
+    let author = post["author"]["did"].as_str();
+    let reply_to_self = author.is_some() && parent_uri.and_then(author_did) == author;
+    TweetKind::classify(item["reason"]["$type"] == "app.bsky.feed.defs#reasonRepost", &references, reply_to_self)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_feed_item() {
+        let feed: Value = serde_json::from_str(include_str!("../tests/fixtures/bluesky/author_feed.json")).unwrap();
+        let items = feed["feed"].as_array().unwrap();
+
+        let reply = parse_feed_item(&items[0]).unwrap();
+        assert_eq!(item_id("at://did:plc:vortis/app.bsky.feed.post/3kreply", ""), reply.id);
+        assert_eq!("vortis.bsky.social", reply.screen_name);
+        assert_eq!("徳島ヴォルティス", reply.user_name);
+        assert_eq!(item_id("at://did:plc:supporter/app.bsky.feed.post/3kparent", ""), reply.references.in_reply_to_status_id);
+        assert_eq!("supporter.bsky.social", reply.references.in_reply_to_screen_name);
+        assert_eq!(1, reply.referenced_tweets.len());
+        assert_eq!("次も勝とう！", reply.referenced_tweets[0].text);
+        assert_eq!(Some(String::from("https://bsky.app/profile/vortis.bsky.social/post/3kreply")), post_url(&items[0]));
+
+        assert_eq!(TweetKind::Reply, reply.kind);
+
+        let repost = parse_feed_item(&items[1]).unwrap();
+        assert_eq!(TweetKind::Retweet, repost.kind);
+        assert_eq!(item_id("at://did:plc:jleague/app.bsky.feed.post/3kschedule", ""), repost.retweeted_status_id);
+        assert_ne!(repost.retweeted_status_id, repost.id);
+        assert_eq!("RT @jleague.bsky.social: 試合日程を発表しました", repost.text);
+        assert_eq!("vortis.bsky.social", repost.screen_name);
+        assert_eq!(parse_date(&json!("2020-05-02T11:00:00.000Z")).unwrap(), repost.created_at);
+        assert_eq!(Some(String::from("https://bsky.app/profile/jleague.bsky.social/post/3kschedule")), post_url(&items[1]));
+
+        let photo = parse_feed_item(&items[2]).unwrap();
+        assert_eq!(TweetKind::Quote, photo.kind);
+        assert_eq!(1, photo.media.len());
+        assert_eq!(item_id("at://did:plc:jleague/app.bsky.feed.post/3kschedule", ""), photo.references.quoted_status_id);
+        assert_eq!("jleague.bsky.social", photo.referenced_tweets[0].screen_name);
+    }
+
+    #[test]
+    fn test_position_cursor() {
+        let position = Position { did: String::from("did:plc:vortis"), seen_at: Some(parse_date(&json!("2020-05-02T11:00:00Z")).unwrap()) };
+        assert_eq!(Some(position.clone()), Position::from_cursor(Some(&position.to_cursor())));
+        assert_eq!(None, Position::from_cursor(None));
+    }
+}
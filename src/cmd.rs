@@ -1,18 +1,34 @@
 mod add;
+mod auth;
+mod backfill;
+mod import;
 mod init;
 mod list;
 mod check_updates;
 mod remove;
+mod search;
+mod source;
 
 use clap::ArgMatches;
 
 use self::add::execute_add;
+use self::auth::execute_auth;
+use self::backfill::execute_backfill;
 use self::init::execute_init;
 use self::list::execute_list;
 use self::check_updates::execute_check_updates;
 use self::remove::execute_remove;
+use self::search::{execute_add_search, execute_remove_search};
+use crate::db::Db;
 use crate::error::Error;
 
+fn record_rate_limit<T>(db: &Db, result: Result<T, Error>) -> Result<T, Error> {
+    if let Err(Error::RateLimitError(ref endpoint, ref reset_at)) = result {
+        db.insert_rate_limit_event(endpoint, reset_at)?;
+    }
+    result
+}
+
 pub fn execute(args: &ArgMatches) -> Result<(), Error> {
     if let Some(args) = args.subcommand_matches("init") {
         return execute_init(args);
@@ -20,8 +36,16 @@ pub fn execute(args: &ArgMatches) -> Result<(), Error> {
         return execute_list(args);
     } else if let Some(args) = args.subcommand_matches("add") {
         return execute_add(args);
+    } else if let Some(args) = args.subcommand_matches("auth") {
+        return execute_auth(args);
+    } else if let Some(args) = args.subcommand_matches("backfill") {
+        return execute_backfill(args);
     } else if let Some(args) = args.subcommand_matches("remove") {
         return execute_remove(args);
+    } else if let Some(args) = args.subcommand_matches("add_search") {
+        return execute_add_search(args);
+    } else if let Some(args) = args.subcommand_matches("remove_search") {
+        return execute_remove_search(args);
     } else if let Some(args) = args.subcommand_matches("check_update") {
         return execute_check_updates(args);
     }
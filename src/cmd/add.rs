@@ -1,37 +1,76 @@
-use chrono::{DateTime, Utc};
 use clap::ArgMatches;
 
+use crate::cmd::auth::BearerToken;
+use crate::cmd::import::{insert_like, insert_tweet, parse_list_name, retrieve_or_insert_author, retrieve_or_insert_user, sync_list_members};
+use crate::cmd::record_rate_limit;
+use crate::cmd::source::{Sources, TwitterSource};
 use crate::db::Db;
-use crate::db::models::{self, User};
+use crate::db::models::User;
 use crate::error::Error;
 use crate::config::Config;
+use crate::source::{Source, SourceKind};
 use crate::twitter::TwitterClient;
 
-fn retrieve_or_insert_user(db: &Db, screen_name: &str) -> Result<User, Error> {
-    if let Some(user) = db.get_user_by_screen_name(screen_name)? {
-        return Ok(user);
-    }
+fn add_list(db: &Db, client: &TwitterClient, bearer_token: &BearerToken, name: &str) -> Result<(), Error> {
+    let (owner, slug) = parse_list_name(name)?;
+    let members = record_rate_limit(db, bearer_token.call(|auth| {
+        client.get_list_members(auth, owner, slug)
+    }))?;
 
-    let user = db.insert_user(screen_name)?;
-    println!("{}: added", user.screen_name.as_str());
+    let list = match db.get_list_by_name(owner, slug)? {
+        Some(list) => list,
+        None => {
+            let list = db.insert_list(owner, slug)?;
+            println!("{}/{}: added", owner, slug);
+            list
+        },
+    };
+    let (added, removed) = sync_list_members(db, &list, &members)?;
+    println!("{}/{}: {} members added and {} members removed", list.owner, list.slug, added, removed);
 
-    Ok(user)
-}
+    let tweets = record_rate_limit(db, bearer_token.call(|auth| {
+        client.get_list_tweets(auth, &list.owner, &list.slug, None, None)
+    }))?;
 
-pub fn execute_add(args: &ArgMatches) -> Result<(), Error> {
-    let config = Config::load("default")?;
+    let mut insert_count = 0;
+    let mut newest_id = list.since_id;
+    for tweet in tweets {
+        newest_id = newest_id.max(Some(tweet.id as i64));
 
-    let db = Db::open(&config.database_file)?;
-    let client = TwitterClient::new();
+        if db.get_tweet(tweet.id as i64)?.is_some() {
+            continue;
+        }
 
-    let screen_name = args.value_of("screen_name").unwrap();
-    let user = retrieve_or_insert_user(&db, screen_name)?;
+        let author = retrieve_or_insert_author(db, &tweet)?;
+        insert_tweet(db, client, &author, &tweet, None)?;
+        insert_count += 1;
+    }
 
-    let access_token = client.get_access_token(&config.consumer_key, &config.consumer_secret)?;
-    let tweets = client.get_tweets(&access_token, &screen_name, None)?;
+    if newest_id != list.since_id {
+        db.update_list_since_id(list.id, newest_id.unwrap())?;
+    }
+
+    println!("imported {} tweets", insert_count);
+    Ok(())
+}
+
+fn add_user(db: &Db, client: &TwitterClient, source: &dyn Source, name: &str) -> Result<User, Error> {
+    let profile = record_rate_limit(db, source.resolve(name))?;
+    let user = retrieve_or_insert_user(db, source.kind(), &profile, true)?;
+    if !source.can_fetch(&user) {
+        println!("{}: tweets are protected", user.screen_name);
+        return Ok(user);
+    }
+
+    let page = record_rate_limit(db, source.fetch(&user, None, None))?;
 
     let mut insert_count = 0;
-    for tweet in tweets {
+    let mut newest_id = user.since_id;
+    for tweet in page.tweets {
+        if source.pages_by_since_id() {
+            newest_id = newest_id.max(Some(tweet.id as i64));
+        }
+
         let exists = {
             let tw = db.get_tweet(tweet.id as i64)?;
             tw.is_some()
@@ -40,17 +79,73 @@ pub fn execute_add(args: &ArgMatches) -> Result<(), Error> {
             continue;
         }
 
-        db.insert_tweet(&models::Tweet {
-            id: tweet.id as i64,
-            user_id: user.id,
-            user_name: tweet.user_name,
-            created_at: DateTime::parse_from_str(&tweet.created_at, "%a %b %e %T %z %Y")?.with_timezone(&Utc),
-            text: tweet.text,
-            retweets: if tweet.retweets { 1 } else { 0 },
-            raw_json: tweet.raw_json })?;
+        insert_tweet(db, client, &user, &tweet, None)?;
         insert_count += 1;
     }
 
+    if newest_id != user.since_id {
+        db.update_user_since_id(user.id, newest_id.unwrap())?;
+    }
+    if let Some(ref cursor) = page.cursor {
+        db.update_user_cursor(user.id, cursor)?;
+    }
+
     println!("imported {} tweets", insert_count);
+    Ok(user)
+}
+
+fn add_likes(db: &Db, twitter: &TwitterSource, user: &User) -> Result<(), Error> {
+    if !twitter.can_fetch(user) {
+        println!("{}: likes are protected", user.screen_name);
+        return Ok(());
+    }
+
+    let tweets = record_rate_limit(db, twitter.fetch_likes(user))?;
+
+    let mut insert_count = 0;
+    // likes are stored oldest first, so the most recent one is listed first.
+    for tweet in tweets.iter().rev() {
+        if insert_like(db, user, tweet)? {
+            insert_count += 1;
+        }
+    }
+    db.update_user_watch_likes(user.id, true)?;
+
+    println!("imported {} likes", insert_count);
+    Ok(())
+}
+
+pub fn execute_add(args: &ArgMatches) -> Result<(), Error> {
+    let config = Config::load("default")?;
+
+    let db = Db::open(&config.database_file)?;
+    let client = config.twitter_client()?;
+    let bearer_token = BearerToken::new(&config, &client);
+    let sources = Sources::new(&config, &client, &bearer_token);
+
+    if let Some(name) = args.value_of("list") {
+        return add_list(&db, &client, &bearer_token, name);
+    }
+
+    if let Some(url) = args.value_of("feed") {
+        add_user(&db, &client, sources.get(SourceKind::Feed), url)?;
+        return Ok(());
+    }
+
+    if let Some(name) = args.value_of("mastodon") {
+        add_user(&db, &client, sources.get(SourceKind::Mastodon), name)?;
+        return Ok(());
+    }
+
+    if let Some(handle) = args.value_of("bluesky") {
+        add_user(&db, &client, sources.get(SourceKind::Bluesky), handle)?;
+        return Ok(());
+    }
+
+    let screen_name = args.value_of("screen_name").unwrap();
+    let user = add_user(&db, &client, sources.get(SourceKind::Twitter), screen_name)?;
+    if args.is_present("likes") {
+        add_likes(&db, &sources.twitter, &user)?;
+    }
     Ok(())
 }
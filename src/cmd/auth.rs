@@ -0,0 +1,125 @@
+use std::cell::{Cell, RefCell};
+
+use clap::ArgMatches;
+use log::info;
+use reqwest::StatusCode;
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::twitter::{Auth, Credentials, TwitterClient};
+
+pub struct BearerToken<'a> {
+    config: &'a Config,
+    client: &'a TwitterClient,
+    access_token: RefCell<Option<String>>,
+    refreshed: Cell<bool>,
+    user_credentials: Option<Credentials>,
+}
+
+impl<'a> BearerToken<'a> {
+    pub fn new(config: &'a Config, client: &'a TwitterClient) -> BearerToken<'a> {
+        BearerToken {
+            config: config,
+            client: client,
+            access_token: RefCell::new(None),
+            refreshed: Cell::new(false),
+            user_credentials: config.user_credentials(),
+        }
+    }
+
+    fn get(&self) -> Result<String, Error> {
+        if let Some(access_token) = self.access_token.borrow().clone() {
+            return Ok(access_token);
+        }
+
+        match self.config.load_access_token("default")? {
+            Some(cache) => {
+                *self.access_token.borrow_mut() = Some(cache.access_token.clone());
+                Ok(cache.access_token)
+            },
+            None => self.refresh(),
+        }
+    }
+
+    fn refresh(&self) -> Result<String, Error> {
+        let access_token = self.client.get_access_token(&self.config.consumer_key, &self.config.consumer_secret)?;
+        self.config.save_access_token("default", &access_token)?;
+        *self.access_token.borrow_mut() = Some(access_token.clone());
+        self.refreshed.set(true);
+        Ok(access_token)
+    }
+
+    pub fn call<T, F>(&self, f: F) -> Result<T, Error>
+        where F: Fn(Auth) -> Result<T, Error>
+    {
+        let access_token = self.get()?;
+        match f(Auth::Bearer(&access_token)) {
+            Err(Error::HttpError(StatusCode::UNAUTHORIZED, _)) => {
+                info!("access token was rejected, refreshing it");
+                let access_token = self.refresh()?;
+                f(Auth::Bearer(&access_token))
+            },
+            result => result,
+        }
+    }
+
+    // an account the bearer token is rejected for may have been protected after it was added.
+    pub fn call_for<T, F>(&self, protected: bool, f: F) -> Result<T, Error>
+        where F: Fn(Auth) -> Result<T, Error>
+    {
+        let credentials = match self.user_credentials {
+            Some(ref credentials) => credentials,
+            None => return self.call(f),
+        };
+        if protected {
+            return f(Auth::User(credentials));
+        }
+
+        let access_token = self.get()?;
+        match f(Auth::Bearer(&access_token)) {
+            Err(Error::HttpError(StatusCode::UNAUTHORIZED, _)) => (),
+            result => return result,
+        }
+        // 401 is answered for a protected account as well, so the token is refreshed only once a run.
+        if !self.refreshed.get() {
+            info!("access token was rejected, refreshing it");
+            let access_token = self.refresh()?;
+            match f(Auth::Bearer(&access_token)) {
+                Err(Error::HttpError(StatusCode::UNAUTHORIZED, _)) => (),
+                result => return result,
+            }
+        }
+        info!("bearer token was rejected, retrying as the authorized user");
+        f(Auth::User(credentials))
+    }
+
+    pub fn has_user_credentials(&self) -> bool {
+        self.user_credentials.is_some()
+    }
+}
+
+pub fn execute_auth(args: &ArgMatches) -> Result<(), Error> {
+    let config = Config::load("default")?;
+
+    let opt_cache = config.load_access_token("default")?;
+    if opt_cache.is_none() {
+        println!("access token is not cached");
+        return Ok(());
+    }
+
+    let cache = opt_cache.unwrap();
+    if !args.is_present("revoke") {
+        println!("{}\t{}", cache.access_token, cache.created_at);
+        return Ok(());
+    }
+
+    let client = config.twitter_client()?;
+    if let Err(err) = client.invalidate_access_token(&config.consumer_key, &config.consumer_secret, &cache.access_token) {
+        // the token may be invalidated already, so the cache is removed anyway.
+        println!("failed to invalidate access token: {}", err);
+    }
+    config.remove_access_token("default")?;
+
+    println!("access token is revoked");
+    Ok(())
+}
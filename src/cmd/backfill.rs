@@ -0,0 +1,98 @@
+use std::cmp;
+
+use clap::ArgMatches;
+
+use crate::cmd::auth::BearerToken;
+use crate::cmd::import::{account, insert_tweet};
+use crate::cmd::record_rate_limit;
+use crate::db::Db;
+use crate::db::models::User;
+use crate::error::Error;
+use crate::config::Config;
+use crate::source::SourceKind;
+use crate::twitter::{self, TwitterClient};
+
+// backfill_max_id is set to this value once the timeline has been walked to its end.
+const BACKFILL_COMPLETED: i64 = 0;
+
+fn import_page(db: &Db, client: &TwitterClient, user: &User, tweets: &[twitter::Tweet], next_max_id: i64) -> Result<u32, Error> {
+    let mut insert_count = 0;
+    for tweet in tweets {
+        if db.get_tweet(tweet.id as i64)?.is_some() {
+            continue;
+        }
+
+        insert_tweet(db, client, user, tweet, None)?;
+        insert_count += 1;
+    }
+
+    db.update_user_backfill_max_id(user.id, next_max_id)?;
+    Ok(insert_count)
+}
+
+pub fn execute_backfill(args: &ArgMatches) -> Result<(), Error> {
+    let config = Config::load("default")?;
+    let db = Db::open(&config.database_file)?;
+    let client = config.twitter_client()?;
+    let bearer_token = BearerToken::new(&config, &client);
+
+    let screen_name = args.value_of("screen_name").unwrap();
+    let opt_user = db.get_user_by_screen_name(screen_name)?;
+    if opt_user.is_none() {
+        println!("specified user is not existed");
+        return Ok(());
+    }
+
+    let user = opt_user.unwrap();
+    // only the timeline of Twitter can be walked back by max_id.
+    if user.source_kind != SourceKind::Twitter {
+        println!("{}: backfill is not supported for {} accounts", user.screen_name, user.source_kind);
+        return Ok(());
+    }
+    let max_count: u32 = args.value_of("max_count")
+        .map(|s| s.parse().unwrap_or(twitter::MAX_TIMELINE_DEPTH))
+        .unwrap_or(twitter::MAX_TIMELINE_DEPTH);
+
+    // resume from the page where the previous backfill stopped, or start just below the oldest imported tweet.
+    let mut max_id = match user.backfill_max_id {
+        Some(id) => Some(id),
+        None => db.get_oldest_tweet_id_by_user_id(user.id)?.map(|id| id - 1),
+    };
+    if max_id == Some(BACKFILL_COMPLETED) {
+        println!("{}: already backfilled", user.screen_name);
+        return Ok(());
+    }
+
+    let mut fetch_count = 0;
+    let mut insert_count = 0;
+    while fetch_count < max_count {
+        let count = cmp::min(twitter::MAX_TIMELINE_COUNT, max_count - fetch_count);
+        let tweets = record_rate_limit(&db, bearer_token.call_for(user.protected, |auth| {
+            client.get_tweets(auth, account(&user), Some(count), None, max_id.map(|id| id as u64))
+        }))?;
+        if tweets.is_empty() {
+            db.update_user_backfill_max_id(user.id, BACKFILL_COMPLETED)?;
+            println!("{}: reached the end of timeline", user.screen_name);
+            break;
+        }
+
+        fetch_count += tweets.len() as u32;
+        let next_max_id = tweets.iter().map(|tweet| tweet.id).min().unwrap() as i64 - 1;
+
+        db.begin_transaction()?;
+        match import_page(&db, &client, &user, &tweets, next_max_id) {
+            Ok(count) => {
+                db.commit()?;
+                insert_count += count;
+            },
+            Err(err) => {
+                db.rollback()?;
+                return Err(err);
+            },
+        }
+        max_id = Some(next_max_id);
+    }
+
+    println!("{}: imported {} tweets", user.screen_name, insert_count);
+    Ok(())
+}
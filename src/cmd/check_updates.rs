@@ -1,21 +1,33 @@
 use std::io::Write;
+use std::path::PathBuf;
 use std::process::Command;
+use std::thread;
+use std::time;
 
 use chrono::{DateTime, Utc};
 use clap::ArgMatches;
+use log::error;
 use tempfile::NamedTempFile;
 
+use crate::cmd::auth::BearerToken;
+use crate::cmd::import::{insert_like, insert_referenced_tweet, insert_tweet, retrieve_or_insert_author, sync_list_members};
+use crate::cmd::record_rate_limit;
+use crate::cmd::source::{Sources, TwitterSource};
 use crate::db::Db;
-use crate::db::models::{User, Tweet};
+use crate::db::models::{List, Media, ReferencedTweet, Search, User, Tweet, Url};
 use crate::error::Error;
 use crate::config::Config;
-use crate::twitter::TwitterClient;
+use crate::source::{Source, SourceKind, TweetKind};
+use crate::twitter::{self, TwitterClient};
 
-fn send_notification_mail(config: &Config, user: &User, tweet: &Tweet) -> Result<(), Error> {
-    let url = format!("http://twitter.com/{}/status/{}", user.screen_name, tweet.id);
-    let subject = format!("【更新通知】{}", tweet.user_name);
-    let text = format!("{}\n\nURL: {}", tweet.text, url);
+// count of recently stored tweets compared with the fetched timeline to find deletions.
+const DELETION_CHECK_COUNT: i32 = 20;
 
+fn expand_urls(text: &str, urls: &[Url]) -> String {
+    urls.iter().fold(String::from(text), |text, url| text.replace(&url.url, &url.expanded_url))
+}
+
+fn send_mail(config: &Config, subject: &str, text: &str, attachments: &[PathBuf]) -> Result<(), Error> {
     let mut tmp_file = NamedTempFile::new()?;
     tmp_file.write_all(text.as_bytes())?;
 
@@ -26,10 +38,13 @@ fn send_notification_mail(config: &Config, user: &User, tweet: &Tweet) -> Result
         arg("send").
         arg(tmp_file_path).
         arg("--list-id").arg("twitter-notification.localhost").
-        arg("--subject").arg(subject.as_str());
+        arg("--subject").arg(subject);
     for to in &config.notification_tos {
         command.arg("--to").arg(to.as_str());
     }
+    for attachment in attachments {
+        command.arg("--attach").arg(attachment);
+    }
     let command_output = command.output()?;
     if !command_output.status.success() {
         let stdout = String::from_utf8_lossy(&command_output.stdout);
@@ -41,69 +56,445 @@ fn send_notification_mail(config: &Config, user: &User, tweet: &Tweet) -> Result
     Ok(())
 }
 
-fn check_updates(config: &Config, db: &Db, user: &User) -> Result<(), Error> {
-    let client = TwitterClient::new();
-    let access_token = client.get_access_token(&config.consumer_key, &config.consumer_secret)?;
-    let tweets = client.get_tweets(&access_token, &user.screen_name, None)?;
+fn format_references(tweet: &Tweet, referenced: &[ReferencedTweet]) -> String {
+    let format_referenced = |kind: TweetKind, id: Option<i64>| -> String {
+        let label = match kind {
+            TweetKind::Quote => "引用",
+            _ => "返信先",
+        };
+        match (id, referenced.iter().find(|r| Some(r.id) == id)) {
+            (_, Some(r)) => format!("\n\n{}: {} (@{}): {}", label, r.user_name, r.screen_name, r.text),
+            (Some(_), None) => match (kind, &tweet.in_reply_to_screen_name) {
+                // the tweet replied to may be deleted or protected.
+                (TweetKind::Reply, Some(screen_name)) => format!("\n\n{}: @{}", label, screen_name),
+                _ => String::new(),
+            },
+            (None, None) => String::new(),
+        }
+    };
+    format!("{}{}", format_referenced(TweetKind::Reply, tweet.in_reply_to_status_id), format_referenced(TweetKind::Quote, tweet.quoted_status_id))
+}
+
+fn send_notification_mail(config: &Config, user: &User, tweet: &Tweet, urls: &[Url], media: &[Media], referenced: &[ReferencedTweet]) -> Result<(), Error> {
+    let url = user.source_kind.item_url(&user.screen_name, tweet);
+    let subject = format!("【更新通知】{}", tweet.user_name);
+    let mut text = format!("{}{}\n\nURL: {}", expand_urls(&tweet.text, urls), format_references(tweet, referenced), url);
+    if let Some(ref search_query) = tweet.search_query {
+        text.push_str(&format!("\n検索: {}", search_query));
+    }
+
+    let mut attachments: Vec<PathBuf> = Vec::new();
+    if config.attach_media {
+        let media_dir = Config::media_dir()?;
+        attachments.extend(media.iter().map(|m| media_dir.join(&m.path)));
+    }
+
+    send_mail(config, &subject, &text, &attachments)
+}
+
+fn send_rename_mail(config: &Config, user: &User, old_screen_name: &str) -> Result<(), Error> {
+    let subject = format!("【名前変更通知】{}", user.name.as_deref().unwrap_or(&user.screen_name));
+    let text = format!("@{} → @{}\n\nURL: {}", old_screen_name, user.screen_name, user.source_kind.profile_url(&user.screen_name));
+    send_mail(config, &subject, &text, &[])
+}
+
+fn send_like_mail(config: &Config, user: &User, liked: &ReferencedTweet) -> Result<(), Error> {
+    let name = user.name.as_deref().unwrap_or(&user.screen_name);
+    let url = format!("{}/status/{}", SourceKind::Twitter.profile_url(&liked.screen_name), liked.id);
+    let subject = format!("【いいね通知】{}", name);
+    let text = format!("{} (@{}) がいいねしました\n\n{} (@{}): {}\n\nURL: {}", name, user.screen_name, liked.user_name, liked.screen_name, liked.text, url);
+    send_mail(config, &subject, &text, &[])
+}
+
+fn send_deletion_mail(config: &Config, user: &User, tweet: &Tweet) -> Result<(), Error> {
+    let url = user.source_kind.item_url(&user.screen_name, tweet);
+    let subject = format!("【削除通知】{}", tweet.user_name);
+    let text = format!("{}\n\nURL: {}", tweet.text, url);
+    send_mail(config, &subject, &text, &[])
+}
+
+fn resolve_account_id(db: &Db, source: &dyn Source, user: &User) -> Result<User, Error> {
+    if user.account_id.is_none() {
+        let profile = source.resolve(&user.screen_name)?;
+        db.update_user_profile(user.id, &profile)?;
+    }
+
+    let opt_user = db.get_user_by_row_id(user.id as i64)?;
+    opt_user.ok_or_else(|| Error::ModelError("user is not found"))
+}
+
+fn track_rename(config: &Config, db: &Db, user: User, tweets: &[twitter::Tweet]) -> Result<User, Error> {
+    let screen_name = match tweets.iter().max_by_key(|tweet| tweet.created_at) {
+        Some(tweet) if !tweet.screen_name.is_empty() && tweet.screen_name != user.screen_name => tweet.screen_name.clone(),
+        _ => return Ok(user),
+    };
+
+    db.rename_user(user.id, &user.screen_name, &screen_name)?;
+    println!("{}: renamed to {}", user.screen_name, screen_name);
+
+    let old_screen_name = user.screen_name;
+    let user = User { screen_name: screen_name, ..user };
+    if config.notify_renames {
+        send_rename_mail(config, &user, &old_screen_name)?;
+    }
+    Ok(user)
+}
+
+fn track_deletions(config: &Config, db: &Db, source: &dyn Source, user: &User, recent_ids: &[i64], tweets: &[twitter::Tweet]) -> Result<(), Error> {
+    // tweets older than a full page may be just out of the page.
+    let oldest_id = match tweets.iter().map(|tweet| tweet.id).min() {
+        Some(id) if tweets.len() >= twitter::MAX_TIMELINE_COUNT as usize => id,
+        _ => 0,
+    };
+    let candidates: Vec<u64> = recent_ids.iter()
+        .map(|id| *id as u64)
+        .filter(|id| *id >= oldest_id && !tweets.iter().any(|tweet| tweet.id == *id))
+        .collect();
+    if candidates.is_empty() {
+        return Ok(());
+    }
+
+    let existing = match source.lookup(Some(user), &candidates)? {
+        Some(existing) => existing,
+        None => return Ok(()),
+    };
+    let deleted_at = Utc::now();
+    let mut delete_count = 0;
+    let mut unavailable_count = 0;
+    for id in candidates.iter().filter(|id| !existing.iter().any(|tweet| tweet.id == **id)) {
+        // lookup also leaves out tweets of protected or suspended accounts.
+        if !source.is_deleted(user, *id)? {
+            unavailable_count += 1;
+            continue;
+        }
+        db.update_tweet_deleted_at(*id as i64, &deleted_at)?;
+        delete_count += 1;
+        if config.notify_deletions {
+            if let Some(tw) = db.get_tweet(*id as i64)? {
+                send_deletion_mail(config, user, &tw)?;
+            }
+        }
+    }
+
+    if delete_count > 0 {
+        println!("{}: {} tweets were deleted", user.screen_name, delete_count);
+    }
+    if unavailable_count > 0 {
+        println!("{}: {} tweets were unavailable", user.screen_name, unavailable_count);
+    }
+    Ok(())
+}
+
+fn fetch_replied_tweets(db: &Db, source: &dyn Source, tweets: &[twitter::Tweet]) -> Result<(), Error> {
+    let mut ids: Vec<u64> = Vec::new();
+    for tweet in tweets {
+        let id = tweet.references.in_reply_to_status_id;
+        if id == 0 || ids.contains(&id) || tweet.referenced_tweets.iter().any(|r| r.id == id) {
+            continue;
+        }
+        if db.get_tweet(tweet.id as i64)?.is_none() && db.get_referenced_tweet(id as i64)?.is_none() {
+            ids.push(id);
+        }
+    }
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    let replied_tweets = source.lookup(None, &ids)?.unwrap_or_default();
+    for replied in replied_tweets {
+        insert_referenced_tweet(db, &replied)?;
+    }
+    Ok(())
+}
+
+// returns None when the tweet was already imported, e.g. through a list.
+fn import_and_notify(config: &Config, db: &Db, client: &TwitterClient, user: &User, tweet: &twitter::Tweet, search_query: Option<&str>) -> Result<Option<bool>, Error> {
+    if db.get_tweet(tweet.id as i64)?.is_some() {
+        return Ok(None);
+    }
+
+    let tw = insert_tweet(db, client, user, tweet, search_query)?;
+    if !config.notify_kinds.contains(&tw.kind) {
+        return Ok(Some(false));
+    }
+    // the retweeted tweet was notified when it was imported.
+    if tw.kind == TweetKind::Retweet && db.get_tweet(tweet.retweeted_status_id as i64)?.is_some() {
+        return Ok(Some(false));
+    }
+
+    let urls = db.get_urls_by_tweet_id(tw.id)?;
+    let media = db.get_media_by_tweet_id(tw.id)?;
+    let referenced = db.get_referenced_tweets_by_tweet_id(tw.id)?;
+    send_notification_mail(config, user, &tw, &urls, &media, &referenced)?;
+    Ok(Some(true))
+}
+
+fn check_updates(config: &Config, db: &Db, client: &TwitterClient, source: &dyn Source, user: &User) -> Result<(), Error> {
+    let user = resolve_account_id(db, source, user)?;
+    let paged = source.pages_by_since_id();
+    let recent_ids = if config.detect_deletions && paged {
+        db.get_recent_tweet_ids_by_user_id(user.id, DELETION_CHECK_COUNT)?
+    } else {
+        Vec::new()
+    };
+    // the timeline is fetched from before the recent tweets to find the deleted ones.
+    let since_id = match (user.since_id, recent_ids.iter().min()) {
+        _ if !paged => None,
+        (Some(since_id), Some(oldest_id)) => Some(since_id.min(oldest_id - 1)),
+        (since_id, _) => since_id,
+    };
+    let page = source.fetch(&user, since_id, Some(twitter::MAX_TIMELINE_COUNT))?;
+    let tweets = page.tweets;
+    let user = &track_rename(config, db, user, &tweets)?;
+    fetch_replied_tweets(db, source, &tweets)?;
+    track_deletions(config, db, source, user, &recent_ids, &tweets)?;
 
     let mut insert_count = 0;
     let mut notify_count = 0;
+    let mut newest_id = user.since_id;
     for tweet in tweets {
-        let exists = {
-            let tw = db.get_tweet(tweet.id as i64)?;
-            tw.is_some()
-        };
-        if exists {
-            continue;
+        if paged {
+            newest_id = newest_id.max(Some(tweet.id as i64));
         }
 
-        let tw = db.insert_tweet(&Tweet {
-            id: tweet.id as i64,
-            user_id: user.id,
-            user_name: tweet.user_name,
-            created_at: DateTime::parse_from_str(&tweet.created_at, "%a %b %e %T %z %Y")?.with_timezone(&Utc),
-            text: tweet.text,
-            retweets: if tweet.retweets { 1 } else { 0 },
-            raw_json: tweet.raw_json })?;
-        insert_count += 1;
+        match import_and_notify(config, db, client, user, &tweet, None)? {
+            Some(true) => { insert_count += 1; notify_count += 1; },
+            Some(false) => insert_count += 1,
+            None => (),
+        }
+    }
 
-        let exists2 = {
-            let tw = db.get_tweet(tweet.retweeted_status_id as i64)?;
-            tw.is_some()
-        };
-        if exists2 {
+    if newest_id != user.since_id {
+        db.update_user_since_id(user.id, newest_id.unwrap())?;
+    }
+    if let Some(ref cursor) = page.cursor {
+        db.update_user_cursor(user.id, cursor)?;
+    }
+    db.update_user_checked_at(user.id, &Utc::now())?;
+
+    println!("{}: imported {} tweets and send {} mails", user.screen_name, insert_count, notify_count);
+    Ok(())
+}
+
+fn check_likes(config: &Config, db: &Db, twitter: &TwitterSource, user: &User) -> Result<(), Error> {
+    let tweets = twitter.fetch_likes(user)?;
+
+    let mut insert_count = 0;
+    let mut notify_count = 0;
+    // likes are stored oldest first, so the most recent one is listed first.
+    for tweet in tweets.iter().rev() {
+        if !insert_like(db, user, tweet)? {
             continue;
         }
+        insert_count += 1;
+        if let Some(liked) = db.get_referenced_tweet(tweet.id as i64)? {
+            send_like_mail(config, user, &liked)?;
+            notify_count += 1;
+        }
+    }
 
-        send_notification_mail(config, &user, &tw)?;
-        notify_count += 1;
+    println!("{}: imported {} likes and send {} mails", user.screen_name, insert_count, notify_count);
+    Ok(())
+}
+
+fn check_list_updates(config: &Config, db: &Db, twitter: &TwitterSource, list: &List) -> Result<(), Error> {
+    let (client, bearer_token) = (twitter.client, twitter.bearer_token);
+    let members = bearer_token.call(|auth| client.get_list_members(auth, &list.owner, &list.slug))?;
+    let (added, removed) = sync_list_members(db, list, &members)?;
+    if added > 0 || removed > 0 {
+        println!("{}/{}: {} members added and {} members removed", list.owner, list.slug, added, removed);
     }
 
-    println!("{}: imported {} tweets and send {} mails", user.screen_name, insert_count, notify_count);
+    let since_id = list.since_id.map(|id| id as u64);
+    let tweets = bearer_token.call(|auth| {
+        client.get_list_tweets(auth, &list.owner, &list.slug, Some(twitter::MAX_TIMELINE_COUNT), since_id)
+    })?;
+    fetch_replied_tweets(db, twitter, &tweets)?;
+
+    let mut insert_count = 0;
+    let mut notify_count = 0;
+    let mut newest_id = list.since_id;
+    for tweet in tweets {
+        newest_id = newest_id.max(Some(tweet.id as i64));
+
+        // tweets are attributed to their authors, not to the list.
+        let author = retrieve_or_insert_author(db, &tweet)?;
+        match import_and_notify(config, db, client, &author, &tweet, None)? {
+            Some(true) => { insert_count += 1; notify_count += 1; },
+            Some(false) => insert_count += 1,
+            None => (),
+        }
+    }
+
+    if newest_id != list.since_id {
+        db.update_list_since_id(list.id, newest_id.unwrap())?;
+    }
+    db.update_list_checked_at(list.id, &Utc::now())?;
+
+    println!("{}/{}: imported {} tweets and send {} mails", list.owner, list.slug, insert_count, notify_count);
+    Ok(())
+}
+
+fn check_search_updates(config: &Config, db: &Db, twitter: &TwitterSource, search: &Search) -> Result<(), Error> {
+    let (client, bearer_token) = (twitter.client, twitter.bearer_token);
+    let since_id = search.since_id.map(|id| id as u64);
+    let tweets = bearer_token.call(|auth| {
+        client.search_tweets(auth, &search.query, Some(twitter::MAX_SEARCH_COUNT), since_id)
+    })?;
+    fetch_replied_tweets(db, twitter, &tweets)?;
+
+    let mut insert_count = 0;
+    let mut notify_count = 0;
+    let mut newest_id = search.since_id;
+    for tweet in tweets {
+        newest_id = newest_id.max(Some(tweet.id as i64));
+
+        let author = retrieve_or_insert_author(db, &tweet)?;
+        match import_and_notify(config, db, client, &author, &tweet, Some(&search.query))? {
+            Some(true) => { insert_count += 1; notify_count += 1; },
+            Some(false) => insert_count += 1,
+            None => (),
+        }
+    }
+
+    if newest_id != search.since_id {
+        db.update_search_since_id(search.id, newest_id.unwrap())?;
+    }
+    db.update_search_checked_at(search.id, &Utc::now())?;
+
+    println!("{}: imported {} tweets and send {} mails", search.query, insert_count, notify_count);
     Ok(())
 }
 
+enum Target {
+    User(User),
+    Likes(User),
+    List(List),
+    Search(Search),
+}
+
+impl Target {
+    fn endpoint(&self, sources: &Sources) -> Option<&'static str> {
+        match *self {
+            Target::User(ref user) => sources.get(user.source_kind).endpoint(),
+            Target::Likes(_) => Some(twitter::FAVORITES_LIST_PATH),
+            Target::List(_) => Some(twitter::LIST_STATUSES_PATH),
+            Target::Search(_) => Some(sources.twitter.client.search_endpoint()),
+        }
+    }
+
+    fn name(&self) -> String {
+        match *self {
+            Target::User(ref user) | Target::Likes(ref user) => user.screen_name.clone(),
+            Target::List(ref list) => format!("{}/{}", list.owner, list.slug),
+            Target::Search(ref search) => search.query.clone(),
+        }
+    }
+}
+
+fn user_targets(user: User) -> Vec<Target> {
+    if user.watch_likes {
+        vec![Target::User(user.clone()), Target::Likes(user)]
+    } else {
+        vec![Target::User(user)]
+    }
+}
+
+fn check_target(config: &Config, db: &Db, sources: &Sources, target: &Target) -> Result<(), Error> {
+    match *target {
+        Target::User(ref user) => check_updates(config, db, sources.twitter.client, sources.get(user.source_kind), user),
+        Target::Likes(ref user) => check_likes(config, db, &sources.twitter, user),
+        Target::List(ref list) => check_list_updates(config, db, &sources.twitter, list),
+        Target::Search(ref search) => check_search_updates(config, db, &sources.twitter, search),
+    }
+}
+
+fn deferred(targets: &[Target]) -> String {
+    let count = |f: fn(&Target) -> bool| targets.iter().filter(|target| f(target)).count();
+    let likes = count(|target| matches!(target, Target::Likes(_)));
+    let lists = count(|target| matches!(target, Target::List(_)));
+    let searches = count(|target| matches!(target, Target::Search(_)));
+
+    let mut parts = vec![format!("{} users", targets.len() - likes - lists - searches)];
+    if likes > 0 {
+        parts.push(format!("{} likes", likes));
+    }
+    if lists > 0 {
+        parts.push(format!("{} lists", lists));
+    }
+    if searches > 0 {
+        parts.push(format!("{} searches", searches));
+    }
+    match parts.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+        _ => parts.join(""),
+    }
+}
+
+fn wait_for_reset(reset_at: &DateTime<Utc>) {
+    println!("rate limit exceeded, waiting until {}", reset_at);
+    let duration = (*reset_at - Utc::now()).to_std().unwrap_or(time::Duration::from_secs(0));
+    thread::sleep(duration + time::Duration::from_secs(1));
+}
+
 pub fn execute_check_updates(args: &ArgMatches) -> Result<(), Error> {
     let config = Config::load("default")?;
     let db = Db::open(&config.database_file)?;
+    let client = config.twitter_client()?;
+    let bearer_token = BearerToken::new(&config, &client);
+    let sources = Sources::new(&config, &client, &bearer_token);
+    let wait = args.is_present("wait");
 
-    if let Some(screen_name) = args.value_of("screen_name") {
-        let opt_users = db.get_user_by_screen_name(screen_name)?;
-        if opt_users.is_some() {
-            check_updates(&config, &db, &opt_users.unwrap())?;
-        }
+    // users and lists which were deferred by the rate limit in the previous run come first.
+    let targets: Vec<Target> = if let Some(screen_name) = args.value_of("screen_name") {
+        db.get_user_by_screen_name(screen_name)?.into_iter().flat_map(user_targets).collect()
     } else {
-        let users = db.get_all_users()?;
-        for user in users {
-            check_updates(&config, &db, &user)?;
+        let users = db.get_users_order_by_checked_at()?.into_iter().flat_map(user_targets);
+        let lists = db.get_lists_order_by_checked_at()?.into_iter().map(Target::List);
+        let searches = db.get_searches_order_by_checked_at()?.into_iter().map(Target::Search);
+        users.chain(lists).chain(searches).collect()
+    };
+
+    let mut failed: Vec<String> = Vec::new();
+    let mut index = 0;
+    while index < targets.len() {
+        let reset_at = match targets[index].endpoint(&sources).and_then(|endpoint| client.rate_limit(endpoint)) {
+            Some(ref rate_limit) if rate_limit.is_exhausted() => Some(rate_limit.reset_at),
+            _ => match record_rate_limit(&db, check_target(&config, &db, &sources, &targets[index])) {
+                Ok(_) => {
+                    index += 1;
+                    None
+                },
+                Err(Error::RateLimitError(_, reset_at)) => Some(reset_at),
+                Err(err) => {
+                    // a failing target does not keep the others from being checked.
+                    error!("{}: {}", targets[index].name(), err);
+                    failed.push(targets[index].name());
+                    index += 1;
+                    None
+                },
+            },
+        };
+
+        if let Some(reset_at) = reset_at {
+            if !wait {
+                println!("rate limit exceeded, {} are deferred to next run (limit resets at {})", deferred(&targets[index..]), reset_at);
+                break;
+            }
+            wait_for_reset(&reset_at);
         }
     }
+
+    if !failed.is_empty() {
+        return Err(Error::CommandError(format!("failed to check {}", failed.join(", "))));
+    }
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::source::SourceKind;
 
     #[test]
     fn test_send_notification_mail() {
@@ -112,6 +503,17 @@ mod tests {
             id: 0,
             screen_name: String::from("vortis_pr"),
             created_at: Utc::now(),
+            since_id: None,
+            backfill_max_id: None,
+            checked_at: None,
+            account_id: None,
+            name: None,
+            protected: false,
+            verified: false,
+            watched: true,
+            source_kind: SourceKind::Twitter,
+            cursor: None,
+            watch_likes: false,
         };
         let tweet = Tweet {
             id: 0,
@@ -119,10 +521,15 @@ mod tests {
             user_name: String::from("ヴォルティススタジアム"),
             created_at: Utc::now(),
             text: String::from("テスト"),
-            retweets: 0,
             raw_json: String::from(""),
+            search_query: None,
+            deleted_at: None,
+            quoted_status_id: None,
+            in_reply_to_status_id: None,
+            in_reply_to_screen_name: None,
+            kind: TweetKind::Original,
         };
-        let result = send_notification_mail(&config, &user, &tweet);
+        let result = send_notification_mail(&config, &user, &tweet, &[], &[], &[]);
         println!("{:?}", result);
         assert_eq!(true, result.is_ok());
     }
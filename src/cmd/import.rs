@@ -0,0 +1,188 @@
+use std::fs;
+
+use chrono::Utc;
+use log::warn;
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::db::Db;
+use crate::db::models::{self, List, User};
+use crate::error::Error;
+use crate::source::SourceKind;
+use crate::twitter::{self, TwitterClient};
+
+pub fn account(user: &User) -> twitter::Account<'_> {
+    match user.account_id {
+        Some(id) => twitter::Account::Id(id as u64),
+        None => twitter::Account::ScreenName(&user.screen_name),
+    }
+}
+
+pub fn retrieve_or_insert_user(db: &Db, source_kind: SourceKind, profile: &twitter::User, watched: bool) -> Result<User, Error> {
+    let opt_user = match db.get_user_by_account_id(source_kind, profile.id as i64)? {
+        Some(user) => Some(user),
+        None => db.get_user_by_source_kind_and_screen_name(source_kind, &profile.screen_name)?,
+    };
+    let user = match opt_user {
+        Some(user) => {
+            if !user.screen_name.eq_ignore_ascii_case(&profile.screen_name) {
+                db.rename_user(user.id, &user.screen_name, &profile.screen_name)?;
+                println!("{}: renamed to {}", user.screen_name, profile.screen_name);
+            }
+            if watched && !user.watched {
+                db.update_user_watched(user.id, true)?;
+                println!("{}: added", profile.screen_name);
+            }
+            user
+        },
+        None => {
+            let user = db.insert_user(&profile.screen_name, source_kind)?;
+            db.update_user_watched(user.id, watched)?;
+            if watched {
+                println!("{}: added", profile.screen_name);
+            }
+            user
+        },
+    };
+
+    db.update_user_profile(user.id, profile)?;
+    let opt_user = db.get_user_by_row_id(user.id as i64)?;
+    opt_user.ok_or_else(|| Error::ModelError("update user error"))
+}
+
+pub fn retrieve_or_insert_author(db: &Db, tweet: &twitter::Tweet) -> Result<User, Error> {
+    if let Some(user) = db.get_user_by_account_id(SourceKind::Twitter, tweet.user_id as i64)? {
+        return Ok(user);
+    }
+
+    retrieve_or_insert_user(db, SourceKind::Twitter, &twitter::User {
+        id: tweet.user_id,
+        screen_name: tweet.screen_name.clone(),
+        name: tweet.user_name.clone(),
+        protected: false,
+        verified: false }, false)
+}
+
+pub fn sync_list_members(db: &Db, list: &List, members: &[twitter::User]) -> Result<(usize, usize), Error> {
+    let stored_members = db.get_list_members(list.id)?;
+
+    let mut added = 0;
+    for member in members {
+        let user = retrieve_or_insert_user(db, SourceKind::Twitter, member, false)?;
+        if !stored_members.iter().any(|stored| stored.id == user.id) {
+            db.insert_list_member(list.id, user.id)?;
+            added += 1;
+        }
+    }
+
+    let mut removed = 0;
+    for stored in &stored_members {
+        if !members.iter().any(|member| stored.account_id == Some(member.id as i64)) {
+            db.delete_list_member(list.id, stored.id)?;
+            removed += 1;
+        }
+    }
+
+    Ok((added, removed))
+}
+
+pub fn parse_list_name(name: &str) -> Result<(&str, &str), Error> {
+    match name.split_once('/') {
+        Some((owner, slug)) if !owner.is_empty() && !slug.is_empty() => Ok((owner.trim_start_matches('@'), slug)),
+        _ => Err(Error::CommandError(format!("list must be given as owner/slug: {}", name))),
+    }
+}
+
+fn media_extension(url: &str) -> &str {
+    let path = url.split(&['?', '#'][..]).next().unwrap_or("");
+    let file_name = path.rsplit('/').next().unwrap_or("");
+    match file_name.rfind('.') {
+        Some(index) => &file_name[index + 1..],
+        None => "bin",
+    }
+}
+
+fn download_media(db: &Db, client: &TwitterClient, tweet_id: i64, media: &twitter::Media) -> Result<(), Error> {
+    let (body, content_type) = client.download(&media.url)?;
+    let sha256: String = Sha256::digest(&body).iter().map(|b| format!("{:02x}", b)).collect();
+
+    let path = format!("{}/{}.{}", &sha256[..2], sha256, media_extension(&media.url));
+    let file_path = Config::media_dir()?.join(&path);
+    if !file_path.exists() {
+        fs::create_dir_all(file_path.parent().unwrap())?;
+        fs::write(&file_path, &body)?;
+    }
+
+    db.insert_media(&models::Media {
+        tweet_id: tweet_id,
+        media_key: media.media_key.clone(),
+        media_type: media.media_type.clone(),
+        url: media.url.clone(),
+        path: path,
+        sha256: sha256,
+        content_type: content_type,
+        created_at: Utc::now() })
+}
+
+pub fn insert_referenced_tweet(db: &Db, tweet: &twitter::Tweet) -> Result<(), Error> {
+    db.insert_referenced_tweet(&models::ReferencedTweet {
+        id: tweet.id as i64,
+        screen_name: tweet.screen_name.clone(),
+        user_name: tweet.user_name.clone(),
+        created_at: tweet.created_at,
+        text: tweet.text.clone(),
+        raw_json: tweet.raw_json.clone() })
+}
+
+// returns false when the like is already stored.
+pub fn insert_like(db: &Db, user: &User, tweet: &twitter::Tweet) -> Result<bool, Error> {
+    if db.get_like(user.id, tweet.id as i64)?.is_some() {
+        return Ok(false);
+    }
+    insert_referenced_tweet(db, tweet)?;
+    db.insert_like(user.id, tweet.id as i64)?;
+    Ok(true)
+}
+
+pub fn insert_tweet(db: &Db, client: &TwitterClient, user: &User, tweet: &twitter::Tweet, search_query: Option<&str>) -> Result<models::Tweet, Error> {
+    let id_or_none = |id: u64| if id == 0 { None } else { Some(id as i64) };
+    let references = &tweet.references;
+    let tw = db.insert_tweet(&models::Tweet {
+        id: tweet.id as i64,
+        user_id: user.id,
+        user_name: tweet.user_name.clone(),
+        created_at: tweet.created_at,
+        text: tweet.text.clone(),
+        raw_json: tweet.raw_json.clone(),
+        search_query: search_query.map(String::from),
+        deleted_at: None,
+        quoted_status_id: id_or_none(references.quoted_status_id),
+        in_reply_to_status_id: id_or_none(references.in_reply_to_status_id),
+        in_reply_to_screen_name: Some(references.in_reply_to_screen_name.clone()).filter(|name| !name.is_empty()),
+        kind: tweet.kind })?;
+    db.insert_entities(tw.id, &tweet.entities)?;
+    for referenced in &tweet.referenced_tweets {
+        insert_referenced_tweet(db, referenced)?;
+    }
+
+    // a media which can't be downloaded doesn't prevent the tweet from being imported.
+    for media in &tweet.media {
+        if let Err(err) = download_media(db, client, tw.id, media) {
+            warn!("failed to download {}: {}", media.url, err);
+        }
+    }
+
+    Ok(tw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_media_extension() {
+        assert_eq!("jpg", media_extension("https://pbs.twimg.com/media/EXb3tIaU0AAuAe8.jpg"));
+        assert_eq!("mp4", media_extension("https://video.twimg.com/ext_tw_video/1/pu/vid/1280x720/a.mp4?tag=10"));
+        assert_eq!("bin", media_extension("https://example.com/media/abc"));
+    }
+}
@@ -2,8 +2,11 @@ use std::io::{self,BufRead,Write};
 
 use clap::ArgMatches;
 
+use crate::bluesky;
 use crate::error::Error;
 use crate::config::Config;
+use crate::source::TweetKind;
+use crate::twitter::{self, ApiVersion, HttpSettings, RetryPolicy, UserToken};
 
 fn prompt(label: &str) -> Result<(), Error> {
     print!("put your {}: ", label);
@@ -27,6 +30,17 @@ fn read_from_stdin(label: &str) -> Result<String, Error> {
     }
 }
 
+fn authorize_user(config: &Config) -> Result<UserToken, Error> {
+    let client = config.twitter_client()?;
+    let (request_token, authorize_url) = client.get_request_token(&config.consumer_key, &config.consumer_secret)?;
+    println!("open {} and authorize the app", authorize_url);
+    let pin = read_from_stdin("PIN")?;
+
+    let user_token = client.get_user_token(&request_token, pin.trim())?;
+    println!("authorized as {}", user_token.screen_name);
+    Ok(user_token)
+}
+
 pub fn execute_init(args: &ArgMatches) -> Result<(), Error> {
     let consumer_key = String::from(args.value_of("consumer_key").unwrap_or_else(|| panic!("specify consumer key")));
     let consumer_secret = match args.value_of("consumer_secret") {
@@ -41,18 +55,37 @@ pub fn execute_init(args: &ArgMatches) -> Result<(), Error> {
         Some(database_file) => String::from(database_file),
         _ => Config::default_database_file()?,
     };
+    let api_version = match args.value_of("api_version") {
+        Some(api_version) => api_version.parse()?,
+        _ => ApiVersion::V1_1,
+    };
     let notification_from_email = read_from_stdin("From Email Address")?;
     let notification_tos = read_from_stdin("To Email Addresses(comma separated)")?;
     let v2: Vec<String> = notification_tos.split(",").map(|item| item.trim()).map(String::from).collect();
 
-    let config = Config {
+    let mut config = Config {
         consumer_key: consumer_key,
         consumer_secret: consumer_secret,
         gmail_command: gmail_command,
         database_file: database_file,
         notification_from_email: notification_from_email,
         notification_tos: v2,
+        api_base_url: String::from(args.value_of("api_base_url").unwrap_or(twitter::DEFAULT_API_BASE_URL)),
+        api_version: api_version,
+        attach_media: false,
+        notify_renames: false,
+        detect_deletions: false,
+        notify_deletions: false,
+        notify_kinds: TweetKind::ALL.to_vec(),
+        retry_policy: RetryPolicy::default(),
+        http: HttpSettings::default(),
+        user_token: None,
+        mastodon_scheme: String::from("https"),
+        bluesky_api_base_url: String::from(bluesky::DEFAULT_API_BASE_URL),
     };
+    if args.is_present("user_auth") {
+        config.user_token = Some(authorize_user(&config)?);
+    }
     config.save("default")?;
 
     Ok(())
@@ -3,25 +3,99 @@ use clap::ArgMatches;
 use crate::config::Config;
 use crate::error::Error;
 use crate::db::Db;
+use crate::source::{SourceKind, TweetKind};
 
 pub fn execute_list(args: &ArgMatches) -> Result<(), Error> {
     let config = Config::load("default")?;
     let db = Db::open(&config.database_file)?;
 
-    if let Some(screen_name) = args.value_of("screen_name") {
+    if let Some(query) = args.value_of("search") {
+        // list tweets found by the search
+        let max_count: i32 = args.value_of("max_count").map(|s| s.parse().unwrap_or(20)).unwrap_or(20);
+        for tweet in db.get_tweets_by_search_query(query, max_count)? {
+            println!("{}\t{}\t{}\t{}", tweet.id, tweet.created_at, tweet.user_name, tweet.text);
+        }
+    } else if let Some(screen_name) = args.value_of("screen_name") {
         // list tweets
         if let Some(ref user) = db.get_user_by_screen_name(screen_name)? {
+            if args.is_present("renames") {
+                for change in db.get_screen_name_changes_by_user_id(user.id)? {
+                    println!("{}\t{}\t{}", change.changed_at, change.old_screen_name, change.new_screen_name);
+                }
+                return Ok(());
+            }
+
             let max_count: i32 = args.value_of("max_count").map(|s| s.parse().unwrap_or(20)).unwrap_or(20);
-            let tweets = db.get_tweets_by_user_id(user.id, max_count)?;
+            if args.is_present("likes") {
+                // liked tweets are listed with their authors, not as tweets of the user.
+                for like in db.get_likes_by_user_id(user.id, max_count)? {
+                    if let Some(liked) = db.get_referenced_tweet(like.tweet_id)? {
+                        println!("{}\t{}\t@{}: {}", liked.id, like.created_at, liked.screen_name, liked.text);
+                    }
+                }
+                return Ok(());
+            }
+            if args.is_present("deleted") {
+                for tweet in db.get_deleted_tweets_by_user_id(user.id, max_count)? {
+                    println!("{}\t{}\t{}\t{}", tweet.id, tweet.created_at, tweet.text, tweet.deleted_at.unwrap());
+                }
+                return Ok(());
+            }
+
+            let tweets = match args.value_of("kind") {
+                Some(kinds) => {
+                    let kinds = kinds.split(',').map(|kind| kind.trim().parse()).collect::<Result<Vec<TweetKind>, Error>>()?;
+                    db.get_tweets_by_user_id_and_kinds(user.id, &kinds, max_count)?
+                },
+                None => db.get_tweets_by_user_id(user.id, max_count)?,
+            };
+            let media_dir = Config::media_dir()?;
             for tweet in tweets {
                 println!("{}\t{}\t{}", tweet.id, tweet.created_at, tweet.text);
+                let referenced = db.get_referenced_tweets_by_tweet_id(tweet.id)?;
+                if let Some(id) = tweet.in_reply_to_status_id {
+                    match referenced.iter().find(|r| r.id == id) {
+                        Some(r) => println!("\treply to @{}: {}", r.screen_name, r.text),
+                        None => println!("\treply to @{}", tweet.in_reply_to_screen_name.as_deref().unwrap_or("")),
+                    }
+                }
+                if let Some(r) = referenced.iter().find(|r| Some(r.id) == tweet.quoted_status_id) {
+                    println!("\tquote of @{}: {}", r.screen_name, r.text);
+                }
+                for media in db.get_media_by_tweet_id(tweet.id)? {
+                    println!("\t{}", media_dir.join(&media.path).display());
+                }
             }
         }
     } else {
         // list users
-        let users: Vec<_> = db.get_all_users()?;
-        for user in users {
-            println!("{}", user.screen_name);
+        // members of lists and authors of search results are listed with the lists and searches.
+        for user in db.get_all_users()?.into_iter().filter(|user| user.watched) {
+            let source_kind = user.source_kind.to_string();
+            let mut flags: Vec<&str> = Vec::new();
+            if user.source_kind != SourceKind::Twitter {
+                flags.push(&source_kind);
+            }
+            if user.protected {
+                flags.push("protected");
+            }
+            if user.verified {
+                flags.push("verified");
+            }
+            if user.watch_likes {
+                flags.push("likes");
+            }
+            println!("{}\t{}\t{}", user.screen_name, user.name.as_deref().unwrap_or(""), flags.join(","));
+        }
+
+        // list lists
+        for list in db.get_lists_order_by_checked_at()? {
+            println!("{}/{}\t{} members", list.owner, list.slug, db.get_list_members(list.id)?.len());
+        }
+
+        // list searches
+        for search in db.get_searches_order_by_checked_at()? {
+            println!("{}\tsearch", search.query);
         }
     }
 
@@ -1,5 +1,6 @@
 use clap::ArgMatches;
 
+use crate::cmd::import::parse_list_name;
 use crate::db::Db;
 use crate::error::Error;
 use crate::config::Config;
@@ -8,6 +9,18 @@ pub fn execute_remove(args: &ArgMatches) -> Result<(), Error> {
     let config = Config::load("default")?;
     let db = Db::open(&config.database_file)?;
 
+    if let Some(name) = args.value_of("list") {
+        let (owner, slug) = parse_list_name(name)?;
+        match db.get_list_by_name(owner, slug)? {
+            Some(list) => {
+                db.delete_list(list.id)?;
+                println!("list is removed");
+            },
+            None => println!("specified list is not existed"),
+        }
+        return Ok(());
+    }
+
     let screen_name = args.value_of("screen_name").unwrap();
     let opt_user = db.get_user_by_screen_name(screen_name)?;
     if opt_user.is_none() {
@@ -16,8 +29,22 @@ pub fn execute_remove(args: &ArgMatches) -> Result<(), Error> {
     }
 
     let user = opt_user.unwrap();
+    if args.is_present("likes") {
+        db.begin_transaction()?;
+        db.update_user_watch_likes(user.id, false)?;
+        db.delete_likes_by_user_id(user.id)?;
+        db.commit()?;
+        println!("likes are removed");
+        return Ok(());
+    }
+
     db.begin_transaction()?;
     db.delete_user(user.id)?;
+    db.delete_entities_by_user_id(user.id)?;
+    db.delete_media_by_user_id(user.id)?;
+    db.delete_screen_name_changes_by_user_id(user.id)?;
+    db.delete_list_members_by_user_id(user.id)?;
+    db.delete_likes_by_user_id(user.id)?;
     db.delete_tweets_by_user_id(user.id)?;
     db.commit()?;
 
@@ -0,0 +1,69 @@
+use clap::ArgMatches;
+
+use crate::cmd::auth::BearerToken;
+use crate::cmd::import::{insert_tweet, retrieve_or_insert_author};
+use crate::cmd::record_rate_limit;
+use crate::db::Db;
+use crate::error::Error;
+use crate::config::Config;
+use crate::twitter;
+
+pub fn execute_add_search(args: &ArgMatches) -> Result<(), Error> {
+    let config = Config::load("default")?;
+
+    let db = Db::open(&config.database_file)?;
+    let client = config.twitter_client()?;
+    let bearer_token = BearerToken::new(&config, &client);
+
+    let query = args.value_of("query").unwrap();
+    let search = match db.get_search_by_query(query)? {
+        Some(search) => search,
+        None => {
+            let search = db.insert_search(query)?;
+            println!("{}: added", search.query);
+            search
+        },
+    };
+
+    // tweets found now are imported without notification, as `add` does.
+    let tweets = record_rate_limit(&db, bearer_token.call(|auth| {
+        client.search_tweets(auth, &search.query, Some(twitter::MAX_SEARCH_COUNT), search.since_id.map(|id| id as u64))
+    }))?;
+
+    let mut insert_count = 0;
+    let mut newest_id = search.since_id;
+    for tweet in tweets {
+        newest_id = newest_id.max(Some(tweet.id as i64));
+
+        if db.get_tweet(tweet.id as i64)?.is_some() {
+            continue;
+        }
+
+        let author = retrieve_or_insert_author(&db, &tweet)?;
+        insert_tweet(&db, &client, &author, &tweet, Some(&search.query))?;
+        insert_count += 1;
+    }
+
+    if newest_id != search.since_id {
+        db.update_search_since_id(search.id, newest_id.unwrap())?;
+    }
+
+    println!("imported {} tweets", insert_count);
+    Ok(())
+}
+
+pub fn execute_remove_search(args: &ArgMatches) -> Result<(), Error> {
+    let config = Config::load("default")?;
+    let db = Db::open(&config.database_file)?;
+
+    let query = args.value_of("query").unwrap();
+    match db.get_search_by_query(query)? {
+        Some(search) => {
+            db.delete_search(search.id)?;
+            println!("search is removed");
+        },
+        None => println!("specified search is not existed"),
+    }
+
+    Ok(())
+}
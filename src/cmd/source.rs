@@ -0,0 +1,150 @@
+use crate::bluesky::{BlueskyClient, Position};
+use crate::cmd::auth::BearerToken;
+use crate::config::Config;
+use crate::cmd::import::account;
+use crate::db::models::User;
+use crate::error::Error;
+use crate::feed::{FeedClient, Validators};
+use crate::mastodon::{Location, MastodonClient};
+use crate::source::{Page, Source, SourceKind};
+use crate::twitter::{self, TwitterClient};
+
+pub struct TwitterSource<'a> {
+    pub client: &'a TwitterClient,
+    pub bearer_token: &'a BearerToken<'a>,
+}
+
+impl<'a> TwitterSource<'a> {
+    pub fn fetch_likes(&self, user: &User) -> Result<Vec<twitter::Tweet>, Error> {
+        self.bearer_token.call_for(user.protected, |auth| {
+            self.client.get_favorites(auth, account(user), Some(twitter::MAX_FAVORITES_COUNT))
+        })
+    }
+}
+
+impl<'a> Source for TwitterSource<'a> {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Twitter
+    }
+
+    fn resolve(&self, name: &str) -> Result<twitter::User, Error> {
+        self.bearer_token.call(|auth| self.client.lookup_user(auth, name))
+    }
+
+    fn can_fetch(&self, user: &User) -> bool {
+        !user.protected || self.bearer_token.has_user_credentials()
+    }
+
+    fn pages_by_since_id(&self) -> bool {
+        true
+    }
+
+    fn fetch(&self, user: &User, since_id: Option<i64>, count: Option<u32>) -> Result<Page, Error> {
+        let tweets = self.bearer_token.call_for(user.protected, |auth| {
+            self.client.get_tweets(auth, account(user), count, since_id.map(|id| id as u64), None)
+        })?;
+        Ok(Page { tweets: tweets, cursor: None })
+    }
+
+    fn lookup(&self, user: Option<&User>, ids: &[u64]) -> Result<Option<Vec<twitter::Tweet>>, Error> {
+        let protected = user.map(|user| user.protected).unwrap_or(false);
+        let tweets = self.bearer_token.call_for(protected, |auth| self.client.lookup_tweets(auth, ids))?;
+        Ok(Some(tweets))
+    }
+
+    fn is_deleted(&self, user: &User, id: u64) -> Result<bool, Error> {
+        self.bearer_token.call_for(user.protected, |auth| self.client.is_tweet_deleted(auth, id))
+    }
+
+    fn endpoint(&self) -> Option<&'static str> {
+        Some(self.client.timeline_endpoint())
+    }
+}
+
+impl Source for FeedClient {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Feed
+    }
+
+    fn resolve(&self, name: &str) -> Result<twitter::User, Error> {
+        match self.get_feed(name, &Validators::default())? {
+            Some((feed, _)) => Ok(feed.profile(name)),
+            None => Err(Error::FeedError(format!("{} is not modified without validators", name))),
+        }
+    }
+
+    fn fetch(&self, user: &User, _since_id: Option<i64>, _count: Option<u32>) -> Result<Page, Error> {
+        let validators = Validators::from_cursor(user.cursor.as_deref());
+        match self.get_feed(&user.screen_name, &validators)? {
+            Some((feed, validators)) => Ok(Page { tweets: feed.to_tweets(&user.screen_name), cursor: Some(validators.to_cursor()) }),
+            None => Ok(Page::default()),
+        }
+    }
+}
+
+impl Source for MastodonClient {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Mastodon
+    }
+
+    fn resolve(&self, name: &str) -> Result<twitter::User, Error> {
+        Ok(self.lookup_account(name)?.1)
+    }
+
+    fn fetch(&self, user: &User, _since_id: Option<i64>, count: Option<u32>) -> Result<Page, Error> {
+        let location = match Location::from_cursor(user.cursor.as_deref()) {
+            Some(location) => location,
+            None => self.lookup_account(&user.screen_name)?.0,
+        };
+        let (tweets, location) = self.get_statuses(&location, count)?;
+        Ok(Page { tweets: tweets, cursor: Some(location.to_cursor()) })
+    }
+}
+
+impl Source for BlueskyClient {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Bluesky
+    }
+
+    fn resolve(&self, name: &str) -> Result<twitter::User, Error> {
+        let did = self.resolve_handle(name)?;
+        self.get_profile(&did)
+    }
+
+    fn fetch(&self, user: &User, _since_id: Option<i64>, count: Option<u32>) -> Result<Page, Error> {
+        let position = match Position::from_cursor(user.cursor.as_deref()) {
+            Some(position) => position,
+            None => Position { did: self.resolve_handle(&user.screen_name)?, seen_at: None },
+        };
+        let (tweets, position) = self.get_author_feed(&position, count)?;
+        Ok(Page { tweets: tweets, cursor: Some(position.to_cursor()) })
+    }
+}
+
+pub struct Sources<'a> {
+    pub twitter: TwitterSource<'a>,
+    pub feed: FeedClient,
+    pub mastodon: MastodonClient,
+    pub bluesky: BlueskyClient,
+}
+
+impl<'a> Sources<'a> {
+    pub fn new(config: &Config, client: &'a TwitterClient, bearer_token: &'a BearerToken<'a>) -> Sources<'a> {
+        Sources {
+            twitter: TwitterSource { client: client, bearer_token: bearer_token },
+            // feeds are fetched with the same timeouts, proxy and certificates as the API.
+            feed: FeedClient::new(client.client.clone()),
+            mastodon: MastodonClient::new(client.client.clone(), &config.mastodon_scheme),
+            bluesky: BlueskyClient::new(client.client.clone(), &config.bluesky_api_base_url),
+        }
+    }
+
+    pub fn get(&self, kind: SourceKind) -> &dyn Source {
+        match kind {
+            SourceKind::Twitter => &self.twitter,
+            SourceKind::Feed => &self.feed,
+            SourceKind::Mastodon => &self.mastodon,
+            SourceKind::Bluesky => &self.bluesky,
+        }
+    }
+}
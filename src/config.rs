@@ -1,11 +1,16 @@
 use std::env;
 use std::fs;
-use std::io::Write;
+use std::io::{self, Write};
 use std::path;
+use std::time::Duration;
 
+use chrono::{DateTime, Utc};
 use serde_json::{self, json};
 
+use crate::bluesky;
 use crate::error::Error;
+use crate::source::TweetKind;
+use crate::twitter::{self, ApiVersion, Credentials, HttpSettings, RetryPolicy, TwitterClient, UserToken};
 
 #[derive(Debug)]
 pub struct Config {
@@ -15,6 +20,25 @@ pub struct Config {
     pub database_file: String,
     pub notification_from_email: String,
     pub notification_tos: Vec<String>,
+    pub api_base_url: String,
+    pub api_version: ApiVersion,
+    pub attach_media: bool,
+    pub notify_renames: bool,
+    pub detect_deletions: bool,
+    pub notify_deletions: bool,
+    // all of them by default
+    pub notify_kinds: Vec<TweetKind>,
+    pub retry_policy: RetryPolicy,
+    pub http: HttpSettings,
+    pub user_token: Option<UserToken>,
+    pub mastodon_scheme: String,
+    pub bluesky_api_base_url: String,
+}
+
+#[derive(Debug)]
+pub struct CachedToken {
+    pub access_token: String,
+    pub created_at: DateTime<Utc>,
 }
 
 impl Config {
@@ -30,6 +54,10 @@ impl Config {
         return Ok(homepath);
     }
 
+    pub fn media_dir() -> Result<path::PathBuf, env::VarError> {
+        Ok(Self::home_dir()?.join("media"))
+    }
+
     pub fn default_database_file() -> Result<String, Error> {
         let config_dir = Config::home_dir()?;
         Ok(String::from(config_dir.as_path().join("default.sqlite3").to_str().unwrap()))
@@ -52,6 +80,53 @@ impl Config {
         } else {
             vec![]
         };
+        // TWITNOT_API_BASE_URL overrides the profile, e.g. to point to a mock server.
+        let api_base_url = env::var("TWITNOT_API_BASE_URL").ok()
+            .or_else(|| cfg["api_base_url"].as_str().map(String::from))
+            .unwrap_or_else(|| String::from(twitter::DEFAULT_API_BASE_URL));
+        let api_version = match cfg["api_version"].as_str() {
+            Some(api_version) => api_version.parse()?,
+            None => ApiVersion::V1_1,
+        };
+        let attach_media = cfg["attach_media"].as_bool().unwrap_or(false);
+        let notify_renames = cfg["notify_renames"].as_bool().unwrap_or(false);
+        let detect_deletions = cfg["detect_deletions"].as_bool().unwrap_or(false);
+        let notify_deletions = cfg["notify_deletions"].as_bool().unwrap_or(false);
+        let notify_kinds = match cfg["notify_kinds"].as_array() {
+            Some(ary) => ary.iter()
+                .map(|item| item.as_str().and_then(|kind| kind.parse().ok()).ok_or(Error::ConfigError("notify_kinds")))
+                .collect::<Result<Vec<TweetKind>, Error>>()?,
+            None => TweetKind::ALL.to_vec(),
+        };
+        let retry_policy = RetryPolicy {
+            max_attempts: cfg["retry_attempts"].as_u64().map(|n| n.max(1) as u32).unwrap_or(twitter::DEFAULT_RETRY_ATTEMPTS),
+            deadline: Duration::from_secs(cfg["retry_deadline"].as_u64().unwrap_or(twitter::DEFAULT_RETRY_DEADLINE_SECS)),
+        };
+
+        let http = HttpSettings {
+            connect_timeout: Duration::from_secs(cfg["connect_timeout"].as_u64().unwrap_or(twitter::DEFAULT_CONNECT_TIMEOUT_SECS)),
+            read_timeout: Duration::from_secs(cfg["read_timeout"].as_u64().unwrap_or(twitter::DEFAULT_READ_TIMEOUT_SECS)),
+            proxy: cfg["proxy"].as_str().map(String::from),
+            no_proxy: if let Some(ary) = cfg["no_proxy"].as_array() {
+                ary.into_iter().filter_map(|item| item.as_str().map(String::from)).collect()
+            } else {
+                vec![]
+            },
+            ca_bundle: cfg["ca_bundle"].as_str().map(String::from),
+        };
+
+        let user_token = match (cfg["user_access_token"].as_str(), cfg["user_access_token_secret"].as_str()) {
+            (Some(token), Some(token_secret)) => Some(UserToken {
+                token: String::from(token),
+                token_secret: String::from(token_secret),
+                screen_name: cfg["user_screen_name"].as_str().map(String::from).unwrap_or_default(),
+            }),
+            _ => None,
+        };
+
+        let mastodon_scheme = cfg["mastodon_scheme"].as_str().map(String::from).unwrap_or_else(|| String::from("https"));
+        let bluesky_api_base_url = cfg["bluesky_api_base_url"].as_str().map(String::from)
+            .unwrap_or_else(|| String::from(bluesky::DEFAULT_API_BASE_URL));
 
         return Ok(Config {
             consumer_key: consumer_key,
@@ -60,6 +135,18 @@ impl Config {
             database_file: database_file,
             notification_from_email: notification_from_email,
             notification_tos: notification_tos,
+            api_base_url: api_base_url,
+            api_version: api_version,
+            attach_media: attach_media,
+            notify_renames: notify_renames,
+            detect_deletions: detect_deletions,
+            notify_deletions: notify_deletions,
+            notify_kinds: notify_kinds,
+            retry_policy: retry_policy,
+            http: http,
+            user_token: user_token,
+            mastodon_scheme: mastodon_scheme,
+            bluesky_api_base_url: bluesky_api_base_url,
         });
     }
 
@@ -71,6 +158,25 @@ impl Config {
             "database_file": self.database_file,
             "notification_from_email": self.notification_from_email,
             "notification_tos": self.notification_tos,
+            "api_base_url": self.api_base_url,
+            "api_version": self.api_version.to_string(),
+            "attach_media": self.attach_media,
+            "notify_renames": self.notify_renames,
+            "detect_deletions": self.detect_deletions,
+            "notify_deletions": self.notify_deletions,
+            "notify_kinds": self.notify_kinds.iter().map(|kind| kind.to_string()).collect::<Vec<String>>(),
+            "retry_attempts": self.retry_policy.max_attempts,
+            "retry_deadline": self.retry_policy.deadline.as_secs(),
+            "connect_timeout": self.http.connect_timeout.as_secs(),
+            "read_timeout": self.http.read_timeout.as_secs(),
+            "proxy": self.http.proxy,
+            "no_proxy": self.http.no_proxy,
+            "ca_bundle": self.http.ca_bundle,
+            "user_access_token": self.user_token.as_ref().map(|user_token| &user_token.token),
+            "user_access_token_secret": self.user_token.as_ref().map(|user_token| &user_token.token_secret),
+            "user_screen_name": self.user_token.as_ref().map(|user_token| &user_token.screen_name),
+            "mastodon_scheme": self.mastodon_scheme,
+            "bluesky_api_base_url": self.bluesky_api_base_url,
         });
 
         let config_dir = Self::home_dir()?;
@@ -83,4 +189,70 @@ impl Config {
 
         return Ok(());
     }
+
+    pub fn twitter_client(&self) -> Result<TwitterClient, Error> {
+        let mut client = TwitterClient::new(&self.api_base_url, self.api_version);
+        client.client = self.http.build_client()?;
+        client.retry_policy = self.retry_policy;
+        Ok(client)
+    }
+
+    pub fn user_credentials(&self) -> Option<Credentials> {
+        self.user_token.as_ref().map(|user_token| Credentials {
+            consumer_key: self.consumer_key.clone(),
+            consumer_secret: self.consumer_secret.clone(),
+            token: user_token.token.clone(),
+            token_secret: user_token.token_secret.clone(),
+        })
+    }
+
+    fn access_token_file(profile: &str) -> Result<path::PathBuf, Error> {
+        let config_dir = Self::home_dir()?;
+        Ok(config_dir.as_path().join(format!("{}.token", profile)))
+    }
+
+    // a token issued for another consumer key is treated as not cached.
+    pub fn load_access_token(&self, profile: &str) -> Result<Option<CachedToken>, Error> {
+        let file = match fs::File::open(Self::access_token_file(profile)?) {
+            Ok(file) => file,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(Error::from(err)),
+        };
+
+        let cache: serde_json::Value = serde_json::from_reader(file)?;
+        if cache["consumer_key"].as_str() != Some(self.consumer_key.as_str()) {
+            return Ok(None);
+        }
+
+        let access_token = cache["access_token"].as_str().map(String::from).ok_or(Error::ConfigError("access_token"))?;
+        let created_at = cache["created_at"].as_str().ok_or(Error::ConfigError("created_at"))?;
+        Ok(Some(CachedToken {
+            access_token: access_token,
+            created_at: DateTime::parse_from_rfc3339(created_at)?.with_timezone(&Utc),
+        }))
+    }
+
+    pub fn save_access_token(&self, profile: &str, access_token: &str) -> Result<(), Error> {
+        let cache = json!({
+            "consumer_key": self.consumer_key,
+            "access_token": access_token,
+            "created_at": Utc::now().to_rfc3339(),
+        });
+
+        let config_dir = Self::home_dir()?;
+        fs::create_dir_all(config_dir.as_path())?;
+
+        let mut file = fs::File::create(Self::access_token_file(profile)?)?;
+        file.write_all(cache.to_string().as_bytes())?;
+
+        Ok(())
+    }
+
+    pub fn remove_access_token(&self, profile: &str) -> Result<bool, Error> {
+        match fs::remove_file(Self::access_token_file(profile)?) {
+            Ok(_) => Ok(true),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(Error::from(err)),
+        }
+    }
 }
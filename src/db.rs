@@ -1,13 +1,17 @@
 use std::convert::TryFrom;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use log::info;
 use rusqlite::{self, NO_PARAMS, Connection, params};
 
 use crate::error::Error;
+use crate::source::{SourceKind, TweetKind};
+use crate::twitter;
 
 pub mod models;
 mod query;
 
+const SCHEMA_VERSION: i32 = 15;
+
 pub struct Db {
     conn: Connection,
 }
@@ -19,10 +23,199 @@ impl Db {
         conn.execute(query::ENABLE_FOREIGN_KEY, NO_PARAMS)?;
         conn.execute(query::CREATE_USERS_TABLE, NO_PARAMS)?;
         conn.execute(query::CREATE_TWEETS_TABLE, NO_PARAMS)?;
+        conn.execute(query::CREATE_RATE_LIMIT_EVENTS_TABLE, NO_PARAMS)?;
+        conn.execute(query::CREATE_URLS_TABLE, NO_PARAMS)?;
+        conn.execute(query::CREATE_HASHTAGS_TABLE, NO_PARAMS)?;
+        conn.execute(query::CREATE_USER_MENTIONS_TABLE, NO_PARAMS)?;
+        conn.execute(query::CREATE_SYMBOLS_TABLE, NO_PARAMS)?;
+        conn.execute(query::CREATE_MEDIA_TABLE, NO_PARAMS)?;
+        conn.execute(query::CREATE_REFERENCED_TWEETS_TABLE, NO_PARAMS)?;
+        conn.execute(query::CREATE_SCREEN_NAME_CHANGES_TABLE, NO_PARAMS)?;
+        conn.execute(query::CREATE_LISTS_TABLE, NO_PARAMS)?;
+        conn.execute(query::CREATE_LIST_MEMBERS_TABLE, NO_PARAMS)?;
+        conn.execute(query::CREATE_LIKES_TABLE, NO_PARAMS)?;
+        conn.execute(query::CREATE_SEARCHES_TABLE, NO_PARAMS)?;
         conn.execute(query::CREATE_INDEX_USER_ID_ON_TWEETS, NO_PARAMS)?;
         conn.execute(query::CREATE_INDEX_CREATED_AT_ON_TWEETS, NO_PARAMS)?;
+        conn.execute(query::CREATE_INDEX_TWEET_ID_ON_URLS, NO_PARAMS)?;
+        conn.execute(query::CREATE_INDEX_TWEET_ID_ON_HASHTAGS, NO_PARAMS)?;
+        conn.execute(query::CREATE_INDEX_TWEET_ID_ON_USER_MENTIONS, NO_PARAMS)?;
+        conn.execute(query::CREATE_INDEX_TWEET_ID_ON_SYMBOLS, NO_PARAMS)?;
+
+        let db = Db { conn: conn };
+        db.migrate()?;
+
+        Ok(db)
+    }
+
+    fn migrate(&self) -> Result<(), Error> {
+        let version: i32 = self.conn.query_row(query::GET_SCHEMA_VERSION, NO_PARAMS, |row| row.get(0))?;
+        if version >= SCHEMA_VERSION {
+            return Ok(());
+        }
+
+        self.begin_transaction()?;
+        if version < 1 {
+            self.conn.execute(query::ADD_SINCE_ID_TO_USERS, NO_PARAMS)?;
+        }
+        if version < 2 {
+            self.conn.execute(query::ADD_BACKFILL_MAX_ID_TO_USERS, NO_PARAMS)?;
+        }
+        if version < 3 {
+            self.conn.execute(query::ADD_CHECKED_AT_TO_USERS, NO_PARAMS)?;
+        }
+        if version < 4 {
+            self.restore_full_texts()?;
+        }
+        if version < 5 {
+            self.extract_entities()?;
+        }
+        if version < 6 {
+            self.conn.execute(query::ADD_ACCOUNT_ID_TO_USERS, NO_PARAMS)?;
+            self.conn.execute(query::ADD_NAME_TO_USERS, NO_PARAMS)?;
+            self.conn.execute(query::ADD_PROTECTED_TO_USERS, NO_PARAMS)?;
+            self.conn.execute(query::ADD_VERIFIED_TO_USERS, NO_PARAMS)?;
+            self.conn.execute(query::CREATE_INDEX_ACCOUNT_ID_ON_USERS, NO_PARAMS)?;
+        }
+        if version < 7 {
+            self.conn.execute(query::ADD_WATCHED_TO_USERS, NO_PARAMS)?;
+        }
+        if version < 8 {
+            self.conn.execute(query::ADD_SEARCH_QUERY_TO_TWEETS, NO_PARAMS)?;
+        }
+        if version < 9 {
+            self.conn.execute(query::ADD_DELETED_AT_TO_TWEETS, NO_PARAMS)?;
+        }
+        if version < 10 {
+            self.conn.execute(query::ADD_QUOTED_STATUS_ID_TO_TWEETS, NO_PARAMS)?;
+            self.conn.execute(query::ADD_IN_REPLY_TO_STATUS_ID_TO_TWEETS, NO_PARAMS)?;
+            self.conn.execute(query::ADD_IN_REPLY_TO_SCREEN_NAME_TO_TWEETS, NO_PARAMS)?;
+            self.extract_references()?;
+        }
+        if version < 11 {
+            // account ids are unique only within a backend.
+            self.conn.execute(query::ADD_SOURCE_KIND_TO_USERS, NO_PARAMS)?;
+            self.conn.execute(query::DROP_INDEX_ACCOUNT_ID_ON_USERS, NO_PARAMS)?;
+            self.conn.execute(query::CREATE_INDEX_SOURCE_KIND_ACCOUNT_ID_ON_USERS, NO_PARAMS)?;
+        }
+        if version < 12 {
+            self.conn.execute(query::ADD_CURSOR_TO_USERS, NO_PARAMS)?;
+        }
+        if version < 13 {
+            self.conn.execute(query::ADD_KIND_TO_TWEETS, NO_PARAMS)?;
+            self.classify_tweets()?;
+        }
+        if version < 14 {
+            self.conn.execute(query::ADD_WATCH_LIKES_TO_USERS, NO_PARAMS)?;
+        }
+        if version < 15 {
+            // screen names are unique only within a backend, and sqlite cannot drop a column constraint.
+            self.conn.execute(query::CREATE_NEW_USERS_TABLE, NO_PARAMS)?;
+            self.conn.execute(query::COPY_USERS_TO_NEW_USERS, NO_PARAMS)?;
+            self.conn.execute(query::DROP_USERS_TABLE, NO_PARAMS)?;
+            self.conn.execute(query::RENAME_NEW_USERS_TO_USERS, NO_PARAMS)?;
+            self.conn.execute(query::CREATE_INDEX_SOURCE_KIND_ACCOUNT_ID_ON_USERS, NO_PARAMS)?;
+        }
+        self.conn.execute_batch(&format!("PRAGMA user_version = {};", SCHEMA_VERSION))?;
+        self.commit()?;
+
+        info!("database was migrated from version {} to {}", version, SCHEMA_VERSION);
+        Ok(())
+    }
+
+    fn restore_full_texts(&self) -> Result<(), Error> {
+        let mut stmt = self.conn.prepare(query::GET_ALL_TWEET_RAW_JSONS)?;
+        let iter = stmt.query_map(NO_PARAMS, |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Option<String>>(1)?, row.get::<_, Option<String>>(2)?)))?;
+
+        let mut update_count = 0;
+        for result in iter {
+            let (id, text, raw_json) = result?;
+            let status: serde_json::Value = match raw_json.as_ref().map(|raw_json| serde_json::from_str(raw_json)) {
+                Some(Ok(status)) => status,
+                _ => continue,
+            };
+            if let Ok(tweet) = twitter::parse_raw_json(&status) {
+                if text.as_ref() != Some(&tweet.text) {
+                    self.conn.execute(query::UPDATE_TWEET_TEXT, params![id, tweet.text])?;
+                    update_count += 1;
+                }
+            }
+        }
+
+        info!("{} tweets were restored to full text", update_count);
+        Ok(())
+    }
+
+    fn extract_entities(&self) -> Result<(), Error> {
+        let mut stmt = self.conn.prepare(query::GET_ALL_TWEET_RAW_JSONS)?;
+        let iter = stmt.query_map(NO_PARAMS, |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Option<String>>(2)?)))?;
+
+        let mut extract_count = 0;
+        for result in iter {
+            let (id, raw_json) = result?;
+            if let Some(Ok(status)) = raw_json.as_ref().map(|raw_json| serde_json::from_str(raw_json)) {
+                let tweet = match twitter::parse_raw_json(&status) {
+                    Ok(tweet) => tweet,
+                    Err(_) => continue,
+                };
+                self.insert_entities(id, &tweet.entities)?;
+                extract_count += 1;
+            }
+        }
+
+        info!("entities of {} tweets were extracted", extract_count);
+        Ok(())
+    }
+
+    fn extract_references(&self) -> Result<(), Error> {
+        let mut stmt = self.conn.prepare(query::GET_ALL_TWEET_RAW_JSONS)?;
+        let iter = stmt.query_map(NO_PARAMS, |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Option<String>>(2)?)))?;
+
+        let id_or_none = |id: u64| if id == 0 { None } else { Some(id as i64) };
+        let mut extract_count = 0;
+        for result in iter {
+            let (id, raw_json) = result?;
+            if let Some(Ok(status)) = raw_json.as_ref().map(|raw_json| serde_json::from_str(raw_json)) {
+                let references = match twitter::parse_raw_json(&status) {
+                    Ok(tweet) => tweet.references,
+                    Err(_) => continue,
+                };
+                if references.quoted_status_id != 0 || references.in_reply_to_status_id != 0 {
+                    self.conn.execute(query::UPDATE_TWEET_REFERENCES, params![
+                        id,
+                        id_or_none(references.quoted_status_id),
+                        id_or_none(references.in_reply_to_status_id),
+                        Some(references.in_reply_to_screen_name).filter(|name| !name.is_empty())
+                    ])?;
+                    extract_count += 1;
+                }
+            }
+        }
+
+        info!("references of {} tweets were extracted", extract_count);
+        Ok(())
+    }
+
+    // `retweets` was inverted for v1.1 statuses.
+    fn classify_tweets(&self) -> Result<(), Error> {
+        let mut stmt = self.conn.prepare(query::GET_ALL_TWEET_RAW_JSONS)?;
+        let iter = stmt.query_map(NO_PARAMS, |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Option<String>>(2)?)))?;
+
+        let mut classify_count = 0;
+        for result in iter {
+            let (id, raw_json) = result?;
+            let kind = match raw_json.as_ref().map(|raw_json| serde_json::from_str(raw_json)) {
+                Some(Ok(status)) => TweetKind::from_raw_json(&status),
+                _ => TweetKind::Original,
+            };
+            self.conn.execute(query::UPDATE_TWEET_KIND, params![id, kind, kind == TweetKind::Retweet])?;
+            if kind != TweetKind::Original {
+                classify_count += 1;
+            }
+        }
 
-        Ok(Db { conn: conn })
+        info!("{} tweets were classified as other than original", classify_count);
+        Ok(())
     }
 
     pub fn begin_transaction(&self) -> Result<(), Error> {
@@ -35,6 +228,11 @@ impl Db {
         Ok(())
     }
 
+    pub fn rollback(&self) -> Result<(), Error> {
+        self.conn.execute(query::ROLLBACK_TRANSACTION, NO_PARAMS)?;
+        Ok(())
+    }
+
     pub fn get_all_users(&self) -> Result<Vec<models::User>, Error> {
         let mut stmt = self.conn.prepare(query::GET_ALL_USERS)?;
         let iter = stmt.query_map(NO_PARAMS, |row| Ok(models::User::try_from(row)?))?;
@@ -43,6 +241,14 @@ impl Db {
         Ok(users?)
     }
 
+    pub fn get_users_order_by_checked_at(&self) -> Result<Vec<models::User>, Error> {
+        let mut stmt = self.conn.prepare(query::GET_USERS_ORDER_BY_CHECKED_AT)?;
+        let iter = stmt.query_map(NO_PARAMS, |row| Ok(models::User::try_from(row)?))?;
+
+        let users: Result<Vec<models::User>, rusqlite::Error> = iter.collect();
+        Ok(users?)
+    }
+
     pub fn get_user_by_screen_name(&self, screen_name: &str) -> Result<Option<models::User>, Error> {
         let mut stmt = self.conn.prepare(query::GET_USER_BY_SCREEN_NAME)?;
         let mut iter = stmt.query_map(params![screen_name], |row| Ok(models::User::try_from(row)?))?;
@@ -53,6 +259,26 @@ impl Db {
         })
     }
 
+    pub fn get_user_by_source_kind_and_screen_name(&self, source_kind: SourceKind, screen_name: &str) -> Result<Option<models::User>, Error> {
+        let mut stmt = self.conn.prepare(query::GET_USER_BY_SOURCE_KIND_AND_SCREEN_NAME)?;
+        let mut iter = stmt.query_map(params![source_kind, screen_name], |row| Ok(models::User::try_from(row)?))?;
+
+        Ok(match iter.next() {
+            Some(result) => Some(result?),
+            _ => None
+        })
+    }
+
+    pub fn get_user_by_account_id(&self, source_kind: SourceKind, account_id: i64) -> Result<Option<models::User>, Error> {
+        let mut stmt = self.conn.prepare(query::GET_USER_BY_ACCOUNT_ID)?;
+        let mut iter = stmt.query_map(params![source_kind, account_id], |row| Ok(models::User::try_from(row)?))?;
+
+        Ok(match iter.next() {
+            Some(result) => Some(result?),
+            _ => None
+        })
+    }
+
     pub fn get_user_by_row_id(&self, row_id: i64) -> Result<Option<models::User>, Error> {
         let mut stmt = self.conn.prepare(query::GET_USER_BY_ROW_ID)?;
         let mut iter = stmt.query_map(&[row_id], |row| Ok(models::User::try_from(row)?))?;
@@ -63,9 +289,9 @@ impl Db {
         })
     }
 
-    pub fn insert_user(&self, screen_name: &str) -> Result<models::User, Error> {
+    pub fn insert_user(&self, screen_name: &str, source_kind: SourceKind) -> Result<models::User, Error> {
         let mut stmt = self.conn.prepare(query::INSERT_USER)?;
-        let changes = stmt.execute(params![screen_name, &Utc::now()])?;
+        let changes = stmt.execute(params![screen_name, source_kind, &Utc::now()])?;
         if changes == 0 {
             return Err(Error::ModelError("insert user error"));
         }
@@ -75,6 +301,62 @@ impl Db {
         opt_user.ok_or_else(|| Error::ModelError("insert user error"))
     }
 
+    pub fn update_user_profile(&self, id: i32, profile: &twitter::User) -> Result<(), Error> {
+        self.conn.execute(query::UPDATE_USER_PROFILE, params![
+            id, profile.id as i64, profile.screen_name, profile.name, profile.protected, profile.verified
+        ])?;
+        Ok(())
+    }
+
+    pub fn rename_user(&self, id: i32, old_screen_name: &str, new_screen_name: &str) -> Result<(), Error> {
+        self.conn.execute(query::UPDATE_USER_SCREEN_NAME, params![id, new_screen_name])?;
+        self.conn.execute(query::INSERT_SCREEN_NAME_CHANGE, params![id, old_screen_name, new_screen_name, &Utc::now()])?;
+        Ok(())
+    }
+
+    pub fn get_screen_name_changes_by_user_id(&self, user_id: i32) -> Result<Vec<models::ScreenNameChange>, Error> {
+        let mut stmt = self.conn.prepare(query::GET_SCREEN_NAME_CHANGES_BY_USER_ID)?;
+        let iter = stmt.query_map(&[user_id], |row| Ok(models::ScreenNameChange::try_from(row)?))?;
+
+        let changes: Result<Vec<models::ScreenNameChange>, rusqlite::Error> = iter.collect();
+        Ok(changes?)
+    }
+
+    pub fn delete_screen_name_changes_by_user_id(&self, user_id: i32) -> Result<(), Error> {
+        self.conn.execute(query::DELETE_SCREEN_NAME_CHANGES_BY_USER_ID, &[user_id])?;
+        Ok(())
+    }
+
+    pub fn update_user_watched(&self, id: i32, watched: bool) -> Result<(), Error> {
+        self.conn.execute(query::UPDATE_USER_WATCHED, params![id, watched])?;
+        Ok(())
+    }
+
+    pub fn update_user_watch_likes(&self, id: i32, watch_likes: bool) -> Result<(), Error> {
+        self.conn.execute(query::UPDATE_USER_WATCH_LIKES, params![id, watch_likes])?;
+        Ok(())
+    }
+
+    pub fn update_user_since_id(&self, id: i32, since_id: i64) -> Result<(), Error> {
+        self.conn.execute(query::UPDATE_USER_SINCE_ID, params![id, since_id])?;
+        Ok(())
+    }
+
+    pub fn update_user_cursor(&self, id: i32, cursor: &str) -> Result<(), Error> {
+        self.conn.execute(query::UPDATE_USER_CURSOR, params![id, cursor])?;
+        Ok(())
+    }
+
+    pub fn update_user_backfill_max_id(&self, id: i32, backfill_max_id: i64) -> Result<(), Error> {
+        self.conn.execute(query::UPDATE_USER_BACKFILL_MAX_ID, params![id, backfill_max_id])?;
+        Ok(())
+    }
+
+    pub fn update_user_checked_at(&self, id: i32, checked_at: &DateTime<Utc>) -> Result<(), Error> {
+        self.conn.execute(query::UPDATE_USER_CHECKED_AT, params![id, checked_at])?;
+        Ok(())
+    }
+
     pub fn delete_user(&self, id: i32) -> Result<(), Error> {
         let changes = self.conn.execute(query::DELETE_USER, &[id])?;
         info!("{} users were deleted", changes);
@@ -82,6 +364,176 @@ impl Db {
         Ok(())
     }
 
+    pub fn get_lists_order_by_checked_at(&self) -> Result<Vec<models::List>, Error> {
+        let mut stmt = self.conn.prepare(query::GET_LISTS_ORDER_BY_CHECKED_AT)?;
+        let iter = stmt.query_map(NO_PARAMS, |row| Ok(models::List::try_from(row)?))?;
+
+        let lists: Result<Vec<models::List>, rusqlite::Error> = iter.collect();
+        Ok(lists?)
+    }
+
+    pub fn get_list_by_name(&self, owner: &str, slug: &str) -> Result<Option<models::List>, Error> {
+        let mut stmt = self.conn.prepare(query::GET_LIST_BY_NAME)?;
+        let mut iter = stmt.query_map(params![owner, slug], |row| Ok(models::List::try_from(row)?))?;
+
+        Ok(match iter.next() {
+            Some(result) => Some(result?),
+            _ => None
+        })
+    }
+
+    pub fn get_list_by_row_id(&self, row_id: i64) -> Result<Option<models::List>, Error> {
+        let mut stmt = self.conn.prepare(query::GET_LIST_BY_ROW_ID)?;
+        let mut iter = stmt.query_map(&[row_id], |row| Ok(models::List::try_from(row)?))?;
+
+        Ok(match iter.next() {
+            Some(result) => Some(result?),
+            _ => None
+        })
+    }
+
+    pub fn insert_list(&self, owner: &str, slug: &str) -> Result<models::List, Error> {
+        let mut stmt = self.conn.prepare(query::INSERT_LIST)?;
+        let changes = stmt.execute(params![owner, slug, &Utc::now()])?;
+        if changes == 0 {
+            return Err(Error::ModelError("insert list error"));
+        }
+
+        let row_id = self.conn.last_insert_rowid();
+        let opt_list = self.get_list_by_row_id(row_id)?;
+        opt_list.ok_or_else(|| Error::ModelError("insert list error"))
+    }
+
+    pub fn update_list_since_id(&self, id: i32, since_id: i64) -> Result<(), Error> {
+        self.conn.execute(query::UPDATE_LIST_SINCE_ID, params![id, since_id])?;
+        Ok(())
+    }
+
+    pub fn update_list_checked_at(&self, id: i32, checked_at: &DateTime<Utc>) -> Result<(), Error> {
+        self.conn.execute(query::UPDATE_LIST_CHECKED_AT, params![id, checked_at])?;
+        Ok(())
+    }
+
+    pub fn delete_list(&self, id: i32) -> Result<(), Error> {
+        self.conn.execute(query::DELETE_LIST_MEMBERS_BY_LIST_ID, &[id])?;
+        self.conn.execute(query::DELETE_LIST, &[id])?;
+        Ok(())
+    }
+
+    pub fn get_list_members(&self, list_id: i32) -> Result<Vec<models::User>, Error> {
+        let mut stmt = self.conn.prepare(query::GET_LIST_MEMBERS)?;
+        let iter = stmt.query_map(&[list_id], |row| Ok(models::User::try_from(row)?))?;
+
+        let users: Result<Vec<models::User>, rusqlite::Error> = iter.collect();
+        Ok(users?)
+    }
+
+    pub fn insert_list_member(&self, list_id: i32, user_id: i32) -> Result<(), Error> {
+        self.conn.execute(query::INSERT_LIST_MEMBER, &[list_id, user_id])?;
+        Ok(())
+    }
+
+    pub fn delete_list_member(&self, list_id: i32, user_id: i32) -> Result<(), Error> {
+        self.conn.execute(query::DELETE_LIST_MEMBER, &[list_id, user_id])?;
+        Ok(())
+    }
+
+    pub fn delete_list_members_by_user_id(&self, user_id: i32) -> Result<(), Error> {
+        self.conn.execute(query::DELETE_LIST_MEMBERS_BY_USER_ID, &[user_id])?;
+        Ok(())
+    }
+
+    pub fn get_like(&self, user_id: i32, tweet_id: i64) -> Result<Option<models::Like>, Error> {
+        let mut stmt = self.conn.prepare(query::GET_LIKE)?;
+        let mut iter = stmt.query_map(params![user_id, tweet_id], |row| Ok(models::Like::try_from(row)?))?;
+
+        Ok(match iter.next() {
+            Some(result) => Some(result?),
+            _ => None
+        })
+    }
+
+    pub fn get_likes_by_user_id(&self, user_id: i32, limit: i32) -> Result<Vec<models::Like>, Error> {
+        let mut stmt = self.conn.prepare(query::GET_LIKES_BY_USER_ID)?;
+        let iter = stmt.query_map(params![user_id, limit], |row| Ok(models::Like::try_from(row)?))?;
+
+        let likes: Result<Vec<models::Like>, rusqlite::Error> = iter.collect();
+        Ok(likes?)
+    }
+
+    pub fn insert_like(&self, user_id: i32, tweet_id: i64) -> Result<(), Error> {
+        self.conn.execute(query::INSERT_LIKE, params![user_id, tweet_id, &Utc::now()])?;
+        Ok(())
+    }
+
+    pub fn delete_likes_by_user_id(&self, user_id: i32) -> Result<(), Error> {
+        self.conn.execute(query::DELETE_LIKES_BY_USER_ID, &[user_id])?;
+        Ok(())
+    }
+
+    pub fn get_searches_order_by_checked_at(&self) -> Result<Vec<models::Search>, Error> {
+        let mut stmt = self.conn.prepare(query::GET_SEARCHES_ORDER_BY_CHECKED_AT)?;
+        let iter = stmt.query_map(NO_PARAMS, |row| Ok(models::Search::try_from(row)?))?;
+
+        let searches: Result<Vec<models::Search>, rusqlite::Error> = iter.collect();
+        Ok(searches?)
+    }
+
+    pub fn get_search_by_query(&self, query: &str) -> Result<Option<models::Search>, Error> {
+        let mut stmt = self.conn.prepare(query::GET_SEARCH_BY_QUERY)?;
+        let mut iter = stmt.query_map(params![query], |row| Ok(models::Search::try_from(row)?))?;
+
+        Ok(match iter.next() {
+            Some(result) => Some(result?),
+            _ => None
+        })
+    }
+
+    pub fn get_search_by_row_id(&self, row_id: i64) -> Result<Option<models::Search>, Error> {
+        let mut stmt = self.conn.prepare(query::GET_SEARCH_BY_ROW_ID)?;
+        let mut iter = stmt.query_map(&[row_id], |row| Ok(models::Search::try_from(row)?))?;
+
+        Ok(match iter.next() {
+            Some(result) => Some(result?),
+            _ => None
+        })
+    }
+
+    pub fn insert_search(&self, query: &str) -> Result<models::Search, Error> {
+        let mut stmt = self.conn.prepare(query::INSERT_SEARCH)?;
+        let changes = stmt.execute(params![query, &Utc::now()])?;
+        if changes == 0 {
+            return Err(Error::ModelError("insert search error"));
+        }
+
+        let row_id = self.conn.last_insert_rowid();
+        let opt_search = self.get_search_by_row_id(row_id)?;
+        opt_search.ok_or_else(|| Error::ModelError("insert search error"))
+    }
+
+    pub fn update_search_since_id(&self, id: i32, since_id: i64) -> Result<(), Error> {
+        self.conn.execute(query::UPDATE_SEARCH_SINCE_ID, params![id, since_id])?;
+        Ok(())
+    }
+
+    pub fn update_search_checked_at(&self, id: i32, checked_at: &DateTime<Utc>) -> Result<(), Error> {
+        self.conn.execute(query::UPDATE_SEARCH_CHECKED_AT, params![id, checked_at])?;
+        Ok(())
+    }
+
+    pub fn delete_search(&self, id: i32) -> Result<(), Error> {
+        self.conn.execute(query::DELETE_SEARCH, &[id])?;
+        Ok(())
+    }
+
+    pub fn get_tweets_by_search_query(&self, search_query: &str, limit: i32) -> Result<Vec<models::Tweet>, Error> {
+        let mut stmt = self.conn.prepare(query::GET_TWEETS_BY_SEARCH_QUERY)?;
+        let iter = stmt.query_map(params![search_query, limit], |row| Ok(models::Tweet::try_from(row)?))?;
+
+        let tweets: Result<Vec<models::Tweet>, rusqlite::Error> = iter.collect();
+        Ok(tweets?)
+    }
+
     pub fn get_tweets_by_user_id(&self, user_id: i32, limit: i32) -> Result<Vec<models::Tweet>, Error> {
         let mut stmt = self.conn.prepare(query::GET_TWEETS_BY_USER_ID)?;
         let iter = stmt.query_map(params![user_id, limit], |row| {
@@ -92,6 +544,44 @@ impl Db {
         Ok(tweets?)
     }
 
+    pub fn get_tweets_by_user_id_and_kinds(&self, user_id: i32, kinds: &[TweetKind], limit: i32) -> Result<Vec<models::Tweet>, Error> {
+        // the kinds are matched as a comma separated list, e.g. ",reply,quote,".
+        let kinds = format!(",{},", kinds.iter().map(|kind| kind.to_string()).collect::<Vec<_>>().join(","));
+        let mut stmt = self.conn.prepare(query::GET_TWEETS_BY_USER_ID_AND_KINDS)?;
+        let iter = stmt.query_map(params![user_id, kinds, limit], |row| {
+            Ok(models::Tweet::try_from(row)?)
+        })?;
+
+        let tweets: Result<Vec<models::Tweet>, rusqlite::Error> = iter.collect();
+        Ok(tweets?)
+    }
+
+    pub fn get_recent_tweet_ids_by_user_id(&self, user_id: i32, limit: i32) -> Result<Vec<i64>, Error> {
+        let mut stmt = self.conn.prepare(query::GET_RECENT_TWEET_IDS_BY_USER_ID)?;
+        let iter = stmt.query_map(&[user_id, limit], |row| row.get(0))?;
+
+        let ids: Result<Vec<i64>, rusqlite::Error> = iter.collect();
+        Ok(ids?)
+    }
+
+    pub fn get_deleted_tweets_by_user_id(&self, user_id: i32, limit: i32) -> Result<Vec<models::Tweet>, Error> {
+        let mut stmt = self.conn.prepare(query::GET_DELETED_TWEETS_BY_USER_ID)?;
+        let iter = stmt.query_map(&[user_id, limit], |row| Ok(models::Tweet::try_from(row)?))?;
+
+        let tweets: Result<Vec<models::Tweet>, rusqlite::Error> = iter.collect();
+        Ok(tweets?)
+    }
+
+    pub fn update_tweet_deleted_at(&self, id: i64, deleted_at: &DateTime<Utc>) -> Result<(), Error> {
+        self.conn.execute(query::UPDATE_TWEET_DELETED_AT, params![id, deleted_at])?;
+        Ok(())
+    }
+
+    pub fn get_oldest_tweet_id_by_user_id(&self, user_id: i32) -> Result<Option<i64>, Error> {
+        let id = self.conn.query_row(query::GET_OLDEST_TWEET_ID_BY_USER_ID, &[user_id], |row| row.get(0))?;
+        Ok(id)
+    }
+
     pub fn get_tweet(&self, id: i64) -> Result<Option<models::Tweet>, Error> {
         let mut stmt = self.conn.prepare(query::GET_TWEET)?;
         let mut iter = stmt.query_map(&[id], |row| {
@@ -119,7 +609,8 @@ impl Db {
     pub fn insert_tweet(&self, tweet: &models::Tweet) -> Result<models::Tweet, Error> {
         let mut stmt = self.conn.prepare(query::INSERT_TWEET)?;
         let changes = stmt.execute(params![
-            tweet.id, tweet.user_id, tweet.user_name.as_str(), &tweet.created_at, tweet.text.as_str(), tweet.retweets, tweet.raw_json.as_str()
+            tweet.id, tweet.user_id, tweet.user_name.as_str(), &tweet.created_at, tweet.text.as_str(), tweet.kind == TweetKind::Retweet, tweet.raw_json.as_str(), tweet.search_query,
+            tweet.quoted_status_id, tweet.in_reply_to_status_id, tweet.in_reply_to_screen_name, tweet.kind
         ])?;
         if changes == 0 {
             return Err(Error::ModelError("insert tweet error"));
@@ -130,10 +621,240 @@ impl Db {
         opt_tweet.ok_or_else(|| Error::ModelError("insert tweet error"))
     }
 
+    pub fn insert_entities(&self, tweet_id: i64, entities: &twitter::Entities) -> Result<(), Error> {
+        for url in &entities.urls {
+            self.conn.execute(query::INSERT_URL, params![tweet_id, url.url, url.expanded_url, url.display_url])?;
+        }
+        for hashtag in &entities.hashtags {
+            self.conn.execute(query::INSERT_HASHTAG, params![tweet_id, hashtag])?;
+        }
+        for user_mention in &entities.user_mentions {
+            self.conn.execute(query::INSERT_USER_MENTION, params![tweet_id, user_mention.id as i64, user_mention.screen_name])?;
+        }
+        for symbol in &entities.symbols {
+            self.conn.execute(query::INSERT_SYMBOL, params![tweet_id, symbol])?;
+        }
+
+        Ok(())
+    }
+
+    pub fn get_urls_by_tweet_id(&self, tweet_id: i64) -> Result<Vec<models::Url>, Error> {
+        let mut stmt = self.conn.prepare(query::GET_URLS_BY_TWEET_ID)?;
+        let iter = stmt.query_map(&[tweet_id], |row| Ok(models::Url::try_from(row)?))?;
+
+        let urls: Result<Vec<models::Url>, rusqlite::Error> = iter.collect();
+        Ok(urls?)
+    }
+
+    pub fn delete_entities_by_user_id(&self, user_id: i32) -> Result<(), Error> {
+        self.conn.execute(query::DELETE_URLS_BY_USER_ID, &[user_id])?;
+        self.conn.execute(query::DELETE_HASHTAGS_BY_USER_ID, &[user_id])?;
+        self.conn.execute(query::DELETE_USER_MENTIONS_BY_USER_ID, &[user_id])?;
+        self.conn.execute(query::DELETE_SYMBOLS_BY_USER_ID, &[user_id])?;
+
+        Ok(())
+    }
+
+    pub fn get_referenced_tweet(&self, id: i64) -> Result<Option<models::ReferencedTweet>, Error> {
+        let mut stmt = self.conn.prepare(query::GET_REFERENCED_TWEET)?;
+        let mut iter = stmt.query_map(&[id], |row| Ok(models::ReferencedTweet::try_from(row)?))?;
+
+        Ok(match iter.next() {
+            Some(result) => Some(result?),
+            _ => None
+        })
+    }
+
+    pub fn get_referenced_tweets_by_tweet_id(&self, tweet_id: i64) -> Result<Vec<models::ReferencedTweet>, Error> {
+        let mut stmt = self.conn.prepare(query::GET_REFERENCED_TWEETS_BY_TWEET_ID)?;
+        let iter = stmt.query_map(&[tweet_id], |row| Ok(models::ReferencedTweet::try_from(row)?))?;
+
+        let tweets: Result<Vec<models::ReferencedTweet>, rusqlite::Error> = iter.collect();
+        Ok(tweets?)
+    }
+
+    pub fn insert_referenced_tweet(&self, tweet: &models::ReferencedTweet) -> Result<(), Error> {
+        self.conn.execute(query::INSERT_REFERENCED_TWEET, params![
+            tweet.id, tweet.screen_name, tweet.user_name, &tweet.created_at, tweet.text, tweet.raw_json
+        ])?;
+        Ok(())
+    }
+
+    pub fn get_media_by_tweet_id(&self, tweet_id: i64) -> Result<Vec<models::Media>, Error> {
+        let mut stmt = self.conn.prepare(query::GET_MEDIA_BY_TWEET_ID)?;
+        let iter = stmt.query_map(&[tweet_id], |row| Ok(models::Media::try_from(row)?))?;
+
+        let media: Result<Vec<models::Media>, rusqlite::Error> = iter.collect();
+        Ok(media?)
+    }
+
+    pub fn insert_media(&self, media: &models::Media) -> Result<(), Error> {
+        self.conn.execute(query::INSERT_MEDIA, params![
+            media.tweet_id, media.media_key, media.media_type, media.url, media.path, media.sha256, media.content_type, &media.created_at
+        ])?;
+        Ok(())
+    }
+
+    pub fn delete_media_by_user_id(&self, user_id: i32) -> Result<(), Error> {
+        self.conn.execute(query::DELETE_MEDIA_BY_USER_ID, &[user_id])?;
+        Ok(())
+    }
+
     pub fn delete_tweets_by_user_id(&self, user_id: i32) -> Result<(), Error> {
         let changes = self.conn.execute(query::DELETE_TWEETS_BY_USER_ID, &[user_id])?;
         log::info!("{} tweets were deleted", changes);
 
         Ok(())
     }
+
+    pub fn insert_rate_limit_event(&self, endpoint: &str, reset_at: &DateTime<Utc>) -> Result<(), Error> {
+        self.conn.execute(query::INSERT_RATE_LIMIT_EVENT, params![endpoint, reset_at, &Utc::now()])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_user_since_id() {
+        let db = Db::open(":memory:").unwrap();
+        let user = db.insert_user("vortis_pr", SourceKind::Twitter).unwrap();
+        assert_eq!(None, user.since_id);
+
+        db.update_user_since_id(user.id, 1234567890).unwrap();
+        let user = db.get_user_by_screen_name("vortis_pr").unwrap().unwrap();
+        assert_eq!(Some(1234567890), user.since_id);
+    }
+
+    #[test]
+    fn test_get_user_by_account_id() {
+        let db = Db::open(":memory:").unwrap();
+        let user = db.insert_user("vortis_pr", SourceKind::Twitter).unwrap();
+        assert_eq!(SourceKind::Twitter, user.source_kind);
+        db.update_user_profile(user.id, &twitter::User {
+            id: 1234,
+            screen_name: String::from("vortis_pr"),
+            name: String::from("ヴォルティススタジアム"),
+            protected: false,
+            verified: false,
+        }).unwrap();
+
+        let found = db.get_user_by_account_id(SourceKind::Twitter, 1234).unwrap().unwrap();
+        assert_eq!(user.id, found.id);
+        assert!(db.get_user_by_account_id(SourceKind::Twitter, 5678).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_get_user_by_source_kind_and_screen_name() {
+        let db = Db::open(":memory:").unwrap();
+        let user = db.insert_user("vortis_pr", SourceKind::Twitter).unwrap();
+
+        let found = db.get_user_by_source_kind_and_screen_name(SourceKind::Twitter, "Vortis_PR").unwrap().unwrap();
+        assert_eq!(user.id, found.id);
+        assert!(db.get_user_by_source_kind_and_screen_name(SourceKind::Bluesky, "vortis_pr").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_rename_user() {
+        let db = Db::open(":memory:").unwrap();
+        let user = db.insert_user("vortis_pr", SourceKind::Twitter).unwrap();
+        db.rename_user(user.id, "vortis_pr", "vortis_stadium").unwrap();
+
+        assert!(db.get_user_by_screen_name("vortis_pr").unwrap().is_none());
+        assert_eq!(user.id, db.get_user_by_screen_name("vortis_stadium").unwrap().unwrap().id);
+        let changes = db.get_screen_name_changes_by_user_id(user.id).unwrap();
+        assert_eq!(1, changes.len());
+        assert_eq!("vortis_pr", changes[0].old_screen_name);
+        assert_eq!("vortis_stadium", changes[0].new_screen_name);
+    }
+
+    #[test]
+    fn test_restore_full_texts() {
+        let db = Db::open(":memory:").unwrap();
+        let user = db.insert_user("vortis_pr", SourceKind::Twitter).unwrap();
+        let raw_json = r#"{"id":1,"created_at":"Sat May 09 12:34:56 +0000 2020","text":"RT @tokushima_vortis: テス…","truncated":false,"user":{"id":10,"screen_name":"vortis_pr","name":"ヴォルティススタジアム"},"retweeted_status":{"id":9,"created_at":"Sat May 09 12:34:56 +0000 2020","text":"テストテスト","truncated":false,"user":{"id":20,"screen_name":"tokushima_vortis","name":"徳島ヴォルティス"}}}"#;
+        db.insert_tweet(&models::Tweet {
+            id: 1,
+            user_id: user.id,
+            user_name: String::from("ヴォルティススタジアム"),
+            created_at: Utc::now(),
+            text: String::from("RT @tokushima_vortis: テス…"),
+            raw_json: String::from(raw_json),
+            search_query: None,
+            deleted_at: None,
+            quoted_status_id: None,
+            in_reply_to_status_id: None,
+            in_reply_to_screen_name: None,
+            kind: TweetKind::Original,
+        }).unwrap();
+
+        db.restore_full_texts().unwrap();
+        let tweet = db.get_tweet(1).unwrap().unwrap();
+        assert_eq!("RT @tokushima_vortis: テストテスト", tweet.text);
+    }
+
+    #[test]
+    fn test_extract_entities() {
+        let db = Db::open(":memory:").unwrap();
+        let user = db.insert_user("vortis_pr", SourceKind::Twitter).unwrap();
+        let raw_json = r#"{"id":1,"created_at":"Sat May 09 12:34:56 +0000 2020","full_text":"https://t.co/abc","user":{"id":10,"screen_name":"vortis_pr","name":"ヴォルティススタジアム"},"entities":{"urls":[{"url":"https://t.co/abc","expanded_url":"https://www.vortis.jp/","display_url":"vortis.jp"}]}}"#;
+        db.insert_tweet(&models::Tweet {
+            id: 1,
+            user_id: user.id,
+            user_name: String::from("ヴォルティススタジアム"),
+            created_at: Utc::now(),
+            text: String::from("https://t.co/abc"),
+            raw_json: String::from(raw_json),
+            search_query: None,
+            deleted_at: None,
+            quoted_status_id: None,
+            in_reply_to_status_id: None,
+            in_reply_to_screen_name: None,
+            kind: TweetKind::Original,
+        }).unwrap();
+
+        db.extract_entities().unwrap();
+        let urls = db.get_urls_by_tweet_id(1).unwrap();
+        assert_eq!(1, urls.len());
+        assert_eq!("https://www.vortis.jp/", urls[0].expanded_url);
+    }
+
+    #[test]
+    fn test_classify_tweets() {
+        let db = Db::open(":memory:").unwrap();
+        let user = db.insert_user("vortis_pr", SourceKind::Twitter).unwrap();
+        let raw_jsons = [
+            r#"{"id":1,"created_at":"Sat May 09 12:34:56 +0000 2020","full_text":"テスト","user":{"id":10,"screen_name":"vortis_pr","name":"ヴォルティススタジアム"}}"#,
+            r#"{"id":2,"created_at":"Sat May 09 12:34:56 +0000 2020","full_text":"RT @tokushima_vortis: テスト","user":{"id":10,"screen_name":"vortis_pr","name":"ヴォルティススタジアム"},"retweeted_status":{"id":9,"created_at":"Sat May 09 12:34:56 +0000 2020","full_text":"テスト","user":{"id":20,"screen_name":"tokushima_vortis","name":"徳島ヴォルティス"}}}"#,
+            r#"{"id":3,"created_at":"Sat May 09 12:34:56 +0000 2020","full_text":"続き","user":{"id":10,"screen_name":"vortis_pr","name":"ヴォルティススタジアム"},"in_reply_to_status_id":1,"in_reply_to_user_id":10}"#,
+            r#"{"id":"4","text":"テスト","author_id":"10","created_at":"2020-05-09T12:34:56.000Z","referenced_tweets":[{"type":"quoted","id":"9"}]}"#,
+        ];
+        for (i, raw_json) in raw_jsons.iter().enumerate() {
+            db.insert_tweet(&models::Tweet {
+                id: i as i64 + 1,
+                user_id: user.id,
+                user_name: String::from("ヴォルティススタジアム"),
+                created_at: Utc::now(),
+                text: String::from("テスト"),
+                raw_json: String::from(*raw_json),
+                search_query: None,
+                deleted_at: None,
+                quoted_status_id: None,
+                in_reply_to_status_id: None,
+                in_reply_to_screen_name: None,
+                kind: TweetKind::Original,
+            }).unwrap();
+        }
+
+        db.classify_tweets().unwrap();
+        let kinds: Vec<TweetKind> = (1..=4).map(|id| db.get_tweet(id).unwrap().unwrap().kind).collect();
+        assert_eq!(vec![TweetKind::Original, TweetKind::Retweet, TweetKind::SelfThread, TweetKind::Quote], kinds);
+
+        let tweets = db.get_tweets_by_user_id_and_kinds(user.id, &[TweetKind::Retweet, TweetKind::Quote], 10).unwrap();
+        let mut ids: Vec<i64> = tweets.iter().map(|tweet| tweet.id).collect();
+        ids.sort();
+        assert_eq!(vec![2, 4], ids);
+    }
 }
@@ -3,11 +3,35 @@ use std::convert::TryFrom;
 use chrono::{DateTime, Utc};
 use rusqlite::Row;
 
-#[derive(Debug)]
+use crate::source::{SourceKind, TweetKind};
+
+#[derive(Debug, Clone)]
 pub struct User {
     pub id: i32,
     pub screen_name: String,
     pub created_at: DateTime<Utc>,
+    pub since_id: Option<i64>,
+    pub backfill_max_id: Option<i64>,
+    pub checked_at: Option<DateTime<Utc>>,
+    // kept over renames
+    pub account_id: Option<i64>,
+    pub name: Option<String>,
+    pub protected: bool,
+    pub verified: bool,
+    // false for a user who is watched only as a member of lists
+    pub watched: bool,
+    pub source_kind: SourceKind,
+    // position of the next fetch for backends which don't page by since_id
+    pub cursor: Option<String>,
+    pub watch_likes: bool,
+}
+
+#[derive(Debug)]
+pub struct List {
+    pub id: i32,
+    pub owner: String,
+    pub slug: String,
+    pub since_id: Option<i64>,
 }
 
 #[derive(Debug)]
@@ -17,8 +41,63 @@ pub struct Tweet {
     pub user_name: String,
     pub created_at: DateTime<Utc>,
     pub text: String,
-    pub retweets: i32,
     pub raw_json: String,
+    pub search_query: Option<String>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub quoted_status_id: Option<i64>,
+    pub in_reply_to_status_id: Option<i64>,
+    pub in_reply_to_screen_name: Option<String>,
+    pub kind: TweetKind,
+}
+
+#[derive(Debug)]
+pub struct ReferencedTweet {
+    pub id: i64,
+    pub screen_name: String,
+    pub user_name: String,
+    pub created_at: DateTime<Utc>,
+    pub text: String,
+    pub raw_json: String,
+}
+
+#[derive(Debug)]
+pub struct Like {
+    pub tweet_id: i64,
+    // when the like was found, as the API does not tell when the tweet was liked
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct Search {
+    pub id: i32,
+    pub query: String,
+    pub since_id: Option<i64>,
+}
+
+#[derive(Debug)]
+pub struct Url {
+    pub url: String,
+    pub expanded_url: String,
+}
+
+#[derive(Debug)]
+pub struct Media {
+    pub tweet_id: i64,
+    pub media_key: String,
+    pub media_type: String,
+    pub url: String,
+    // relative to the media directory
+    pub path: String,
+    pub sha256: String,
+    pub content_type: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct ScreenNameChange {
+    pub old_screen_name: String,
+    pub new_screen_name: String,
+    pub changed_at: DateTime<Utc>,
 }
 
 impl<'a, 'stmt> TryFrom<&'a Row<'stmt>> for User {
@@ -29,6 +108,30 @@ impl<'a, 'stmt> TryFrom<&'a Row<'stmt>> for User {
             id: row.get(0)?,
             screen_name: row.get(1)?,
             created_at: row.get(2)?,
+            since_id: row.get(3)?,
+            backfill_max_id: row.get(4)?,
+            checked_at: row.get(5)?,
+            account_id: row.get(6)?,
+            name: row.get(7)?,
+            protected: row.get(8)?,
+            verified: row.get(9)?,
+            watched: row.get(10)?,
+            source_kind: row.get(11)?,
+            cursor: row.get(12)?,
+            watch_likes: row.get(13)?,
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a Row<'_>> for List {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &'a Row<'_>) -> Result<Self, Self::Error> {
+        Ok(List {
+            id: row.get(0)?,
+            owner: row.get(1)?,
+            slug: row.get(2)?,
+            since_id: row.get(3)?,
         })
     }
 }
@@ -43,8 +146,91 @@ impl<'a> TryFrom<&'a Row<'_>> for Tweet {
             user_name: row.get(2)?,
             created_at: row.get(3)?,
             text: row.get(4)?,
-            retweets: row.get(5)?,
             raw_json: row.get(6)?,
+            search_query: row.get(7)?,
+            deleted_at: row.get(8)?,
+            quoted_status_id: row.get(9)?,
+            in_reply_to_status_id: row.get(10)?,
+            in_reply_to_screen_name: row.get(11)?,
+            kind: row.get(12)?,
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a Row<'_>> for Like {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &'a Row<'_>) -> Result<Self, Self::Error> {
+        Ok(Like {
+            tweet_id: row.get(1)?,
+            created_at: row.get(2)?,
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a Row<'_>> for Search {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &'a Row<'_>) -> Result<Self, Self::Error> {
+        Ok(Search {
+            id: row.get(0)?,
+            query: row.get(1)?,
+            since_id: row.get(2)?,
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a Row<'_>> for Url {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &'a Row<'_>) -> Result<Self, Self::Error> {
+        Ok(Url {
+            url: row.get(0)?,
+            expanded_url: row.get(1)?,
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a Row<'_>> for ReferencedTweet {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &'a Row<'_>) -> Result<Self, Self::Error> {
+        Ok(ReferencedTweet {
+            id: row.get(0)?,
+            screen_name: row.get(1)?,
+            user_name: row.get(2)?,
+            created_at: row.get(3)?,
+            text: row.get(4)?,
+            raw_json: row.get(5)?,
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a Row<'_>> for Media {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &'a Row<'_>) -> Result<Self, Self::Error> {
+        Ok(Media {
+            tweet_id: row.get(0)?,
+            media_key: row.get(1)?,
+            media_type: row.get(2)?,
+            url: row.get(3)?,
+            path: row.get(4)?,
+            sha256: row.get(5)?,
+            content_type: row.get(6)?,
+            created_at: row.get(7)?,
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a Row<'_>> for ScreenNameChange {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &'a Row<'_>) -> Result<Self, Self::Error> {
+        Ok(ScreenNameChange {
+            old_screen_name: row.get(0)?,
+            new_screen_name: row.get(1)?,
+            changed_at: row.get(2)?,
         })
     }
 }
@@ -10,6 +10,14 @@ pub const COMMIT_TRANSACTION: &'static str = r#"
 COMMIT TRANSACTION;
 "#;
 
+pub const ROLLBACK_TRANSACTION: &'static str = r#"
+ROLLBACK TRANSACTION;
+"#;
+
+pub const GET_SCHEMA_VERSION: &'static str = r#"
+PRAGMA user_version;
+"#;
+
 pub const CREATE_USERS_TABLE: &'static str = r#"
 CREATE TABLE IF NOT EXISTS users (
         id          INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -30,6 +38,144 @@ CREATE TABLE IF NOT EXISTS tweets (
 );
 "#;
 
+pub const CREATE_RATE_LIMIT_EVENTS_TABLE: &'static str = r#"
+CREATE TABLE IF NOT EXISTS rate_limit_events (
+        id          INTEGER PRIMARY KEY AUTOINCREMENT,
+        endpoint    TEXT NOT NULL,
+        reset_at    DATETIME NOT NULL,
+        created_at  DATETIME NOT NULL
+);
+"#;
+
+pub const CREATE_URLS_TABLE: &'static str = r#"
+CREATE TABLE IF NOT EXISTS urls (
+        tweet_id     INTEGER NOT NULL,
+        url          TEXT NOT NULL,
+        expanded_url TEXT,
+        display_url  TEXT
+);
+"#;
+
+pub const CREATE_HASHTAGS_TABLE: &'static str = r#"
+CREATE TABLE IF NOT EXISTS hashtags (
+        tweet_id    INTEGER NOT NULL,
+        tag         TEXT NOT NULL
+);
+"#;
+
+pub const CREATE_USER_MENTIONS_TABLE: &'static str = r#"
+CREATE TABLE IF NOT EXISTS user_mentions (
+        tweet_id    INTEGER NOT NULL,
+        user_id     INTEGER NOT NULL,
+        screen_name TEXT NOT NULL
+);
+"#;
+
+pub const CREATE_SYMBOLS_TABLE: &'static str = r#"
+CREATE TABLE IF NOT EXISTS symbols (
+        tweet_id    INTEGER NOT NULL,
+        symbol      TEXT NOT NULL
+);
+"#;
+
+pub const CREATE_MEDIA_TABLE: &'static str = r#"
+CREATE TABLE IF NOT EXISTS media (
+        tweet_id     INTEGER NOT NULL,
+        media_key    TEXT NOT NULL,
+        media_type   TEXT NOT NULL,
+        url          TEXT NOT NULL,
+        path         TEXT NOT NULL,
+        sha256       TEXT NOT NULL,
+        content_type TEXT,
+        created_at   TEXT NOT NULL,
+        PRIMARY KEY (tweet_id, media_key)
+);
+"#;
+
+pub const CREATE_REFERENCED_TWEETS_TABLE: &'static str = r#"
+CREATE TABLE IF NOT EXISTS referenced_tweets (
+        id          INTEGER PRIMARY KEY,
+        screen_name TEXT NOT NULL,
+        user_name   TEXT NOT NULL,
+        created_at  DATETIME NOT NULL,
+        text        TEXT NOT NULL,
+        raw_json    TEXT NOT NULL
+);
+"#;
+
+pub const CREATE_SCREEN_NAME_CHANGES_TABLE: &'static str = r#"
+CREATE TABLE IF NOT EXISTS screen_name_changes (
+        id              INTEGER PRIMARY KEY AUTOINCREMENT,
+        user_id         INTEGER NOT NULL,
+        old_screen_name TEXT NOT NULL,
+        new_screen_name TEXT NOT NULL,
+        changed_at      DATETIME NOT NULL
+);
+"#;
+
+pub const CREATE_LIKES_TABLE: &'static str = r#"
+CREATE TABLE IF NOT EXISTS likes (
+        user_id    INTEGER NOT NULL,
+        tweet_id   INTEGER NOT NULL,
+        created_at DATETIME NOT NULL,
+        PRIMARY KEY (user_id, tweet_id)
+);
+"#;
+
+pub const CREATE_LISTS_TABLE: &'static str = r#"
+CREATE TABLE IF NOT EXISTS lists (
+        id         INTEGER PRIMARY KEY AUTOINCREMENT,
+        owner      VARCHAR NOT NULL,
+        slug       VARCHAR NOT NULL,
+        since_id   INTEGER,
+        created_at DATETIME NOT NULL,
+        checked_at DATETIME,
+        UNIQUE (owner, slug)
+);
+"#;
+
+pub const CREATE_LIST_MEMBERS_TABLE: &'static str = r#"
+CREATE TABLE IF NOT EXISTS list_members (
+        list_id INTEGER NOT NULL,
+        user_id INTEGER NOT NULL,
+        PRIMARY KEY (list_id, user_id)
+);
+"#;
+
+pub const CREATE_SEARCHES_TABLE: &'static str = r#"
+CREATE TABLE IF NOT EXISTS searches (
+        id         INTEGER PRIMARY KEY AUTOINCREMENT,
+        query      TEXT NOT NULL UNIQUE,
+        since_id   INTEGER,
+        created_at DATETIME NOT NULL,
+        checked_at DATETIME
+);
+"#;
+
+pub const CREATE_INDEX_TWEET_ID_ON_URLS: &'static str = r#"
+CREATE INDEX IF NOT EXISTS index_tweet_id_on_urls ON urls (
+    tweet_id
+)
+"#;
+
+pub const CREATE_INDEX_TWEET_ID_ON_HASHTAGS: &'static str = r#"
+CREATE INDEX IF NOT EXISTS index_tweet_id_on_hashtags ON hashtags (
+    tweet_id
+)
+"#;
+
+pub const CREATE_INDEX_TWEET_ID_ON_USER_MENTIONS: &'static str = r#"
+CREATE INDEX IF NOT EXISTS index_tweet_id_on_user_mentions ON user_mentions (
+    tweet_id
+)
+"#;
+
+pub const CREATE_INDEX_TWEET_ID_ON_SYMBOLS: &'static str = r#"
+CREATE INDEX IF NOT EXISTS index_tweet_id_on_symbols ON symbols (
+    tweet_id
+)
+"#;
+
 pub const CREATE_INDEX_USER_ID_ON_TWEETS: &'static str = r#"
 CREATE INDEX IF NOT EXISTS index_user_id ON tweets (
     user_id
@@ -42,12 +188,117 @@ CREATE INDEX IF NOT EXISTS index_created_at ON tweets (
 )
 "#;
 
+pub const ADD_SINCE_ID_TO_USERS: &'static str = r#"
+ALTER TABLE users ADD COLUMN since_id INTEGER
+"#;
+
+pub const ADD_BACKFILL_MAX_ID_TO_USERS: &'static str = r#"
+ALTER TABLE users ADD COLUMN backfill_max_id INTEGER
+"#;
+
+pub const ADD_CHECKED_AT_TO_USERS: &'static str = r#"
+ALTER TABLE users ADD COLUMN checked_at DATETIME
+"#;
+
+pub const ADD_ACCOUNT_ID_TO_USERS: &'static str = r#"
+ALTER TABLE users ADD COLUMN account_id INTEGER
+"#;
+
+pub const ADD_NAME_TO_USERS: &'static str = r#"
+ALTER TABLE users ADD COLUMN name TEXT
+"#;
+
+pub const ADD_PROTECTED_TO_USERS: &'static str = r#"
+ALTER TABLE users ADD COLUMN protected INTEGER NOT NULL DEFAULT 0
+"#;
+
+pub const ADD_VERIFIED_TO_USERS: &'static str = r#"
+ALTER TABLE users ADD COLUMN verified INTEGER NOT NULL DEFAULT 0
+"#;
+
+pub const CREATE_INDEX_ACCOUNT_ID_ON_USERS: &'static str = r#"
+CREATE UNIQUE INDEX IF NOT EXISTS index_account_id_on_users ON users (
+    account_id
+)
+"#;
+
+pub const ADD_WATCHED_TO_USERS: &'static str = r#"
+ALTER TABLE users ADD COLUMN watched INTEGER NOT NULL DEFAULT 1
+"#;
+
+pub const ADD_SOURCE_KIND_TO_USERS: &'static str = r#"
+ALTER TABLE users ADD COLUMN source_kind TEXT NOT NULL DEFAULT 'twitter'
+"#;
+
+pub const DROP_INDEX_ACCOUNT_ID_ON_USERS: &'static str = r#"
+DROP INDEX IF EXISTS index_account_id_on_users
+"#;
+
+pub const CREATE_INDEX_SOURCE_KIND_ACCOUNT_ID_ON_USERS: &'static str = r#"
+CREATE UNIQUE INDEX IF NOT EXISTS index_source_kind_account_id_on_users ON users (
+    source_kind,
+    account_id
+)
+"#;
+
+pub const ADD_CURSOR_TO_USERS: &'static str = r#"
+ALTER TABLE users ADD COLUMN cursor TEXT
+"#;
+
+pub const ADD_WATCH_LIKES_TO_USERS: &'static str = r#"
+ALTER TABLE users ADD COLUMN watch_likes INTEGER NOT NULL DEFAULT 0
+"#;
+
+pub const CREATE_NEW_USERS_TABLE: &'static str = r#"
+CREATE TABLE new_users (
+        id              INTEGER PRIMARY KEY AUTOINCREMENT,
+        screen_name     VARCHAR NOT NULL,
+        created_at      DATETIME NOT NULL,
+        since_id        INTEGER,
+        backfill_max_id INTEGER,
+        checked_at      DATETIME,
+        account_id      INTEGER,
+        name            TEXT,
+        protected       INTEGER NOT NULL DEFAULT 0,
+        verified        INTEGER NOT NULL DEFAULT 0,
+        watched         INTEGER NOT NULL DEFAULT 1,
+        source_kind     TEXT NOT NULL DEFAULT 'twitter',
+        cursor          TEXT,
+        watch_likes     INTEGER NOT NULL DEFAULT 0,
+        UNIQUE(source_kind, screen_name)
+)
+"#;
+
+pub const COPY_USERS_TO_NEW_USERS: &'static str = r#"
+INSERT INTO new_users SELECT * FROM users
+"#;
+
+pub const DROP_USERS_TABLE: &'static str = r#"
+DROP TABLE users
+"#;
+
+pub const RENAME_NEW_USERS_TO_USERS: &'static str = r#"
+ALTER TABLE new_users RENAME TO users
+"#;
+
 pub const GET_ALL_USERS: &'static str = r#"
 SELECT * FROM users
 "#;
 
+pub const GET_USERS_ORDER_BY_CHECKED_AT: &'static str = r#"
+SELECT * FROM users WHERE watched=1 ORDER BY checked_at IS NOT NULL, checked_at
+"#;
+
 pub const GET_USER_BY_SCREEN_NAME: &'static str = r#"
-SELECT * FROM users WHERE screen_name=?1
+SELECT * FROM users WHERE screen_name=?1 COLLATE NOCASE
+"#;
+
+pub const GET_USER_BY_SOURCE_KIND_AND_SCREEN_NAME: &'static str = r#"
+SELECT * FROM users WHERE source_kind=?1 AND screen_name=?2 COLLATE NOCASE
+"#;
+
+pub const GET_USER_BY_ACCOUNT_ID: &'static str = r#"
+SELECT * FROM users WHERE source_kind=?1 AND account_id=?2
 "#;
 
 pub const GET_USER_BY_ROW_ID: &'static str = r#"
@@ -55,17 +306,129 @@ SELECT * FROM users WHERE ROWID=?1
 "#;
 
 pub const INSERT_USER: &'static str = r#"
-INSERT INTO users(screen_name, created_at) VALUES (?1, ?2)
+INSERT INTO users(screen_name, source_kind, created_at) VALUES (?1, ?2, ?3)
+"#;
+
+pub const UPDATE_USER_PROFILE: &'static str = r#"
+UPDATE users SET account_id=?2, screen_name=?3, name=?4, protected=?5, verified=?6 WHERE id=?1
+"#;
+
+pub const UPDATE_USER_SCREEN_NAME: &'static str = r#"
+UPDATE users SET screen_name=?2 WHERE id=?1
+"#;
+
+pub const INSERT_SCREEN_NAME_CHANGE: &'static str = r#"
+INSERT INTO screen_name_changes(user_id, old_screen_name, new_screen_name, changed_at) VALUES (?1, ?2, ?3, ?4)
+"#;
+
+pub const GET_SCREEN_NAME_CHANGES_BY_USER_ID: &'static str = r#"
+SELECT old_screen_name, new_screen_name, changed_at FROM screen_name_changes WHERE user_id=?1 ORDER BY id
+"#;
+
+pub const DELETE_SCREEN_NAME_CHANGES_BY_USER_ID: &'static str = r#"
+DELETE FROM screen_name_changes WHERE user_id=?1
+"#;
+
+pub const UPDATE_USER_WATCHED: &'static str = r#"
+UPDATE users SET watched=?2 WHERE id=?1
+"#;
+
+pub const UPDATE_USER_WATCH_LIKES: &'static str = r#"
+UPDATE users SET watch_likes=?2 WHERE id=?1
+"#;
+
+pub const UPDATE_USER_SINCE_ID: &'static str = r#"
+UPDATE users SET since_id=?2 WHERE id=?1
+"#;
+
+pub const UPDATE_USER_CURSOR: &'static str = r#"
+UPDATE users SET cursor=?2 WHERE id=?1
+"#;
+
+pub const UPDATE_USER_BACKFILL_MAX_ID: &'static str = r#"
+UPDATE users SET backfill_max_id=?2 WHERE id=?1
+"#;
+
+pub const UPDATE_USER_CHECKED_AT: &'static str = r#"
+UPDATE users SET checked_at=?2 WHERE id=?1
 "#;
 
 pub const DELETE_USER: &'static str = r#"
 DELETE FROM users WHERE ID=?
 "#;
 
+pub const ADD_SEARCH_QUERY_TO_TWEETS: &'static str = r#"
+ALTER TABLE tweets ADD COLUMN search_query TEXT
+"#;
+
+pub const ADD_DELETED_AT_TO_TWEETS: &'static str = r#"
+ALTER TABLE tweets ADD COLUMN deleted_at DATETIME
+"#;
+
+pub const ADD_QUOTED_STATUS_ID_TO_TWEETS: &'static str = r#"
+ALTER TABLE tweets ADD COLUMN quoted_status_id INTEGER
+"#;
+
+pub const ADD_IN_REPLY_TO_STATUS_ID_TO_TWEETS: &'static str = r#"
+ALTER TABLE tweets ADD COLUMN in_reply_to_status_id INTEGER
+"#;
+
+pub const ADD_IN_REPLY_TO_SCREEN_NAME_TO_TWEETS: &'static str = r#"
+ALTER TABLE tweets ADD COLUMN in_reply_to_screen_name TEXT
+"#;
+
+pub const UPDATE_TWEET_REFERENCES: &'static str = r#"
+UPDATE tweets SET quoted_status_id=?2, in_reply_to_status_id=?3, in_reply_to_screen_name=?4 WHERE id=?1
+"#;
+
+pub const ADD_KIND_TO_TWEETS: &'static str = r#"
+ALTER TABLE tweets ADD COLUMN kind TEXT NOT NULL DEFAULT 'original'
+"#;
+
+pub const UPDATE_TWEET_KIND: &'static str = r#"
+UPDATE tweets SET kind=?2, retweets=?3 WHERE id=?1
+"#;
+
+pub const GET_REFERENCED_TWEET: &'static str = r#"
+SELECT * FROM referenced_tweets WHERE id=?1
+"#;
+
+pub const GET_REFERENCED_TWEETS_BY_TWEET_ID: &'static str = r#"
+SELECT r.* FROM referenced_tweets r JOIN tweets t ON r.id IN (t.quoted_status_id, t.in_reply_to_status_id) WHERE t.id=?1
+"#;
+
+pub const INSERT_REFERENCED_TWEET: &'static str = r#"
+INSERT OR REPLACE INTO referenced_tweets(id, screen_name, user_name, created_at, text, raw_json) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+"#;
+
+pub const GET_RECENT_TWEET_IDS_BY_USER_ID: &'static str = r#"
+SELECT id FROM tweets WHERE user_id=?1 AND deleted_at IS NULL ORDER BY id desc LIMIT ?2
+"#;
+
+pub const GET_DELETED_TWEETS_BY_USER_ID: &'static str = r#"
+SELECT * FROM tweets WHERE user_id=?1 AND deleted_at IS NOT NULL ORDER BY deleted_at desc LIMIT ?2
+"#;
+
+pub const UPDATE_TWEET_DELETED_AT: &'static str = r#"
+UPDATE tweets SET deleted_at=?2 WHERE id=?1
+"#;
+
+pub const GET_TWEETS_BY_SEARCH_QUERY: &'static str = r#"
+SELECT * FROM tweets WHERE search_query=?1 ORDER BY created_at desc LIMIT ?2
+"#;
+
 pub const GET_TWEETS_BY_USER_ID: &'static str = r#"
 SELECT * FROM tweets WHERE user_id=?1 ORDER BY created_at desc LIMIT ?2
 "#;
 
+pub const GET_TWEETS_BY_USER_ID_AND_KINDS: &'static str = r#"
+SELECT * FROM tweets WHERE user_id=?1 AND instr(?2, ',' || kind || ',') > 0 ORDER BY created_at desc LIMIT ?3
+"#;
+
+pub const GET_OLDEST_TWEET_ID_BY_USER_ID: &'static str = r#"
+SELECT MIN(id) FROM tweets WHERE user_id=?1
+"#;
+
 pub const GET_TWEET: &'static str = r#"
 SELECT * FROM tweets WHERE id=?1
 "#;
@@ -74,10 +437,162 @@ pub const GET_TWEET_BY_ROW_ID: &'static str = r#"
 SELECT * FROM tweets WHERE ROWID=?1
 "#;
 
+pub const GET_ALL_TWEET_RAW_JSONS: &'static str = r#"
+SELECT id, text, raw_json FROM tweets
+"#;
+
+pub const UPDATE_TWEET_TEXT: &'static str = r#"
+UPDATE tweets SET text=?2 WHERE id=?1
+"#;
+
 pub const INSERT_TWEET: &'static str = r#"
-INSERT INTO tweets(id,user_id,user_name,created_at,text,retweets,raw_json) VALUES (?1,?2,?3,?4,?5,?6,?7)
+INSERT INTO tweets(id,user_id,user_name,created_at,text,retweets,raw_json,search_query,quoted_status_id,in_reply_to_status_id,in_reply_to_screen_name,kind) VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12)
 "#;
 
 pub const DELETE_TWEETS_BY_USER_ID: &'static str = r#"
 DELETE FROM tweets WHERE user_id=?1
 "#;
+
+pub const INSERT_RATE_LIMIT_EVENT: &'static str = r#"
+INSERT INTO rate_limit_events(endpoint, reset_at, created_at) VALUES (?1, ?2, ?3)
+"#;
+
+pub const GET_URLS_BY_TWEET_ID: &'static str = r#"
+SELECT url, expanded_url FROM urls WHERE tweet_id=?1
+"#;
+
+pub const INSERT_URL: &'static str = r#"
+INSERT INTO urls(tweet_id, url, expanded_url, display_url) VALUES (?1, ?2, ?3, ?4)
+"#;
+
+pub const INSERT_HASHTAG: &'static str = r#"
+INSERT INTO hashtags(tweet_id, tag) VALUES (?1, ?2)
+"#;
+
+pub const INSERT_USER_MENTION: &'static str = r#"
+INSERT INTO user_mentions(tweet_id, user_id, screen_name) VALUES (?1, ?2, ?3)
+"#;
+
+pub const INSERT_SYMBOL: &'static str = r#"
+INSERT INTO symbols(tweet_id, symbol) VALUES (?1, ?2)
+"#;
+
+pub const DELETE_URLS_BY_USER_ID: &'static str = r#"
+DELETE FROM urls WHERE tweet_id IN (SELECT id FROM tweets WHERE user_id=?1)
+"#;
+
+pub const DELETE_HASHTAGS_BY_USER_ID: &'static str = r#"
+DELETE FROM hashtags WHERE tweet_id IN (SELECT id FROM tweets WHERE user_id=?1)
+"#;
+
+pub const DELETE_USER_MENTIONS_BY_USER_ID: &'static str = r#"
+DELETE FROM user_mentions WHERE tweet_id IN (SELECT id FROM tweets WHERE user_id=?1)
+"#;
+
+pub const DELETE_SYMBOLS_BY_USER_ID: &'static str = r#"
+DELETE FROM symbols WHERE tweet_id IN (SELECT id FROM tweets WHERE user_id=?1)
+"#;
+
+pub const GET_MEDIA_BY_TWEET_ID: &'static str = r#"
+SELECT * FROM media WHERE tweet_id=?1 ORDER BY rowid
+"#;
+
+pub const INSERT_MEDIA: &'static str = r#"
+INSERT OR IGNORE INTO media(tweet_id, media_key, media_type, url, path, sha256, content_type, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+"#;
+
+pub const DELETE_MEDIA_BY_USER_ID: &'static str = r#"
+DELETE FROM media WHERE tweet_id IN (SELECT id FROM tweets WHERE user_id=?1)
+"#;
+
+pub const GET_LISTS_ORDER_BY_CHECKED_AT: &'static str = r#"
+SELECT * FROM lists ORDER BY checked_at IS NOT NULL, checked_at
+"#;
+
+pub const GET_LIST_BY_NAME: &'static str = r#"
+SELECT * FROM lists WHERE owner=?1 COLLATE NOCASE AND slug=?2 COLLATE NOCASE
+"#;
+
+pub const GET_LIST_BY_ROW_ID: &'static str = r#"
+SELECT * FROM lists WHERE ROWID=?1
+"#;
+
+pub const INSERT_LIST: &'static str = r#"
+INSERT INTO lists(owner, slug, created_at) VALUES (?1, ?2, ?3)
+"#;
+
+pub const UPDATE_LIST_SINCE_ID: &'static str = r#"
+UPDATE lists SET since_id=?2 WHERE id=?1
+"#;
+
+pub const UPDATE_LIST_CHECKED_AT: &'static str = r#"
+UPDATE lists SET checked_at=?2 WHERE id=?1
+"#;
+
+pub const DELETE_LIST: &'static str = r#"
+DELETE FROM lists WHERE id=?1
+"#;
+
+pub const GET_LIST_MEMBERS: &'static str = r#"
+SELECT users.* FROM users JOIN list_members ON list_members.user_id=users.id WHERE list_members.list_id=?1 ORDER BY users.id
+"#;
+
+pub const INSERT_LIST_MEMBER: &'static str = r#"
+INSERT OR IGNORE INTO list_members(list_id, user_id) VALUES (?1, ?2)
+"#;
+
+pub const DELETE_LIST_MEMBER: &'static str = r#"
+DELETE FROM list_members WHERE list_id=?1 AND user_id=?2
+"#;
+
+pub const DELETE_LIST_MEMBERS_BY_LIST_ID: &'static str = r#"
+DELETE FROM list_members WHERE list_id=?1
+"#;
+
+pub const DELETE_LIST_MEMBERS_BY_USER_ID: &'static str = r#"
+DELETE FROM list_members WHERE user_id=?1
+"#;
+
+pub const GET_LIKE: &'static str = r#"
+SELECT * FROM likes WHERE user_id=?1 AND tweet_id=?2
+"#;
+
+pub const GET_LIKES_BY_USER_ID: &'static str = r#"
+SELECT * FROM likes WHERE user_id=?1 ORDER BY created_at desc, ROWID desc LIMIT ?2
+"#;
+
+pub const INSERT_LIKE: &'static str = r#"
+INSERT INTO likes(user_id, tweet_id, created_at) VALUES (?1, ?2, ?3)
+"#;
+
+pub const DELETE_LIKES_BY_USER_ID: &'static str = r#"
+DELETE FROM likes WHERE user_id=?1
+"#;
+
+pub const GET_SEARCHES_ORDER_BY_CHECKED_AT: &'static str = r#"
+SELECT * FROM searches ORDER BY checked_at IS NOT NULL, checked_at
+"#;
+
+pub const GET_SEARCH_BY_QUERY: &'static str = r#"
+SELECT * FROM searches WHERE query=?1
+"#;
+
+pub const GET_SEARCH_BY_ROW_ID: &'static str = r#"
+SELECT * FROM searches WHERE ROWID=?1
+"#;
+
+pub const INSERT_SEARCH: &'static str = r#"
+INSERT INTO searches(query, created_at) VALUES (?1, ?2)
+"#;
+
+pub const UPDATE_SEARCH_SINCE_ID: &'static str = r#"
+UPDATE searches SET since_id=?2 WHERE id=?1
+"#;
+
+pub const UPDATE_SEARCH_CHECKED_AT: &'static str = r#"
+UPDATE searches SET checked_at=?2 WHERE id=?1
+"#;
+
+pub const DELETE_SEARCH: &'static str = r#"
+DELETE FROM searches WHERE id=?1
+"#;
@@ -3,7 +3,7 @@ use std::io;
 use std::io::Read;
 use std::fmt;
 
-use chrono;
+use chrono::{self, DateTime, Utc};
 use reqwest;
 use serde_json;
 use rusqlite;
@@ -14,6 +14,9 @@ pub enum Error {
     IoError(io::Error),
     ReqwestError(reqwest::Error),
     HttpError(reqwest::StatusCode, String),
+    RateLimitError(String, DateTime<Utc>),
+    UserNotFoundError(String),
+    ListNotFoundError(String),
     SerdeError(serde_json::error::Error),
     ConfigError(&'static str),
     SqliteError(rusqlite::Error),
@@ -21,6 +24,11 @@ pub enum Error {
     ChronoParseError(chrono::ParseError),
     UnknownCommandError,
     CommandError(String),
+    XmlError(roxmltree::Error),
+    FeedError(String),
+    MastodonError(String),
+    BlueskyError(String),
+    PayloadError(String),
 }
 
 impl fmt::Display for Error {
@@ -31,6 +39,9 @@ impl fmt::Display for Error {
             // Error::HyperError(ref err) => write!(f, "Hyper error: {}", err),
             Error::ReqwestError(ref err) => write!(f, "Reqwest error: {}", err),
             Error::HttpError(ref status, ref msg) => write!(f, "HTTP error: {}\n{}", status, msg),
+            Error::RateLimitError(ref endpoint, ref reset_at) => write!(f, "Rate limit error: {} is limited until {}", endpoint, reset_at),
+            Error::UserNotFoundError(ref screen_name) => write!(f, "User not found error: {} does not exist or is suspended", screen_name),
+            Error::ListNotFoundError(ref list) => write!(f, "List not found error: {} does not exist", list),
             Error::SerdeError(ref err) => write!(f, "Serde error: {}", err),
             // Error::NativeTlsError(ref err) => write!(f, "NativeTls error: {}", err),
             Error::ConfigError(msg) => write!(f, "Config error: {}", msg),
@@ -39,6 +50,11 @@ impl fmt::Display for Error {
             Error::ChronoParseError(ref err) => write!(f, "Chrono Parse error: {}", err),
             Error::UnknownCommandError => write!(f, "Unknown Command"),
             Error::CommandError(ref msg) => write!(f, "Command error: {}", msg),
+            Error::XmlError(ref err) => write!(f, "XML error: {}", err),
+            Error::FeedError(ref msg) => write!(f, "Feed error: {}", msg),
+            Error::MastodonError(ref msg) => write!(f, "Mastodon error: {}", msg),
+            Error::BlueskyError(ref msg) => write!(f, "Bluesky error: {}", msg),
+            Error::PayloadError(ref msg) => write!(f, "Unexpected payload: {}", msg),
         }
     }
 }
@@ -134,3 +150,9 @@ impl From<chrono::ParseError> for Error {
         Error::ChronoParseError(err)
     }
 }
+
+impl From<roxmltree::Error> for Error {
+    fn from(err: roxmltree::Error) -> Error {
+        Error::XmlError(err)
+    }
+}
@@ -0,0 +1,354 @@
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use roxmltree::Node;
+use serde_json::{self, json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+use crate::html;
+use crate::source::TweetKind;
+use crate::twitter::{self, USER_AGENT};
+
+// time zone abbreviations used in feeds but not defined by RFC 2822
+const ZONE_OFFSETS: &'static [(&'static str, &'static str)] = &[
+    ("UTC", "+0000"),
+    ("Z", "+0000"),
+    ("BST", "+0100"),
+    ("CET", "+0100"),
+    ("CEST", "+0200"),
+    ("EET", "+0200"),
+    ("EEST", "+0300"),
+    ("IST", "+0530"),
+    ("SGT", "+0800"),
+    ("HKT", "+0800"),
+    ("JST", "+0900"),
+    ("KST", "+0900"),
+    ("AEST", "+1000"),
+    ("AEDT", "+1100"),
+];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Feed {
+    pub title: String,
+    pub link: String,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug)]
+pub struct Entry {
+    // or the link when the feed has neither
+    pub guid: String,
+    pub title: String,
+    pub link: String,
+    pub published: DateTime<Utc>,
+    pub summary: String,
+}
+
+#[derive(Debug)]
+pub struct FeedClient {
+    pub client: Client,
+}
+
+impl Validators {
+    pub fn from_cursor(cursor: Option<&str>) -> Validators {
+        let value: Value = cursor.and_then(|cursor| serde_json::from_str(cursor).ok()).unwrap_or(Value::Null);
+        Validators {
+            etag: value["etag"].as_str().map(String::from),
+            last_modified: value["last_modified"].as_str().map(String::from),
+        }
+    }
+
+    pub fn to_cursor(&self) -> String {
+        json!({ "etag": self.etag, "last_modified": self.last_modified }).to_string()
+    }
+}
+
+impl FeedClient {
+    pub fn new(client: Client) -> FeedClient {
+        FeedClient { client: client }
+    }
+
+    pub fn get_feed(&self, url: &str, validators: &Validators) -> Result<Option<(Feed, Validators)>, Error> {
+        let mut req = self.client.get(url).header(reqwest::header::USER_AGENT, USER_AGENT);
+        if let Some(ref etag) = validators.etag {
+            req = req.header(IF_NONE_MATCH, etag.as_str());
+        }
+        if let Some(ref last_modified) = validators.last_modified {
+            req = req.header(IF_MODIFIED_SINCE, last_modified.as_str());
+        }
+
+        let res = req.send()?;
+        if res.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        if !res.status().is_success() {
+            return Err(Error::from(res));
+        }
+
+        let header = |name| res.headers().get(name).and_then(|value| value.to_str().ok()).map(String::from);
+        let validators = Validators {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        };
+        let body = res.text()?;
+        Ok(Some((parse_feed(&body)?, validators)))
+    }
+}
+
+impl Feed {
+    pub fn profile(&self, url: &str) -> twitter::User {
+        twitter::User {
+            id: item_id(url, ""),
+            screen_name: String::from(url),
+            name: if self.title.is_empty() { String::from(url) } else { self.title.clone() },
+            protected: false,
+            verified: false,
+        }
+    }
+
+    pub fn to_tweets(&self, url: &str) -> Vec<twitter::Tweet> {
+        let profile = self.profile(url);
+        let mut entries: Vec<&Entry> = self.entries.iter().collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.published));
+        entries.into_iter().map(|entry| {
+            let summary = html::to_text(&entry.summary);
+            let text = if summary.is_empty() || summary == entry.title {
+                entry.title.clone()
+            } else {
+                format!("{}\n\n{}", entry.title, summary)
+            };
+            let raw_json = json!({
+                "guid": entry.guid,
+                "title": entry.title,
+                // an entry without a link is linked to the site of the feed.
+                "link": if entry.link.is_empty() { &self.link } else { &entry.link },
+                "published": entry.published.to_rfc3339(),
+                "summary": entry.summary,
+            });
+
+            twitter::Tweet {
+                id: item_id(url, &entry.guid),
+                created_at: entry.published,
+                user_id: profile.id,
+                user_name: profile.name.clone(),
+                screen_name: profile.screen_name.clone(),
+                text: text,
+                kind: TweetKind::Original,
+                retweeted_status_id: 0,
+                entities: twitter::Entities::default(),
+                references: twitter::References::default(),
+                referenced_tweets: Vec::new(),
+                media: Vec::new(),
+                raw_json: raw_json.to_string(),
+            }
+        }).collect()
+    }
+}
+
+// an entry keeps its id when it is edited, so it is notified only once.
+pub fn item_id(url: &str, guid: &str) -> u64 {
+    let digest = Sha256::digest(format!("{}\n{}", url, guid).as_bytes());
+    let id = digest.iter().take(8).fold(0u64, |id, b| id << 8 | *b as u64);
+    id & 0x7fff_ffff_ffff_ffff
+}
+
+pub fn parse_date(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(s) {
+        return Some(date.with_timezone(&Utc));
+    }
+    if let Ok(date) = DateTime::parse_from_rfc2822(s) {
+        return Some(date.with_timezone(&Utc));
+    }
+    if let Some((rest, zone)) = s.rsplit_once(' ') {
+        if let Some((_, offset)) = ZONE_OFFSETS.iter().find(|(name, _)| name.eq_ignore_ascii_case(zone)) {
+            if let Ok(date) = DateTime::parse_from_rfc2822(&format!("{} {}", rest, offset)) {
+                return Some(date.with_timezone(&Utc));
+            }
+        }
+    }
+    // a date without time zone is taken as UTC.
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").ok().map(|date| Utc.from_utc_datetime(&date))
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.is_element() && child.tag_name().name() == name)
+}
+
+fn children<'a, 'input: 'a>(node: Node<'a, 'input>, name: &'a str) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children().filter(move |child| child.is_element() && child.tag_name().name() == name)
+}
+
+fn child_text(node: Node, name: &str) -> String {
+    match child(node, name) {
+        Some(child) => child.descendants().filter(|n| n.is_text()).filter_map(|n| n.text()).collect::<String>().trim().to_string(),
+        None => String::new(),
+    }
+}
+
+fn atom_link(node: Node) -> String {
+    children(node, "link")
+        .find(|link| link.attribute("rel").map(|rel| rel == "alternate").unwrap_or(true))
+        .and_then(|link| link.attribute("href"))
+        .map(String::from)
+        .unwrap_or_default()
+}
+
+fn rss_entry(item: Node) -> Entry {
+    let link = child_text(item, "link");
+    let guid = match child_text(item, "guid") {
+        guid if !guid.is_empty() => guid,
+        _ if !link.is_empty() => link.clone(),
+        _ => child_text(item, "title"),
+    };
+    let date = match child_text(item, "pubDate") {
+        date if !date.is_empty() => date,
+        _ => child_text(item, "date"),
+    };
+    let summary = match child_text(item, "encoded") {
+        content if !content.is_empty() => content,
+        _ => child_text(item, "description"),
+    };
+    Entry {
+        guid: guid,
+        title: html::decode_entities(&child_text(item, "title")),
+        link: link,
+        published: parse_date(&date).unwrap_or_else(Utc::now),
+        summary: summary,
+    }
+}
+
+fn atom_entry(entry: Node) -> Entry {
+    let link = atom_link(entry);
+    let guid = match child_text(entry, "id") {
+        id if !id.is_empty() => id,
+        _ => link.clone(),
+    };
+    let date = match child_text(entry, "published") {
+        date if !date.is_empty() => date,
+        _ => child_text(entry, "updated"),
+    };
+    let summary = match child_text(entry, "content") {
+        content if !content.is_empty() => content,
+        _ => child_text(entry, "summary"),
+    };
+    Entry {
+        guid: guid,
+        title: html::decode_entities(&child_text(entry, "title")),
+        link: link,
+        published: parse_date(&date).unwrap_or_else(Utc::now),
+        summary: summary,
+    }
+}
+
+pub fn parse_feed(xml: &str) -> Result<Feed, Error> {
+    let doc = roxmltree::Document::parse(xml)?;
+    let root = doc.root_element();
+    match root.tag_name().name() {
+        "rss" => {
+            let channel = child(root, "channel").ok_or_else(|| Error::FeedError(String::from("channel is missing")))?;
+            Ok(Feed {
+                title: child_text(channel, "title"),
+                link: child_text(channel, "link"),
+                entries: children(channel, "item").map(rss_entry).collect(),
+            })
+        },
+        "RDF" => {
+            // items of RSS 1.0 are siblings of the channel.
+            let channel = child(root, "channel").ok_or_else(|| Error::FeedError(String::from("channel is missing")))?;
+            Ok(Feed {
+                title: child_text(channel, "title"),
+                link: child_text(channel, "link"),
+                entries: children(root, "item").map(rss_entry).collect(),
+            })
+        },
+        "feed" => Ok(Feed {
+            title: child_text(root, "title"),
+            link: atom_link(root),
+            entries: children(root, "entry").map(atom_entry).collect(),
+        }),
+        name => Err(Error::FeedError(format!("<{}> is not a root of RSS or Atom", name))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rss() {
+        let feed = parse_feed(include_str!("../tests/fixtures/feeds/rss.xml")).unwrap();
+        assert_eq!("徳島ヴォルティス ニュース", feed.title);
+        assert_eq!(2, feed.entries.len());
+
+        let entry = &feed.entries[0];
+        assert_eq!("https://www.vortis.jp/news/2", entry.guid);
+        assert_eq!("ホームゲーム開催のお知らせ", entry.title);
+        assert_eq!("https://www.vortis.jp/news/2?from=rss", entry.link);
+        // declared in JST
+        assert_eq!(Utc.ymd(2020, 5, 1).and_hms(3, 0, 0), entry.published);
+        assert!(entry.summary.contains("<b>鳴門</b>"));
+
+        // the link is the guid of an item without it
+        assert_eq!("https://www.vortis.jp/news/1", feed.entries[1].guid);
+        assert_eq!(Utc.ymd(2020, 4, 30).and_hms(12, 0, 0), feed.entries[1].published);
+    }
+
+    #[test]
+    fn test_parse_atom() {
+        let feed = parse_feed(include_str!("../tests/fixtures/feeds/atom.xml")).unwrap();
+        assert_eq!("Vortis Blog", feed.title);
+        assert_eq!("https://blog.vortis.jp/", feed.link);
+        assert_eq!(2, feed.entries.len());
+
+        let entry = &feed.entries[0];
+        assert_eq!("tag:blog.vortis.jp,2020:entry-2", entry.guid);
+        assert_eq!("https://blog.vortis.jp/entry/2", entry.link);
+        assert_eq!(Utc.ymd(2020, 5, 2).and_hms(1, 30, 0), entry.published);
+
+        // the updated date is used without published one
+        assert_eq!(Utc.ymd(2020, 5, 1).and_hms(9, 0, 0), feed.entries[1].published);
+    }
+
+    #[test]
+    fn test_to_tweets() {
+        let feed = parse_feed(include_str!("../tests/fixtures/feeds/rss.xml")).unwrap();
+        let tweets = feed.to_tweets("https://www.vortis.jp/rss");
+        assert_eq!(item_id("https://www.vortis.jp/rss", "https://www.vortis.jp/news/2"), tweets[0].id);
+        assert_eq!("https://www.vortis.jp/rss", tweets[0].screen_name);
+        assert_eq!("ホームゲーム開催のお知らせ\n\n鳴門 大塚スポーツパークで開催します。", tweets[0].text);
+        assert!(tweets[0].created_at > tweets[1].created_at);
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let expected = Utc.ymd(2020, 5, 1).and_hms(3, 0, 0);
+        assert_eq!(Some(expected), parse_date("Fri, 01 May 2020 12:00:00 +0900"));
+        assert_eq!(Some(expected), parse_date("Fri, 01 May 2020 12:00:00 JST"));
+        assert_eq!(Some(expected), parse_date("Thu, 30 Apr 2020 23:00:00 EDT"));
+        assert_eq!(Some(expected), parse_date("2020-05-01T12:00:00+09:00"));
+        assert_eq!(Some(expected), parse_date("2020-05-01T03:00:00"));
+        assert_eq!(None, parse_date("someday"));
+    }
+
+    #[test]
+    fn test_parse_feed_rejects_other_documents() {
+        assert!(parse_feed("<html><body></body></html>").is_err());
+        assert!(parse_feed("not xml").is_err());
+    }
+
+    #[test]
+    fn test_validators_cursor() {
+        let validators = Validators { etag: Some(String::from("\"abc\"")), last_modified: None };
+        assert_eq!(validators, Validators::from_cursor(Some(&validators.to_cursor())));
+        assert_eq!(Validators::default(), Validators::from_cursor(None));
+    }
+}
@@ -0,0 +1,101 @@
+pub fn to_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&decode_entities(&rest[..start]));
+        rest = &rest[start..];
+        // a "<" which does not start a tag or is never closed is a literal.
+        let opens_tag = rest[1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/' || c == '!');
+        let end = match rest.find('>') {
+            Some(end) if opens_tag => end,
+            _ => {
+                text.push('<');
+                rest = &rest[1..];
+                continue;
+            },
+        };
+        let tag = rest[1..end].trim().to_lowercase();
+        let name = tag.trim_start_matches('/').split(|c: char| c.is_whitespace() || c == '/').next().unwrap_or("");
+        match name {
+            "br" => text.push('\n'),
+            "p" | "div" | "li" | "blockquote" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" if tag.starts_with('/') => text.push_str("\n\n"),
+            _ => (),
+        }
+        rest = &rest[end + 1..];
+    }
+    text.push_str(&decode_entities(rest));
+
+    // collapse the blank lines left by nested blocks.
+    let lines: Vec<&str> = text.lines().map(|line| line.trim_end()).collect();
+    let mut collapsed: Vec<&str> = Vec::new();
+    for line in lines {
+        if line.is_empty() && collapsed.last().map(|last| last.is_empty()).unwrap_or(true) {
+            continue;
+        }
+        collapsed.push(line);
+    }
+    collapsed.join("\n").trim().to_string()
+}
+
+pub fn decode_entities(s: &str) -> String {
+    let mut decoded = String::new();
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = match rest.find(';') {
+            Some(end) if end <= 10 => end,
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+                continue;
+            },
+        };
+        let name = &rest[1..end];
+        let c = match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ if name.starts_with("#x") || name.starts_with("#X") => u32::from_str_radix(&name[2..], 16).ok().and_then(std::char::from_u32),
+            _ if name.starts_with('#') => name[1..].parse().ok().and_then(std::char::from_u32),
+            _ => None,
+        };
+        match c {
+            Some(c) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            },
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            },
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_text() {
+        assert_eq!("徳島ヴォルティス\n勝利！", to_text("<p>徳島ヴォルティス<br>勝利！</p>"));
+        assert_eq!("first\n\nsecond", to_text("<p>first</p><p>second</p>"));
+        assert_eq!("Tom & Jerry <3", to_text("<span class=\"x\">Tom &amp; Jerry &lt;3</span>"));
+        assert_eq!("plain text", to_text("plain text"));
+        assert_eq!("1 < 2", to_text("1 < 2"));
+        assert_eq!("a < b > c", to_text("a < b > c"));
+        assert_eq!("x <y", to_text("<b>x</b> <y"));
+    }
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!("あ'\"", decode_entities("&#x3042;&#39;&quot;"));
+        assert_eq!("AT&T &unknown;", decode_entities("AT&T &unknown;"));
+    }
+}
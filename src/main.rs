@@ -1,7 +1,12 @@
+mod bluesky;
 mod cmd;
 mod config;
 mod db;
 mod error;
+mod feed;
+mod html;
+mod mastodon;
+mod source;
 mod twitter;
 
 use std::io::{self, Write};
@@ -26,23 +31,58 @@ fn main() {
             (@arg consumer_secret: --secret +takes_value "consumer secret")
             (@arg database_file: --db +takes_value "database file")
             (@arg gmail_command: --gmail_command +takes_value "gmail command path")
+            (@arg api_base_url: --api_base_url +takes_value "Twitter API base URL")
+            (@arg api_version: --api_version +takes_value "Twitter API version, 1.1 or 2 [default is 1.1]")
+            (@arg user_auth: --user_auth "authorizes as a user to read protected accounts the user follows")
         )
         (@subcommand add =>
             (about: "Adds screen name to watch updates")
+            (@arg screen_name: required_unless[list feed mastodon bluesky] "screen name")
+            (@arg list: --list +takes_value conflicts_with[screen_name feed mastodon bluesky] "list to watch, given as owner/slug")
+            (@arg feed: --feed +takes_value conflicts_with[screen_name mastodon bluesky] "URL of RSS or Atom feed to watch")
+            (@arg mastodon: --mastodon +takes_value conflicts_with[screen_name bluesky] "Mastodon account to watch, given as @user@instance")
+            (@arg bluesky: --bluesky +takes_value conflicts_with[screen_name] "Bluesky handle to watch, e.g. user.bsky.social")
+            (@arg likes: --likes requires[screen_name] "watches the tweets the screen name likes as well")
+        )
+        (@subcommand auth =>
+            (about: "Shows or revokes cached access token")
+            (@arg revoke: --revoke "revokes cached access token")
+        )
+        (@subcommand backfill =>
+            (about: "Imports older tweets of watched screen name")
             (@arg screen_name: +required "screen name")
+            (@arg max_count: --max +takes_value "max count of tweet [default is 3200]")
         )
         (@subcommand list =>
             (about: "Lists tweets collected currently")
             (@arg screen_name: "screen name")
             (@arg max_count: --max +takes_value "max count of tweet [default is 10]")
+            (@arg renames: --renames "lists screen name changes instead of tweets")
+            (@arg deleted: --deleted conflicts_with[renames] "lists deleted tweets")
+            (@arg kind: --kind +takes_value conflicts_with[renames deleted] "lists tweets of the kinds only, comma separated from original, retweet, reply, quote and self_thread")
+            (@arg likes: --likes conflicts_with[renames deleted kind] "lists tweets the screen name liked")
+            (@arg search: --search +takes_value conflicts_with[screen_name] "lists tweets found by the search query")
         )
         (@subcommand remove =>
             (about: "Removes screen name watching updates")
-            (@arg screen_name: +required "screen name")
+            (@arg screen_name: required_unless[list] "screen name")
+            (@arg list: --list +takes_value conflicts_with[screen_name] "list to stop watching, given as owner/slug")
+            (@arg likes: --likes requires[screen_name] "stops watching only the tweets the screen name likes")
+        )
+        (@subcommand add_search =>
+            (about: "Adds search query to watch matching tweets")
+            (alias: "add-search")
+            (@arg query: +required "search query")
+        )
+        (@subcommand remove_search =>
+            (about: "Removes search query watching matching tweets")
+            (alias: "remove-search")
+            (@arg query: +required "search query")
         )
         (@subcommand check_update =>
             (about: "Checks timeline updates and notify updated tweets")
             (@arg screen_name: "screen name")
+            (@arg wait: --wait "waits for rate limit reset instead of deferring remaining users to next run")
         )
     ).get_matches();
 
@@ -0,0 +1,293 @@
+use chrono::{DateTime, Utc};
+use reqwest::blocking::Client;
+use serde_json::{self, json, Value};
+
+use crate::error::Error;
+use crate::feed;
+use crate::html;
+use crate::source::TweetKind;
+use crate::twitter::{self, USER_AGENT};
+
+pub const MAX_STATUSES_COUNT: u32 = 40;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    // host the API of the account is served on
+    pub instance: String,
+    pub account_id: String,
+    // API id of the newest status seen
+    pub since_id: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct MastodonClient {
+    pub client: Client,
+    pub scheme: String,
+}
+
+impl Location {
+    pub fn from_cursor(cursor: Option<&str>) -> Option<Location> {
+        let value: Value = serde_json::from_str(cursor?).ok()?;
+        Some(Location {
+            instance: String::from(value["instance"].as_str()?),
+            account_id: String::from(value["account_id"].as_str()?),
+            since_id: value["since_id"].as_str().map(String::from),
+        })
+    }
+
+    pub fn to_cursor(&self) -> String {
+        json!({ "instance": self.instance, "account_id": self.account_id, "since_id": self.since_id }).to_string()
+    }
+}
+
+pub fn parse_acct(name: &str) -> Result<(&str, &str), Error> {
+    match name.trim_start_matches('@').split_once('@') {
+        Some((username, domain)) if !username.is_empty() && !domain.is_empty() && !domain.contains('@') => Ok((username, domain)),
+        _ => Err(Error::MastodonError(format!("{} is not an account like @user@instance", name))),
+    }
+}
+
+impl MastodonClient {
+    pub fn new(client: Client, scheme: &str) -> MastodonClient {
+        MastodonClient { client: client, scheme: String::from(scheme) }
+    }
+
+    fn get(&self, host: &str, path: &str, query: &[(&str, String)]) -> Result<Value, Error> {
+        let url = format!("{}://{}{}", self.scheme, host, path);
+        let res = self.client.get(&url)
+            .header(reqwest::header::USER_AGENT, USER_AGENT)
+            .header(reqwest::header::ACCEPT, "application/json")
+            .query(query)
+            .send()?;
+        if !res.status().is_success() {
+            return Err(Error::from(res));
+        }
+        Ok(res.json()?)
+    }
+
+    pub fn webfinger(&self, name: &str) -> Result<(String, String), Error> {
+        let (username, domain) = parse_acct(name)?;
+        let resource = format!("acct:{}@{}", username, domain);
+        let value = self.get(domain, "/.well-known/webfinger", &[("resource", resource)])?;
+
+        // the ActivityPub actor is served by the instance, which may differ from the domain of the account.
+        let href = value["links"].as_array()
+            .and_then(|links| links.iter().find(|link| link["rel"] == "self"))
+            .and_then(|link| link["href"].as_str())
+            .ok_or_else(|| Error::MastodonError(format!("{} has no actor", name)))?;
+        let instance = href.split("://").nth(1).and_then(|rest| rest.split('/').next()).unwrap_or(domain);
+        Ok((String::from(username), String::from(instance)))
+    }
+
+    pub fn lookup_account(&self, name: &str) -> Result<(Location, twitter::User), Error> {
+        let (username, instance) = self.webfinger(name)?;
+        let account = self.get(&instance, "/api/v1/accounts/lookup", &[("acct", username)])?;
+        let location = Location {
+            instance: instance.clone(),
+            account_id: String::from(account["id"].as_str().ok_or_else(|| Error::MastodonError(String::from("account without id")))?),
+            since_id: None,
+        };
+        let profile = parse_account(&account, &instance);
+        Ok((location, profile))
+    }
+
+    pub fn get_statuses(&self, location: &Location, count: Option<u32>) -> Result<(Vec<twitter::Tweet>, Location), Error> {
+        let mut query = vec![("limit", count.unwrap_or(20).min(MAX_STATUSES_COUNT).to_string())];
+        if let Some(ref since_id) = location.since_id {
+            query.push(("since_id", since_id.clone()));
+        }
+        let path = format!("/api/v1/accounts/{}/statuses", location.account_id);
+        let statuses = self.get(&location.instance, &path, &query)?;
+        let statuses = statuses.as_array().ok_or_else(|| Error::MastodonError(String::from("statuses are not an array")))?;
+
+        let tweets = statuses.iter().map(|status| parse_status(status, &location.instance)).collect::<Result<Vec<_>, Error>>()?;
+        // the statuses are all newer than since_id.
+        let newest_id = statuses.iter().filter_map(|status| status["id"].as_str()?.parse::<u64>().ok()).max();
+        let since_id = newest_id.map(|id| id.to_string()).or_else(|| location.since_id.clone());
+        Ok((tweets, Location { since_id: since_id, ..location.clone() }))
+    }
+}
+
+// accounts of different instances may have the same API id.
+pub fn account_key(instance: &str, account_id: &str) -> u64 {
+    feed::item_id(instance, account_id)
+}
+
+// statuses of different instances, and tweets, may have the same id.
+pub fn status_key(instance: &str, status_id: u64) -> u64 {
+    feed::item_id(instance, &status_id.to_string())
+}
+
+fn qualified_acct(account: &Value, instance: &str) -> String {
+    let acct = account["acct"].as_str().unwrap_or("");
+    if acct.contains('@') {
+        String::from(acct)
+    } else {
+        format!("{}@{}", acct, instance)
+    }
+}
+
+fn parse_account(account: &Value, instance: &str) -> twitter::User {
+    let screen_name = qualified_acct(account, instance);
+    let name = match account["display_name"].as_str() {
+        Some(name) if !name.is_empty() => String::from(name),
+        _ => screen_name.clone(),
+    };
+    twitter::User {
+        id: account_key(instance, account["id"].as_str().unwrap_or("")),
+        screen_name: screen_name,
+        name: name,
+        protected: account["locked"].as_bool().unwrap_or(false),
+        verified: false,
+    }
+}
+
+fn parse_id(value: &Value) -> Result<u64, Error> {
+    value.as_str().and_then(|id| id.parse().ok()).ok_or_else(|| Error::MastodonError(format!("invalid id: {}", value)))
+}
+
+fn status_text(status: &Value) -> String {
+    let content = html::to_text(status["content"].as_str().unwrap_or(""));
+    match status["spoiler_text"].as_str() {
+        Some(spoiler) if !spoiler.is_empty() => format!("{}\n\n{}", spoiler, content),
+        _ => content,
+    }
+}
+
+pub fn parse_status(status: &Value, instance: &str) -> Result<twitter::Tweet, Error> {
+    let account = &status["account"];
+    let reblog = &status["reblog"];
+    let (content, text) = if reblog.is_object() {
+        (reblog, format!("RT @{}: {}", qualified_acct(&reblog["account"], instance), status_text(reblog)))
+    } else {
+        (status, status_text(status))
+    };
+
+    let mentions: Vec<&Value> = content["mentions"].as_array().map(|mentions| mentions.iter().collect()).unwrap_or_default();
+    let in_reply_to_screen_name = match content["in_reply_to_account_id"].as_str() {
+        Some(id) if Some(id) == content["account"]["id"].as_str() => qualified_acct(&content["account"], instance),
+        Some(id) => mentions.iter().find(|mention| mention["id"].as_str() == Some(id))
+            .map(|mention| qualified_acct(mention, instance))
+            .unwrap_or_default(),
+        None => String::new(),
+    };
+
+    Ok(twitter::Tweet {
+        id: status_key(instance, parse_id(&status["id"])?),
+        created_at: DateTime::parse_from_rfc3339(status["created_at"].as_str().unwrap_or(""))?.with_timezone(&Utc),
+        user_id: account_key(instance, account["id"].as_str().unwrap_or("")),
+        user_name: parse_account(account, instance).name,
+        screen_name: qualified_acct(account, instance),
+        text: text,
+        kind: status_kind(status),
+        retweeted_status_id: if reblog.is_object() { status_key(instance, parse_id(&reblog["id"])?) } else { 0 },
+        entities: twitter::Entities {
+            urls: Vec::new(),
+            hashtags: content["tags"].as_array().map(|tags| {
+                tags.iter().filter_map(|tag| tag["name"].as_str().map(String::from)).collect()
+            }).unwrap_or_default(),
+            user_mentions: mentions.iter().map(|mention| twitter::UserMention {
+                id: account_key(instance, mention["id"].as_str().unwrap_or("")),
+                screen_name: qualified_acct(mention, instance),
+            }).collect(),
+            symbols: Vec::new(),
+        },
+        references: twitter::References {
+            quoted_status_id: 0,
+            in_reply_to_status_id: content["in_reply_to_id"].as_str().and_then(|id| id.parse().ok()).map(|id| status_key(instance, id)).unwrap_or(0),
+            in_reply_to_screen_name: in_reply_to_screen_name,
+        },
+        referenced_tweets: Vec::new(),
+        media: content["media_attachments"].as_array().map(|attachments| {
+            attachments.iter().filter_map(|attachment| {
+                let media_type = match attachment["type"].as_str()? {
+                    "image" => "photo",
+                    "gifv" => "animated_gif",
+                    "video" => "video",
+                    _ => return None,
+                };
+                Some(twitter::Media {
+                    media_key: String::from(attachment["id"].as_str()?),
+                    media_type: String::from(media_type),
+                    url: String::from(attachment["url"].as_str()?),
+                })
+            }).collect()
+        }).unwrap_or_default(),
+        raw_json: status.to_string(),
+    })
+}
+
+pub fn status_kind(status: &Value) -> TweetKind {
+    let references = twitter::References {
+        quoted_status_id: 0,
+        in_reply_to_status_id: status["in_reply_to_id"].as_str().and_then(|id| id.parse().ok()).unwrap_or(0),
+        in_reply_to_screen_name: String::new(),
+    };
+    let reply_to_self = status["in_reply_to_account_id"].is_string() && status["in_reply_to_account_id"] == status["account"]["id"];
+    TweetKind::classify(status["reblog"].is_object(), &references, reply_to_self)
+}
+
+pub fn status_url(status: &Value) -> Option<&str> {
+    status["url"].as_str().or_else(|| status["reblog"]["url"].as_str()).filter(|url| !url.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_acct() {
+        assert_eq!(("vortis", "mstdn.jp"), parse_acct("@vortis@mstdn.jp").unwrap());
+        assert_eq!(("vortis", "mstdn.jp"), parse_acct("vortis@mstdn.jp").unwrap());
+        assert!(parse_acct("@vortis").is_err());
+        assert!(parse_acct("a@b@c").is_err());
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let statuses: Value = serde_json::from_str(include_str!("../tests/fixtures/mastodon/statuses.json")).unwrap();
+        let reply = parse_status(&statuses[0], "mstdn.jp").unwrap();
+        assert_eq!(status_key("mstdn.jp", 104139990427291200), reply.id);
+        assert_eq!("vortis@mstdn.jp", reply.screen_name);
+        assert_eq!("徳島ヴォルティス", reply.user_name);
+        assert_eq!("@supporter 応援ありがとうございます！\n次節もよろしくお願いします。 #vortis", reply.text);
+        assert_eq!(status_key("mstdn.jp", 104139980427291100), reply.references.in_reply_to_status_id);
+        assert_eq!("supporter@mastodon.social", reply.references.in_reply_to_screen_name);
+        assert_eq!(vec!["vortis"], reply.entities.hashtags);
+        assert_eq!(account_key("mstdn.jp", "1"), reply.user_id);
+        assert_eq!(TweetKind::Reply, reply.kind);
+        assert_eq!(statuses[0], serde_json::from_str::<Value>(&reply.raw_json).unwrap());
+
+        let reblog = parse_status(&statuses[1], "mstdn.jp").unwrap();
+        assert_eq!(TweetKind::Retweet, reblog.kind);
+        assert_eq!(status_key("mstdn.jp", 104139970427291000), reblog.retweeted_status_id);
+        assert_eq!("RT @jleague@mstdn.jp: 試合日程を発表しました", reblog.text);
+        assert_eq!(Some("https://mstdn.jp/@jleague/104139970427291000"), status_url(&statuses[1]));
+
+        let media = parse_status(&statuses[2], "mstdn.jp").unwrap();
+        assert_eq!("ネタバレ注意\n\n勝ちました", media.text);
+        assert_eq!(TweetKind::Original, media.kind);
+        assert_eq!(1, media.media.len());
+        assert_eq!("photo", media.media[0].media_type);
+    }
+
+    #[test]
+    fn test_account_key() {
+        assert_ne!(account_key("mstdn.jp", "1"), account_key("mastodon.social", "1"));
+        assert_eq!(account_key("mstdn.jp", "1"), account_key("mstdn.jp", "1"));
+    }
+
+    #[test]
+    fn test_status_key() {
+        assert_ne!(status_key("mstdn.jp", 104139970427291000), status_key("mastodon.social", 104139970427291000));
+        assert_ne!(104139970427291000, status_key("mstdn.jp", 104139970427291000));
+    }
+
+    #[test]
+    fn test_location_cursor() {
+        let location = Location { instance: String::from("mstdn.jp"), account_id: String::from("1"), since_id: None };
+        assert_eq!(Some(location.clone()), Location::from_cursor(Some(&location.to_cursor())));
+        let location = Location { since_id: Some(String::from("104139970427291000")), ..location };
+        assert_eq!(Some(location.clone()), Location::from_cursor(Some(&location.to_cursor())));
+    }
+}
@@ -0,0 +1,237 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+
+use crate::bluesky;
+use crate::db::models::{self, User};
+use crate::error::Error;
+use crate::mastodon;
+use crate::twitter;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SourceKind {
+    Twitter,
+    Feed,
+    Mastodon,
+    Bluesky,
+}
+
+impl SourceKind {
+    pub fn profile_url(&self, screen_name: &str) -> String {
+        match *self {
+            SourceKind::Twitter => format!("http://twitter.com/{}", screen_name),
+            // the screen name of a feed is its URL.
+            SourceKind::Feed => String::from(screen_name),
+            // the screen name of a Mastodon account is "user@instance".
+            SourceKind::Mastodon => match screen_name.split_once('@') {
+                Some((username, instance)) => format!("https://{}/@{}", instance, username),
+                None => String::from(screen_name),
+            },
+            SourceKind::Bluesky => format!("https://bsky.app/profile/{}", screen_name),
+        }
+    }
+
+    pub fn item_url(&self, screen_name: &str, tweet: &models::Tweet) -> String {
+        match *self {
+            SourceKind::Twitter => format!("http://twitter.com/{}/status/{}", screen_name, tweet.id),
+            SourceKind::Feed => raw_json_str(tweet, "link").unwrap_or_else(|| String::from(screen_name)),
+            SourceKind::Mastodon => serde_json::from_str(&tweet.raw_json).ok()
+                .and_then(|status: serde_json::Value| mastodon::status_url(&status).map(String::from))
+                .unwrap_or_else(|| self.profile_url(screen_name)),
+            SourceKind::Bluesky => serde_json::from_str(&tweet.raw_json).ok()
+                .and_then(|item: serde_json::Value| bluesky::post_url(&item))
+                .unwrap_or_else(|| self.profile_url(screen_name)),
+        }
+    }
+}
+
+fn raw_json_str(tweet: &models::Tweet, key: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(&tweet.raw_json).ok()?;
+    value[key].as_str().filter(|s| !s.is_empty()).map(String::from)
+}
+
+impl FromStr for SourceKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<SourceKind, Error> {
+        match s {
+            "twitter" => Ok(SourceKind::Twitter),
+            "feed" => Ok(SourceKind::Feed),
+            "mastodon" => Ok(SourceKind::Mastodon),
+            "bluesky" => Ok(SourceKind::Bluesky),
+            _ => Err(Error::ModelError("source_kind")),
+        }
+    }
+}
+
+impl fmt::Display for SourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SourceKind::Twitter => write!(f, "twitter"),
+            SourceKind::Feed => write!(f, "feed"),
+            SourceKind::Mastodon => write!(f, "mastodon"),
+            SourceKind::Bluesky => write!(f, "bluesky"),
+        }
+    }
+}
+
+impl FromSql for SourceKind {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value.as_str()?.parse().map_err(|_| FromSqlError::InvalidType)
+    }
+}
+
+impl ToSql for SourceKind {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TweetKind {
+    Original,
+    Retweet,
+    Reply,
+    Quote,
+    // a reply to a tweet of the same author
+    SelfThread,
+}
+
+impl TweetKind {
+    pub const ALL: [TweetKind; 5] = [TweetKind::Original, TweetKind::Retweet, TweetKind::Reply, TweetKind::Quote, TweetKind::SelfThread];
+
+    // a retweet is a retweet whatever it refers to, and a reply which quotes another tweet is a reply.
+    pub fn classify(retweet: bool, references: &twitter::References, reply_to_self: bool) -> TweetKind {
+        if retweet {
+            TweetKind::Retweet
+        } else if references.in_reply_to_status_id != 0 {
+            if reply_to_self { TweetKind::SelfThread } else { TweetKind::Reply }
+        } else if references.quoted_status_id != 0 {
+            TweetKind::Quote
+        } else {
+            TweetKind::Original
+        }
+    }
+
+    pub fn from_raw_json(raw_json: &serde_json::Value) -> TweetKind {
+        if raw_json["post"].is_object() {
+            bluesky::feed_item_kind(raw_json)
+        } else if raw_json["account"].is_object() {
+            mastodon::status_kind(raw_json)
+        } else if raw_json["user"].is_object() || raw_json["author_id"].is_string() {
+            twitter::parse_raw_json(raw_json).map(|tweet| tweet.kind).unwrap_or(TweetKind::Original)
+        } else {
+            // an entry of a feed
+            TweetKind::Original
+        }
+    }
+}
+
+impl FromStr for TweetKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<TweetKind, Error> {
+        match s {
+            "original" => Ok(TweetKind::Original),
+            "retweet" => Ok(TweetKind::Retweet),
+            "reply" => Ok(TweetKind::Reply),
+            "quote" => Ok(TweetKind::Quote),
+            "self_thread" => Ok(TweetKind::SelfThread),
+            _ => Err(Error::ModelError("tweet_kind")),
+        }
+    }
+}
+
+impl fmt::Display for TweetKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TweetKind::Original => write!(f, "original"),
+            TweetKind::Retweet => write!(f, "retweet"),
+            TweetKind::Reply => write!(f, "reply"),
+            TweetKind::Quote => write!(f, "quote"),
+            TweetKind::SelfThread => write!(f, "self_thread"),
+        }
+    }
+}
+
+impl FromSql for TweetKind {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value.as_str()?.parse().map_err(|_| FromSqlError::InvalidType)
+    }
+}
+
+impl ToSql for TweetKind {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Page {
+    pub tweets: Vec<twitter::Tweet>,
+    // e.g. the validators of a feed
+    pub cursor: Option<String>,
+}
+
+pub trait Source {
+    fn kind(&self) -> SourceKind;
+
+    fn resolve(&self, name: &str) -> Result<twitter::User, Error>;
+
+    fn can_fetch(&self, _user: &User) -> bool {
+        true
+    }
+
+    // ids of other backends are hashes, which are not ordered by time.
+    fn pages_by_since_id(&self) -> bool {
+        false
+    }
+
+    fn fetch(&self, user: &User, since_id: Option<i64>, count: Option<u32>) -> Result<Page, Error>;
+
+    // returns None when the backend can't look up items.
+    fn lookup(&self, _user: Option<&User>, _ids: &[u64]) -> Result<Option<Vec<twitter::Tweet>>, Error> {
+        Ok(None)
+    }
+
+    fn is_deleted(&self, _user: &User, _id: u64) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    fn endpoint(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_source_kind() {
+        assert_eq!(SourceKind::Twitter, "twitter".parse().unwrap());
+        assert_eq!("twitter", SourceKind::Twitter.to_string());
+        assert_eq!(SourceKind::Feed, "feed".parse().unwrap());
+        assert_eq!(SourceKind::Mastodon, "mastodon".parse().unwrap());
+        assert_eq!("https://mstdn.jp/@vortis", SourceKind::Mastodon.profile_url("vortis@mstdn.jp"));
+        assert_eq!(SourceKind::Bluesky, "bluesky".parse().unwrap());
+        assert!("myspace".parse::<SourceKind>().is_err());
+    }
+
+    #[test]
+    fn test_classify_tweet_kind() {
+        let references = |quoted_status_id, in_reply_to_status_id| twitter::References {
+            quoted_status_id: quoted_status_id,
+            in_reply_to_status_id: in_reply_to_status_id,
+            in_reply_to_screen_name: String::new(),
+        };
+        assert_eq!(TweetKind::Original, TweetKind::classify(false, &references(0, 0), false));
+        assert_eq!(TweetKind::Retweet, TweetKind::classify(true, &references(1, 2), false));
+        assert_eq!(TweetKind::Reply, TweetKind::classify(false, &references(1, 2), false));
+        assert_eq!(TweetKind::SelfThread, TweetKind::classify(false, &references(0, 2), true));
+        assert_eq!(TweetKind::Quote, TweetKind::classify(false, &references(1, 0), false));
+        assert_eq!(TweetKind::SelfThread, "self_thread".parse().unwrap());
+        assert_eq!(TweetKind::Original, TweetKind::from_raw_json(&serde_json::json!({ "guid": "1", "title": "entry" })));
+    }
+}
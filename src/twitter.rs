@@ -1,96 +1,702 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
 use std::io::Read;
+use std::str::FromStr;
+use std::thread;
+use std::time;
 use std::vec::Vec;
 
-use reqwest::blocking::Client;
-use serde_json;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use log::{info, warn};
+use reqwest::StatusCode;
+use reqwest::{Certificate, Proxy, Url};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::HeaderMap;
+use serde_json::{self, Value};
 
 use crate::error::Error;
+use crate::source::TweetKind;
+
+mod oauth1;
+mod v1;
+mod v2;
+
+pub use oauth1::{Credentials, UserToken};
+use v1::{parse_statuses, parse_user};
 
 pub const USER_AGENT: &'static str = "twitnot/0.1";
-pub const TOKEN_URL: &'static str = "https://api.twitter.com/oauth2/token";
+pub const DEFAULT_API_BASE_URL: &'static str = "https://api.twitter.com";
+pub const TOKEN_PATH: &'static str = "/oauth2/token";
+pub const INVALIDATE_TOKEN_PATH: &'static str = "/oauth2/invalidate_token";
+pub const USER_TIMELINE_PATH: &'static str = "/1.1/statuses/user_timeline.json";
+pub const USERS_LOOKUP_PATH: &'static str = "/1.1/users/lookup.json";
+pub const LIST_STATUSES_PATH: &'static str = "/1.1/lists/statuses.json";
+pub const LIST_MEMBERS_PATH: &'static str = "/1.1/lists/members.json";
+pub const SEARCH_TWEETS_PATH: &'static str = "/1.1/search/tweets.json";
+pub const STATUSES_LOOKUP_PATH: &'static str = "/1.1/statuses/lookup.json";
+pub const STATUSES_SHOW_PATH: &'static str = "/1.1/statuses/show.json";
+pub const FAVORITES_LIST_PATH: &'static str = "/1.1/favorites/list.json";
+pub const MEDIA_ENDPOINT: &'static str = "media";
+pub const MAX_TIMELINE_COUNT: u32 = 200;
+pub const MAX_SEARCH_COUNT: u32 = 100;
+pub const MAX_LOOKUP_COUNT: usize = 100;
+pub const MAX_FAVORITES_COUNT: u32 = 200;
+// user_timeline can only return up to 3,200 of a user's most recent tweets.
+pub const MAX_TIMELINE_DEPTH: u32 = 3200;
+pub const DEFAULT_RETRY_ATTEMPTS: u32 = 3;
+pub const DEFAULT_RETRY_DEADLINE_SECS: u64 = 60;
+pub const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+pub const DEFAULT_READ_TIMEOUT_SECS: u64 = 30;
+const BASE_RETRY_DELAY_MILLIS: u64 = 1000;
+const MAX_RETRY_DELAY_MILLIS: u64 = 30000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ApiVersion {
+    V1_1,
+    V2,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Auth<'a> {
+    Bearer(&'a str),
+    User(&'a Credentials),
+}
 
 #[derive(Debug)]
 pub struct TwitterClient {
     pub client: reqwest::blocking::Client,
+    pub api_base_url: String,
+    pub api_version: ApiVersion,
+    pub retry_policy: RetryPolicy,
+    rate_limits: RefCell<HashMap<String, RateLimit>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub deadline: time::Duration,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub limit: u32,
+    pub remaining: u32,
+    pub reset_at: DateTime<Utc>,
 }
 
 #[derive(Debug)]
 pub struct Tweet {
     pub id: u64,
-    pub created_at: String,
+    pub created_at: DateTime<Utc>,
+    pub user_id: u64,
     pub user_name: String,
+    pub screen_name: String,
     pub text: String,
-    pub retweets: bool,
+    pub kind: TweetKind,
+    // 0 unless the tweet is a retweet
     pub retweeted_status_id: u64,
+    pub entities: Entities,
+    pub references: References,
+    pub referenced_tweets: Vec<Tweet>,
+    pub media: Vec<Media>,
     pub raw_json: String,
 }
 
+// an id is 0 when it does not refer to any tweet.
+#[derive(Debug, Default)]
+pub struct References {
+    pub quoted_status_id: u64,
+    pub in_reply_to_status_id: u64,
+    pub in_reply_to_screen_name: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Entities {
+    pub urls: Vec<UrlEntity>,
+    pub hashtags: Vec<String>,
+    pub user_mentions: Vec<UserMention>,
+    pub symbols: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct UrlEntity {
+    pub url: String,
+    pub expanded_url: String,
+    pub display_url: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Account<'a> {
+    Id(u64),
+    ScreenName(&'a str),
+}
+
+#[derive(Debug)]
+pub struct User {
+    pub id: u64,
+    pub screen_name: String,
+    pub name: String,
+    pub protected: bool,
+    pub verified: bool,
+}
+
+#[derive(Debug)]
+pub struct Media {
+    pub media_key: String,
+    pub media_type: String,
+    pub url: String,
+}
+
+#[derive(Debug)]
+pub struct UserMention {
+    pub id: u64,
+    pub screen_name: String,
+}
+
+// Parses the raw JSON stored with a tweet, which is either a v1.1 status or a v2 tweet.
+pub fn parse_raw_json(raw_json: &Value) -> Result<Tweet, Error> {
+    if raw_json["author_id"].is_string() {
+        v2::parse_raw_tweet(raw_json)
+    } else {
+        v1::parse_status(raw_json)
+    }
+}
+
+fn error_codes(body: &str) -> Vec<u64> {
+    serde_json::from_str::<Value>(body).ok()
+        .and_then(|body| body["errors"].as_array().map(|errors| errors.iter().filter_map(|error| error["code"].as_u64()).collect()))
+        .unwrap_or_default()
+}
+
+impl FromStr for ApiVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<ApiVersion, Error> {
+        match s {
+            "1.1" => Ok(ApiVersion::V1_1),
+            "2" => Ok(ApiVersion::V2),
+            _ => Err(Error::ConfigError("api_version")),
+        }
+    }
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ApiVersion::V1_1 => write!(f, "1.1"),
+            ApiVersion::V2 => write!(f, "2"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpSettings {
+    pub connect_timeout: time::Duration,
+    pub read_timeout: time::Duration,
+    pub proxy: Option<String>,
+    // e.g. "localhost" or ".example.com"
+    pub no_proxy: Vec<String>,
+    pub ca_bundle: Option<String>,
+}
+
+impl Default for HttpSettings {
+    fn default() -> HttpSettings {
+        HttpSettings {
+            connect_timeout: time::Duration::from_secs(DEFAULT_CONNECT_TIMEOUT_SECS),
+            read_timeout: time::Duration::from_secs(DEFAULT_READ_TIMEOUT_SECS),
+            proxy: None,
+            no_proxy: Vec::new(),
+            ca_bundle: None,
+        }
+    }
+}
+
+fn is_no_proxy(host: &str, no_proxy: &[String]) -> bool {
+    no_proxy.iter().any(|pattern| {
+        let domain = pattern.trim_start_matches('.');
+        pattern == "*" || host.eq_ignore_ascii_case(domain)
+            || (host.len() > domain.len() && host.to_ascii_lowercase().ends_with(&format!(".{}", domain.to_ascii_lowercase())))
+    })
+}
+
+// one certificate is parsed from PEM at a time.
+fn parse_ca_bundle(pem: &str) -> Result<Vec<Certificate>, Error> {
+    const END_CERTIFICATE: &'static str = "-----END CERTIFICATE-----";
+    let mut certificates: Vec<Certificate> = Vec::new();
+    for block in pem.split_terminator(END_CERTIFICATE).filter(|block| block.contains("-----BEGIN CERTIFICATE-----")) {
+        let certificate = Certificate::from_pem(format!("{}{}", block, END_CERTIFICATE).as_bytes())
+            .map_err(|_| Error::ConfigError("ca_bundle"))?;
+        certificates.push(certificate);
+    }
+    if certificates.is_empty() {
+        return Err(Error::ConfigError("ca_bundle"));
+    }
+    Ok(certificates)
+}
+
+impl HttpSettings {
+    pub fn build_client(&self) -> Result<Client, Error> {
+        let mut builder = Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.read_timeout);
+        if let Some(ref proxy) = self.proxy {
+            let proxy_url = Url::parse(proxy).map_err(|_| Error::ConfigError("proxy"))?;
+            let no_proxy = self.no_proxy.clone();
+            builder = builder.proxy(Proxy::custom(move |url| match url.host_str() {
+                Some(host) if is_no_proxy(host, &no_proxy) => None,
+                _ => Some(proxy_url.clone()),
+            }));
+        }
+        if let Some(ref ca_bundle) = self.ca_bundle {
+            for certificate in parse_ca_bundle(&std::fs::read_to_string(ca_bundle)?)? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        Ok(builder.build()?)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: DEFAULT_RETRY_ATTEMPTS,
+            deadline: time::Duration::from_secs(DEFAULT_RETRY_DEADLINE_SECS),
+        }
+    }
+}
+
+impl RetryPolicy {
+    // half of the delay is random so that clients failed together do not retry together.
+    fn delay(&self, attempt: u32) -> time::Duration {
+        let exp_delay = BASE_RETRY_DELAY_MILLIS.saturating_mul(1 << (attempt - 1).min(16)).min(MAX_RETRY_DELAY_MILLIS);
+        time::Duration::from_millis(exp_delay / 2 + fastrand::u64(0..=exp_delay / 2))
+    }
+}
+
+impl RateLimit {
+    fn from_headers(headers: &HeaderMap) -> Option<RateLimit> {
+        let value = |name: &str| headers.get(name)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<i64>().ok());
+
+        Some(RateLimit {
+            limit: value("x-rate-limit-limit")? as u32,
+            remaining: value("x-rate-limit-remaining")? as u32,
+            reset_at: Utc.timestamp(value("x-rate-limit-reset")?, 0),
+        })
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining == 0 && self.reset_at > Utc::now()
+    }
+}
+
 impl TwitterClient {
-    pub fn new() -> TwitterClient {
+    pub fn new(api_base_url: &str, api_version: ApiVersion) -> TwitterClient {
         TwitterClient {
             client: Client::new(),
+            api_base_url: String::from(api_base_url.trim_end_matches('/')),
+            api_version: api_version,
+            retry_policy: RetryPolicy::default(),
+            rate_limits: RefCell::new(HashMap::new()),
         }
     }
 
+    pub fn rate_limit(&self, endpoint: &str) -> Option<RateLimit> {
+        self.rate_limits.borrow().get(endpoint).cloned()
+    }
+
+    pub fn search_endpoint(&self) -> &'static str {
+        match self.api_version {
+            ApiVersion::V1_1 => SEARCH_TWEETS_PATH,
+            ApiVersion::V2 => v2::SEARCH_RECENT_ENDPOINT,
+        }
+    }
+
+    pub fn timeline_endpoint(&self) -> &'static str {
+        match self.api_version {
+            ApiVersion::V1_1 => USER_TIMELINE_PATH,
+            ApiVersion::V2 => v2::USER_TWEETS_ENDPOINT,
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.api_base_url, path)
+    }
+
+    // 429 is not transient when the rate limit is exhausted; it is up to the caller to wait for the reset.
+    fn is_transient(&self, endpoint: &str, err: &Error) -> bool {
+        match *err {
+            Error::ReqwestError(_) => true,
+            Error::HttpError(status, _) => status.is_server_error(),
+            Error::RateLimitError(_, _) => !self.rate_limit(endpoint).map(|rate_limit| rate_limit.is_exhausted()).unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    fn send(&self, endpoint: &str, req: RequestBuilder) -> Result<Response, Error> {
+        self.send_with(endpoint, req, Ok)
+    }
+
+    fn send_authorized(&self, endpoint: &str, req: RequestBuilder, auth: Auth) -> Result<Response, Error> {
+        self.send_with(endpoint, req, |req| self.authorize(req, auth))
+    }
+
+    // `authorize` is applied to each attempt, so that a signed request is signed with a fresh nonce.
+    fn send_with<A>(&self, endpoint: &str, mut req: RequestBuilder, authorize: A) -> Result<Response, Error>
+        where A: Fn(RequestBuilder) -> Result<RequestBuilder, Error>
+    {
+        let started_at = time::Instant::now();
+        let mut attempt = 1;
+        loop {
+            let retry_req = req.try_clone();
+            let err = match self.send_once(endpoint, authorize(req)?) {
+                Ok(res) => return Ok(res),
+                Err(err) => err,
+            };
+
+            let delay = self.retry_policy.delay(attempt);
+            let retry_req = match retry_req {
+                Some(retry_req) if attempt < self.retry_policy.max_attempts
+                    && started_at.elapsed() + delay <= self.retry_policy.deadline
+                    && self.is_transient(endpoint, &err) => retry_req,
+                _ => return Err(err),
+            };
+
+            warn!("{}: attempt {} failed, retrying in {}ms: {}", endpoint, attempt, delay.as_millis(), err);
+            thread::sleep(delay);
+            req = retry_req;
+            attempt += 1;
+        }
+    }
+
+    fn authorize(&self, req: RequestBuilder, auth: Auth) -> Result<RequestBuilder, Error> {
+        match auth {
+            Auth::Bearer(token) => Ok(req.bearer_auth(token)),
+            Auth::User(credentials) => {
+                let built = req.try_clone()
+                    .ok_or(Error::ConfigError("oauth"))?
+                    .build()?;
+                let header = oauth1::authorization(credentials, built.method().as_str(), built.url(), &[]);
+                Ok(req.header(reqwest::header::AUTHORIZATION, header))
+            },
+        }
+    }
+
+    fn send_once(&self, endpoint: &str, req: RequestBuilder) -> Result<Response, Error> {
+        let res = req.send()?;
+        if let Some(rate_limit) = RateLimit::from_headers(res.headers()) {
+            info!("{}: {}/{} requests remaining until {}", endpoint, rate_limit.remaining, rate_limit.limit, rate_limit.reset_at);
+            self.rate_limits.borrow_mut().insert(String::from(endpoint), rate_limit);
+        }
+
+        if res.status() == StatusCode::TOO_MANY_REQUESTS {
+            // the limit is reset every 15 minutes when the response does not tell us when.
+            let reset_at = self.rate_limit(endpoint)
+                .map(|rate_limit| rate_limit.reset_at)
+                .unwrap_or_else(|| Utc::now() + Duration::minutes(15));
+            return Err(Error::RateLimitError(String::from(endpoint), reset_at));
+        }
+        if !res.status().is_success() {
+            return Err(Error::from(res))
+        }
+
+        Ok(res)
+    }
+
     pub fn get_access_token(&self, consumer_key: &str, consumer_secret: &str) -> Result<String, Error> {
-        let req = self.client.post(TOKEN_URL)
+        let req = self.client.post(&self.url(TOKEN_PATH))
             .basic_auth(consumer_key, Some(consumer_secret))
             .header(reqwest::header::USER_AGENT, USER_AGENT)
             .header(reqwest::header::CONTENT_TYPE, "application/x-www-form-urlencoded")
             .form(&[("grant_type", "client_credentials")]);
-        let res = req.send()?;
-        if !res.status().is_success() {
-            return Err(Error::from(res))
-        }
+        let res = self.send(TOKEN_PATH, req)?;
 
         let body: serde_json::Value = serde_json::from_reader(res)?;
         let access_token = String::from(body["access_token"].as_str().unwrap());
         Ok(access_token)
     }
 
-    pub fn get_tweets(&self, access_token: &str, screen_name: &str, count: Option<u32>) -> Result<Vec<Tweet>, Error> {
-        let mut builder = self.client.get("https://api.twitter.com/1.1/statuses/user_timeline.json");
-        builder = builder.query(&[("screen_name", screen_name)]);
-        if let Some(c) = count {
-            builder = builder.query(&[("count", &format!("{}", c))]);
+    pub fn invalidate_access_token(&self, consumer_key: &str, consumer_secret: &str, access_token: &str) -> Result<(), Error> {
+        let req = self.client.post(&self.url(INVALIDATE_TOKEN_PATH))
+            .basic_auth(consumer_key, Some(consumer_secret))
+            .header(reqwest::header::USER_AGENT, USER_AGENT)
+            .header(reqwest::header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .form(&[("access_token", access_token)]);
+        self.send(INVALIDATE_TOKEN_PATH, req)?;
+        Ok(())
+    }
+
+    pub fn download(&self, url: &str) -> Result<(Vec<u8>, Option<String>), Error> {
+        let req = self.client.get(url)
+            .header(reqwest::header::USER_AGENT, USER_AGENT);
+        let mut res = self.send(MEDIA_ENDPOINT, req)?;
+
+        let content_type = res.headers().get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
+        let mut body: Vec<u8> = Vec::new();
+        res.read_to_end(&mut body)?;
+
+        Ok((body, content_type))
+    }
+
+    pub fn lookup_user(&self, auth: Auth, screen_name: &str) -> Result<User, Error> {
+        match self.api_version {
+            ApiVersion::V1_1 => self.lookup_user_v1(auth, screen_name),
+            ApiVersion::V2 => self.lookup_user_v2(auth, screen_name),
         }
+    }
 
-        let req = builder.bearer_auth(access_token)
+    fn lookup_user_v1(&self, auth: Auth, screen_name: &str) -> Result<User, Error> {
+        let req = self.client.get(&self.url(USERS_LOOKUP_PATH))
+            .query(&[("screen_name", screen_name), ("include_entities", "false")])
             .header(reqwest::header::USER_AGENT, USER_AGENT);
-        let mut res = req.send()?;
-        if !res.status().is_success() {
-            return Err(Error::from(res))
+        // users/lookup answers 404 when none of the accounts exists, suspended ones included.
+        let res = match self.send_authorized(USERS_LOOKUP_PATH, req, auth) {
+            Err(Error::HttpError(StatusCode::NOT_FOUND, _)) => return Err(Error::UserNotFoundError(String::from(screen_name))),
+            result => result?,
+        };
+
+        let body: serde_json::Value = serde_json::from_reader(res)?;
+        let entry = body.as_array().and_then(|users| users.first())
+            .ok_or_else(|| Error::UserNotFoundError(String::from(screen_name)))?;
+        parse_user(entry)
+    }
+
+    pub fn get_list_tweets(&self, auth: Auth, owner: &str, slug: &str, count: Option<u32>, since_id: Option<u64>) -> Result<Vec<Tweet>, Error> {
+        let mut builder = self.client.get(&self.url(LIST_STATUSES_PATH))
+            .query(&[("owner_screen_name", owner), ("slug", slug), ("include_rts", "true"), ("tweet_mode", "extended")]);
+        if let Some(c) = count {
+            builder = builder.query(&[("count", &format!("{}", c))]);
+        }
+        if let Some(id) = since_id {
+            builder = builder.query(&[("since_id", &format!("{}", id))]);
         }
 
+        let req = builder.header(reqwest::header::USER_AGENT, USER_AGENT);
+        let mut res = match self.send_authorized(LIST_STATUSES_PATH, req, auth) {
+            Err(Error::HttpError(StatusCode::NOT_FOUND, _)) => return Err(Error::ListNotFoundError(format!("{}/{}", owner, slug))),
+            result => result?,
+        };
+
         let mut body = String::new();
         res.read_to_string(&mut body)?;
 
+        parse_statuses(&serde_json::from_str(&body)?)
+    }
+
+    pub fn lookup_tweets(&self, auth: Auth, ids: &[u64]) -> Result<Vec<Tweet>, Error> {
         let mut results: Vec<Tweet> = Vec::new();
-        let timeline_json: serde_json::Value = serde_json::from_str(&body)?;
-        match timeline_json.as_array() {
-            Some(entries) => {
+        for chunk in ids.chunks(MAX_LOOKUP_COUNT) {
+            let ids = chunk.iter().map(|id| id.to_string()).collect::<Vec<String>>().join(",");
+            results.extend(match self.api_version {
+                ApiVersion::V1_1 => self.lookup_tweets_v1(auth, &ids)?,
+                ApiVersion::V2 => self.lookup_tweets_v2(auth, &ids)?,
+            });
+        }
+        Ok(results)
+    }
+
+    fn lookup_tweets_v1(&self, auth: Auth, ids: &str) -> Result<Vec<Tweet>, Error> {
+        // with map=true, a deleted or inaccessible tweet is mapped to null.
+        let req = self.client.get(&self.url(STATUSES_LOOKUP_PATH))
+            .query(&[("id", ids), ("map", "true"), ("tweet_mode", "extended")])
+            .header(reqwest::header::USER_AGENT, USER_AGENT);
+        let res = self.send_authorized(STATUSES_LOOKUP_PATH, req, auth)?;
+
+        let body: serde_json::Value = serde_json::from_reader(res)?;
+        let statuses: Vec<Value> = body["id"].as_object()
+            .map(|entries| entries.values().filter(|status| !status.is_null()).cloned().collect())
+            .unwrap_or_default();
+        parse_statuses(&Value::Array(statuses))
+    }
+
+    // statuses/show is only in API v1.1.
+    pub fn is_tweet_deleted(&self, auth: Auth, id: u64) -> Result<bool, Error> {
+        let req = self.client.get(&self.url(STATUSES_SHOW_PATH))
+            .query(&[("id", &id.to_string())])
+            .header(reqwest::header::USER_AGENT, USER_AGENT);
+        match self.send_authorized(STATUSES_SHOW_PATH, req, auth) {
+            Ok(_) => Ok(false),
+            // 144: No status found with that ID.
+            Err(Error::HttpError(StatusCode::NOT_FOUND, body)) => Ok(error_codes(&body).contains(&144)),
+            Err(Error::HttpError(StatusCode::FORBIDDEN, _)) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn search_tweets(&self, auth: Auth, query: &str, count: Option<u32>, since_id: Option<u64>) -> Result<Vec<Tweet>, Error> {
+        match self.api_version {
+            ApiVersion::V1_1 => self.search_tweets_v1(auth, query, count, since_id),
+            ApiVersion::V2 => self.search_tweets_v2(auth, query, count, since_id),
+        }
+    }
+
+    fn search_tweets_v1(&self, auth: Auth, query: &str, count: Option<u32>, since_id: Option<u64>) -> Result<Vec<Tweet>, Error> {
+        let mut builder = self.client.get(&self.url(SEARCH_TWEETS_PATH))
+            .query(&[("q", query), ("result_type", "recent"), ("tweet_mode", "extended")]);
+        if let Some(c) = count {
+            builder = builder.query(&[("count", &format!("{}", c))]);
+        }
+        if let Some(id) = since_id {
+            builder = builder.query(&[("since_id", &format!("{}", id))]);
+        }
+
+        let req = builder.header(reqwest::header::USER_AGENT, USER_AGENT);
+        let res = self.send_authorized(SEARCH_TWEETS_PATH, req, auth)?;
+
+        let body: serde_json::Value = serde_json::from_reader(res)?;
+        parse_statuses(&body["statuses"])
+    }
+
+    pub fn get_list_members(&self, auth: Auth, owner: &str, slug: &str) -> Result<Vec<User>, Error> {
+        let mut results: Vec<User> = Vec::new();
+        let mut cursor = String::from("-1");
+        loop {
+            let req = self.client.get(&self.url(LIST_MEMBERS_PATH))
+                .query(&[("owner_screen_name", owner), ("slug", slug), ("count", "5000"), ("skip_status", "true"), ("cursor", &cursor)])
+                .header(reqwest::header::USER_AGENT, USER_AGENT);
+            let res = match self.send_authorized(LIST_MEMBERS_PATH, req, auth) {
+                Err(Error::HttpError(StatusCode::NOT_FOUND, _)) => return Err(Error::ListNotFoundError(format!("{}/{}", owner, slug))),
+                result => result?,
+            };
+
+            let body: serde_json::Value = serde_json::from_reader(res)?;
+            if let Some(entries) = body["users"].as_array() {
                 for entry in entries {
-                    let id = entry["id"].as_u64().unwrap_or(0);
-                    let created_at = entry["created_at"].as_str().unwrap_or("");
-                    let user_name = entry["user"]["name"].as_str().unwrap_or("");
-                    let text = entry["text"].as_str().unwrap_or("");
-                    let retweets = entry["retweeted_status"].is_null();
-                    let retweeted_status_id = entry["retweeted_status"]["id"].as_u64().unwrap_or(0);
-
-                    results.push(Tweet {
-                        id: id,
-                        created_at: String::from(created_at),
-                        user_name: String::from(user_name),
-                        text: String::from(text),
-                        retweets: retweets,
-                        retweeted_status_id: retweeted_status_id,
-                        raw_json: entry.to_string(),
-                    });
+                    results.push(parse_user(entry)?);
                 }
-            },
-            _ => {},
-        };
+            }
+
+            cursor = body["next_cursor_str"].as_str().unwrap_or("0").to_string();
+            if cursor == "0" {
+                break;
+            }
+        }
 
         Ok(results)
     }
+
+    // likes are always fetched with API v1.1.
+    pub fn get_favorites(&self, auth: Auth, account: Account, count: Option<u32>) -> Result<Vec<Tweet>, Error> {
+        let mut builder = self.client.get(&self.url(FAVORITES_LIST_PATH));
+        builder = match account {
+            Account::Id(id) => builder.query(&[("user_id", &id.to_string())]),
+            Account::ScreenName(screen_name) => builder.query(&[("screen_name", screen_name)]),
+        };
+        builder = builder.query(&[("tweet_mode", "extended")]);
+        if let Some(c) = count {
+            builder = builder.query(&[("count", &format!("{}", c))]);
+        }
+
+        let req = builder.header(reqwest::header::USER_AGENT, USER_AGENT);
+        let res = self.send_authorized(FAVORITES_LIST_PATH, req, auth)?;
+
+        let body: serde_json::Value = serde_json::from_reader(res)?;
+        parse_statuses(&body)
+    }
+
+    pub fn get_tweets(&self, auth: Auth, account: Account, count: Option<u32>, since_id: Option<u64>, max_id: Option<u64>) -> Result<Vec<Tweet>, Error> {
+        match self.api_version {
+            ApiVersion::V1_1 => self.get_tweets_v1(auth, account, count, since_id, max_id),
+            ApiVersion::V2 => self.get_tweets_v2(auth, account, count, since_id, max_id),
+        }
+    }
+
+    fn get_tweets_v1(&self, auth: Auth, account: Account, count: Option<u32>, since_id: Option<u64>, max_id: Option<u64>) -> Result<Vec<Tweet>, Error> {
+        let mut builder = self.client.get(&self.url(USER_TIMELINE_PATH));
+        builder = match account {
+            Account::Id(id) => builder.query(&[("user_id", &id.to_string())]),
+            Account::ScreenName(screen_name) => builder.query(&[("screen_name", screen_name)]),
+        };
+        builder = builder.query(&[("tweet_mode", "extended")]);
+        if let Some(c) = count {
+            builder = builder.query(&[("count", &format!("{}", c))]);
+        }
+        if let Some(id) = since_id {
+            builder = builder.query(&[("since_id", &format!("{}", id))]);
+        }
+        if let Some(id) = max_id {
+            builder = builder.query(&[("max_id", &format!("{}", id))]);
+        }
+
+        let req = builder.header(reqwest::header::USER_AGENT, USER_AGENT);
+        let mut res = self.send_authorized(USER_TIMELINE_PATH, req, auth)?;
+
+        let mut body = String::new();
+        res.read_to_string(&mut body)?;
+
+        parse_statuses(&serde_json::from_str(&body)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_rate_limit_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert!(RateLimit::from_headers(&headers).is_none());
+
+        headers.insert("x-rate-limit-limit", HeaderValue::from_static("900"));
+        headers.insert("x-rate-limit-remaining", HeaderValue::from_static("0"));
+        headers.insert("x-rate-limit-reset", HeaderValue::from_static("1589000000"));
+        let rate_limit = RateLimit::from_headers(&headers).unwrap();
+        assert_eq!(900, rate_limit.limit);
+        assert_eq!(0, rate_limit.remaining);
+        assert_eq!(Utc.timestamp(1589000000, 0), rate_limit.reset_at);
+        // the reset time is already past
+        assert!(!rate_limit.is_exhausted());
+    }
+
+    #[test]
+    fn test_is_no_proxy() {
+        let no_proxy = vec![String::from("localhost"), String::from(".example.com")];
+        assert!(is_no_proxy("localhost", &no_proxy));
+        assert!(is_no_proxy("example.com", &no_proxy));
+        assert!(is_no_proxy("api.Example.com", &no_proxy));
+        assert!(!is_no_proxy("api.twitter.com", &no_proxy));
+        assert!(!is_no_proxy("badexample.com", &no_proxy));
+        assert!(is_no_proxy("api.twitter.com", &[String::from("*")]));
+    }
+
+    #[test]
+    fn test_parse_ca_bundle() {
+        assert!(parse_ca_bundle("").is_err());
+        assert!(parse_ca_bundle("-----BEGIN CERTIFICATE-----\nnot base64\n-----END CERTIFICATE-----\n").is_err());
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let policy = RetryPolicy::default();
+        for _ in 0..100 {
+            assert!((500..=1000).contains(&policy.delay(1).as_millis()));
+            assert!((2000..=4000).contains(&policy.delay(3).as_millis()));
+            assert!((15000..=30000).contains(&policy.delay(100).as_millis()));
+        }
+    }
+
+    #[test]
+    fn test_parse_raw_json() {
+        let status = json!({
+            "id": 1259093094585434112u64,
+            "created_at": "Sat May 09 12:34:56 +0000 2020",
+            "text": "テス…",
+            "truncated": true,
+            "user": { "id": 2244994945u64, "screen_name": "vortis_pr", "name": "ヴォルティススタジアム" },
+            "extended_tweet": { "full_text": "テストテスト" },
+        });
+        assert_eq!("テストテスト", parse_raw_json(&status).unwrap().text);
+
+        let tweet = json!({ "id": "1259093094585434112", "author_id": "2244994945", "created_at": "2020-05-09T12:34:56.000Z", "text": "テスト" });
+        assert_eq!(1259093094585434112, parse_raw_json(&tweet).unwrap().id);
+
+        assert!(parse_raw_json(&json!({ "text": "テスト" })).is_err());
+    }
 }
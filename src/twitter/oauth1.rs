@@ -0,0 +1,195 @@
+use chrono::Utc;
+use hmac_sha1_compact::HMAC;
+use reqwest::Url;
+
+use crate::error::Error;
+use super::{TwitterClient, USER_AGENT};
+
+pub const REQUEST_TOKEN_PATH: &'static str = "/oauth/request_token";
+pub const AUTHORIZE_PATH: &'static str = "/oauth/authorize";
+pub const ACCESS_TOKEN_PATH: &'static str = "/oauth/access_token";
+
+const BASE64_CHARS: &'static [u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// the token is empty while a user is being authorized.
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub consumer_key: String,
+    pub consumer_secret: String,
+    pub token: String,
+    pub token_secret: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct UserToken {
+    pub token: String,
+    pub token_secret: String,
+    pub screen_name: String,
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes().map(|b| match b {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+        _ => format!("%{:02X}", b),
+    }).collect()
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - i * 8));
+        for i in 0..4 {
+            encoded.push(if i <= chunk.len() { BASE64_CHARS[(n >> (18 - i * 6)) as usize & 0x3f] as char } else { '=' });
+        }
+    }
+    encoded
+}
+
+fn signature(method: &str, url: &Url, params: &[(String, String)], consumer_secret: &str, token_secret: &str) -> String {
+    let mut encoded_params: Vec<(String, String)> = params.iter().map(|(k, v)| (percent_encode(k), percent_encode(v))).collect();
+    encoded_params.sort();
+    let param_string = encoded_params.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<String>>().join("&");
+
+    let mut base_url = url.clone();
+    base_url.set_query(None);
+    base_url.set_fragment(None);
+    let base_string = format!("{}&{}&{}", method.to_uppercase(), percent_encode(base_url.as_str()), percent_encode(&param_string));
+    let key = format!("{}&{}", percent_encode(consumer_secret), percent_encode(token_secret));
+
+    base64_encode(&HMAC::mac(base_string.as_bytes(), key.as_bytes()))
+}
+
+fn authorization_with(credentials: &Credentials, method: &str, url: &Url, extra_params: &[(&str, &str)], nonce: &str, timestamp: i64) -> String {
+    let mut oauth_params: Vec<(String, String)> = vec![
+        (String::from("oauth_consumer_key"), credentials.consumer_key.clone()),
+        (String::from("oauth_nonce"), String::from(nonce)),
+        (String::from("oauth_signature_method"), String::from("HMAC-SHA1")),
+        (String::from("oauth_timestamp"), timestamp.to_string()),
+        (String::from("oauth_version"), String::from("1.0")),
+    ];
+    if !credentials.token.is_empty() {
+        oauth_params.push((String::from("oauth_token"), credentials.token.clone()));
+    }
+    oauth_params.extend(extra_params.iter().map(|(k, v)| (String::from(*k), String::from(*v))));
+
+    let mut params = oauth_params.clone();
+    params.extend(url.query_pairs().map(|(k, v)| (k.into_owned(), v.into_owned())));
+    oauth_params.push((String::from("oauth_signature"), signature(method, url, &params, &credentials.consumer_secret, &credentials.token_secret)));
+
+    let fields: Vec<String> = oauth_params.iter().map(|(k, v)| format!("{}=\"{}\"", percent_encode(k), percent_encode(v))).collect();
+    format!("OAuth {}", fields.join(", "))
+}
+
+// the request must not have a form body.
+pub fn authorization(credentials: &Credentials, method: &str, url: &Url, extra_params: &[(&str, &str)]) -> String {
+    let nonce: String = std::iter::repeat_with(fastrand::alphanumeric).take(32).collect();
+    authorization_with(credentials, method, url, extra_params, &nonce, Utc::now().timestamp())
+}
+
+fn parse_token_response(body: &str) -> Result<(String, String, String), Error> {
+    let value = |key: &str| body.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| String::from(v));
+    match (value("oauth_token"), value("oauth_token_secret")) {
+        (Some(token), Some(token_secret)) => Ok((token, token_secret, value("screen_name").unwrap_or_default())),
+        _ => Err(Error::HttpError(reqwest::StatusCode::OK, String::from(body))),
+    }
+}
+
+impl TwitterClient {
+    fn post_signed(&self, path: &str, credentials: &Credentials, extra_params: &[(&str, &str)]) -> Result<(String, String, String), Error> {
+        let url = Url::parse(&self.url(path)).map_err(|_| Error::ConfigError("api_base_url"))?;
+        let req = self.client.post(url.clone())
+            .header(reqwest::header::USER_AGENT, USER_AGENT);
+        let res = self.send_with(path, req, |req| {
+            Ok(req.header(reqwest::header::AUTHORIZATION, authorization(credentials, "POST", &url, extra_params)))
+        })?;
+        parse_token_response(&res.text()?)
+    }
+
+    pub fn get_request_token(&self, consumer_key: &str, consumer_secret: &str) -> Result<(Credentials, String), Error> {
+        let mut credentials = Credentials {
+            consumer_key: String::from(consumer_key),
+            consumer_secret: String::from(consumer_secret),
+            token: String::new(),
+            token_secret: String::new(),
+        };
+        let (token, token_secret, _) = self.post_signed(REQUEST_TOKEN_PATH, &credentials, &[("oauth_callback", "oob")])?;
+        let authorize_url = format!("{}?oauth_token={}", self.url(AUTHORIZE_PATH), token);
+
+        credentials.token = token;
+        credentials.token_secret = token_secret;
+        Ok((credentials, authorize_url))
+    }
+
+    pub fn get_user_token(&self, request_token: &Credentials, pin: &str) -> Result<UserToken, Error> {
+        let (token, token_secret, screen_name) = self.post_signed(ACCESS_TOKEN_PATH, request_token, &[("oauth_verifier", pin)])?;
+        Ok(UserToken {
+            token: token,
+            token_secret: token_secret,
+            screen_name: screen_name,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent_encode() {
+        assert_eq!("Ladies%20%2B%20Gentlemen", percent_encode("Ladies + Gentlemen"));
+        assert_eq!("An%20encoded%20string%21", percent_encode("An encoded string!"));
+        assert_eq!("%E2%98%83", percent_encode("☃"));
+    }
+
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!("", base64_encode(b""));
+        assert_eq!("Zg==", base64_encode(b"f"));
+        assert_eq!("Zm8=", base64_encode(b"fo"));
+        assert_eq!("Zm9vYmFy", base64_encode(b"foobar"));
+    }
+
+    #[test]
+    fn test_signature() {
+        // the example of "Creating a signature" in the Twitter developer documentation
+        let url = Url::parse("https://api.twitter.com/1.1/statuses/update.json?include_entities=true").unwrap();
+        let params: Vec<(String, String)> = vec![
+            ("status", "Hello Ladies + Gentlemen, a signed OAuth request!"),
+            ("include_entities", "true"),
+            ("oauth_consumer_key", "xvz1evFS4wEEPTGEFPHBog"),
+            ("oauth_nonce", "kYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg"),
+            ("oauth_signature_method", "HMAC-SHA1"),
+            ("oauth_timestamp", "1318622958"),
+            ("oauth_token", "370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb"),
+            ("oauth_version", "1.0"),
+        ].into_iter().map(|(k, v)| (String::from(k), String::from(v))).collect();
+        assert_eq!("hCtSmYh+iHYCEqBWrE7C7hYmtUk=", signature("post", &url, &params,
+            "kAcSOqF21Fu85e7zjz7ZN2U4ZRhfV3WpwPAoE3Z7kBw", "LswwdoUaIvS8ltyTt5jkRh4J50vUPVVHtR2YPi5kE"));
+    }
+
+    #[test]
+    fn test_authorization() {
+        let credentials = Credentials {
+            consumer_key: String::from("xvz1evFS4wEEPTGEFPHBog"),
+            consumer_secret: String::from("kAcSOqF21Fu85e7zjz7ZN2U4ZRhfV3WpwPAoE3Z7kBw"),
+            token: String::new(),
+            token_secret: String::new(),
+        };
+        let url = Url::parse("https://api.twitter.com/oauth/request_token").unwrap();
+        let header = authorization_with(&credentials, "POST", &url, &[("oauth_callback", "oob")], "abc", 1318622958);
+        assert!(header.starts_with("OAuth oauth_consumer_key=\"xvz1evFS4wEEPTGEFPHBog\", oauth_nonce=\"abc\""));
+        assert!(header.contains("oauth_callback=\"oob\""));
+        assert!(header.contains("oauth_signature=\""));
+        assert!(!header.contains("oauth_token="));
+    }
+
+    #[test]
+    fn test_parse_token_response() {
+        let (token, token_secret, screen_name) = parse_token_response("oauth_token=a&oauth_token_secret=b&user_id=1&screen_name=vortis_pr").unwrap();
+        assert_eq!(("a", "b", "vortis_pr"), (token.as_str(), token_secret.as_str(), screen_name.as_str()));
+        assert!(parse_token_response("error").is_err());
+    }
+}
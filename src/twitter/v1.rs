@@ -0,0 +1,323 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_derive::Deserialize;
+use serde_json::Value;
+
+use crate::error::Error;
+use crate::source::TweetKind;
+use super::{Entities, Media, References, Tweet, UrlEntity, User, UserMention};
+
+// fields every status has are required, so a payload of another shape fails to parse.
+#[derive(Debug, Deserialize)]
+pub struct Status {
+    pub id: u64,
+    pub created_at: String,
+    pub full_text: Option<String>,
+    pub text: Option<String>,
+    pub user: StatusUser,
+    #[serde(default)]
+    pub entities: StatusEntities,
+    pub extended_entities: Option<ExtendedEntities>,
+    // full text and entities of a truncated status of the streaming API
+    pub extended_tweet: Option<ExtendedTweet>,
+    pub retweeted_status: Option<Box<Status>>,
+    pub quoted_status: Option<Box<Status>>,
+    pub quoted_status_id: Option<u64>,
+    pub in_reply_to_status_id: Option<u64>,
+    pub in_reply_to_screen_name: Option<String>,
+    pub in_reply_to_user_id: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatusUser {
+    pub id: u64,
+    pub screen_name: String,
+    pub name: String,
+    pub protected: Option<bool>,
+    pub verified: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct StatusEntities {
+    #[serde(default)]
+    pub urls: Vec<UrlItem>,
+    #[serde(default)]
+    pub media: Vec<UrlItem>,
+    #[serde(default)]
+    pub hashtags: Vec<TextItem>,
+    #[serde(default)]
+    pub user_mentions: Vec<MentionItem>,
+    #[serde(default)]
+    pub symbols: Vec<TextItem>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UrlItem {
+    pub url: String,
+    pub expanded_url: Option<String>,
+    pub display_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TextItem {
+    pub text: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MentionItem {
+    pub id: u64,
+    pub screen_name: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ExtendedEntities {
+    #[serde(default)]
+    pub media: Vec<MediaItem>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MediaItem {
+    pub id_str: String,
+    #[serde(rename = "type")]
+    pub media_type: String,
+    pub media_url_https: Option<String>,
+    pub video_info: Option<VideoInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VideoInfo {
+    #[serde(default)]
+    pub variants: Vec<Variant>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Variant {
+    pub content_type: String,
+    pub bitrate: Option<u64>,
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExtendedTweet {
+    pub full_text: String,
+    pub entities: Option<StatusEntities>,
+    pub extended_entities: Option<ExtendedEntities>,
+}
+
+impl Status {
+    fn untruncated_text(&self) -> Result<String, Error> {
+        if let Some(ref retweeted_status) = self.retweeted_status {
+            return Ok(format!("RT @{}: {}", retweeted_status.user.screen_name, retweeted_status.untruncated_text()?));
+        }
+
+        let text = self.full_text.as_ref()
+            .or_else(|| self.extended_tweet.as_ref().map(|extended_tweet| &extended_tweet.full_text))
+            .or(self.text.as_ref());
+        text.cloned().ok_or_else(|| Error::PayloadError(format!("status {} has neither full_text nor text", self.id)))
+    }
+
+    fn content(&self) -> &Status {
+        self.retweeted_status.as_deref().unwrap_or(self)
+    }
+
+    fn entities(&self) -> Entities {
+        let status = self.content();
+        let entities = status.extended_tweet.as_ref().and_then(|extended_tweet| extended_tweet.entities.as_ref()).unwrap_or(&status.entities);
+        Entities {
+            urls: entities.urls.iter().chain(entities.media.iter()).map(|item| UrlEntity {
+                url: item.url.clone(),
+                expanded_url: item.expanded_url.clone().unwrap_or_default(),
+                display_url: item.display_url.clone().unwrap_or_default(),
+            }).collect(),
+            hashtags: entities.hashtags.iter().map(|item| item.text.clone()).collect(),
+            user_mentions: entities.user_mentions.iter().map(|item| UserMention {
+                id: item.id,
+                screen_name: item.screen_name.clone(),
+            }).collect(),
+            symbols: entities.symbols.iter().map(|item| item.text.clone()).collect(),
+        }
+    }
+
+    fn references(&self) -> References {
+        let status = self.content();
+        References {
+            quoted_status_id: status.quoted_status_id.unwrap_or(0),
+            in_reply_to_status_id: status.in_reply_to_status_id.unwrap_or(0),
+            in_reply_to_screen_name: status.in_reply_to_screen_name.clone().unwrap_or_default(),
+        }
+    }
+
+    fn media(&self) -> Vec<Media> {
+        let status = self.content();
+        let extended_entities = status.extended_tweet.as_ref()
+            .and_then(|extended_tweet| extended_tweet.extended_entities.as_ref())
+            .or(status.extended_entities.as_ref());
+        let items = match extended_entities {
+            Some(extended_entities) => &extended_entities.media[..],
+            None => &[],
+        };
+
+        items.iter().filter_map(|item| {
+            let url = match item.media_type.as_str() {
+                "photo" => item.media_url_https.clone(),
+                // the MP4 variant of the highest bitrate.
+                _ => item.video_info.as_ref()?.variants.iter()
+                    .filter(|variant| variant.content_type == "video/mp4")
+                    .max_by_key(|variant| variant.bitrate.unwrap_or(0))
+                    .map(|variant| variant.url.clone()),
+            };
+            url.map(|url| Media {
+                media_key: item.id_str.clone(),
+                media_type: item.media_type.clone(),
+                url: url,
+            })
+        }).collect()
+    }
+
+    fn to_tweet(&self, raw_json: String) -> Result<Tweet, Error> {
+        Ok(Tweet {
+            id: self.id,
+            created_at: DateTime::parse_from_str(&self.created_at, "%a %b %e %T %z %Y")?.with_timezone(&Utc),
+            user_id: self.user.id,
+            user_name: self.user.name.clone(),
+            screen_name: self.user.screen_name.clone(),
+            text: self.untruncated_text()?,
+            kind: TweetKind::classify(self.retweeted_status.is_some(), &self.references(), self.in_reply_to_user_id == Some(self.user.id)),
+            retweeted_status_id: self.retweeted_status.as_ref().map(|status| status.id).unwrap_or(0),
+            entities: self.entities(),
+            references: self.references(),
+            referenced_tweets: Vec::new(),
+            media: self.media(),
+            raw_json: raw_json,
+        })
+    }
+}
+
+pub fn parse_user(entry: &Value) -> Result<User, Error> {
+    let user = StatusUser::deserialize(entry).map_err(|err| Error::PayloadError(format!("user: {}", err)))?;
+    Ok(User {
+        id: user.id,
+        screen_name: user.screen_name,
+        name: user.name,
+        protected: user.protected.unwrap_or(false),
+        verified: user.verified.unwrap_or(false),
+    })
+}
+
+pub fn parse_status(entry: &Value) -> Result<Tweet, Error> {
+    let status = Status::deserialize(entry).map_err(|err| Error::PayloadError(format!("status {}: {}", entry["id"], err)))?;
+    status.to_tweet(entry.to_string())
+}
+
+pub fn parse_statuses(statuses: &Value) -> Result<Vec<Tweet>, Error> {
+    let entries = statuses.as_array().ok_or_else(|| Error::PayloadError(format!("statuses are not an array: {}", statuses)))?;
+
+    let mut results: Vec<Tweet> = Vec::new();
+    for entry in entries {
+        let status = Status::deserialize(entry).map_err(|err| Error::PayloadError(format!("status {}: {}", entry["id"], err)))?;
+        let mut tweet = status.to_tweet(entry.to_string())?;
+
+        // a replied-to status never comes with the status, while a quoted one does.
+        let raw_status = if status.retweeted_status.is_some() { &entry["retweeted_status"] } else { entry };
+        if let Some(ref quoted_status) = status.content().quoted_status {
+            tweet.referenced_tweets.push(quoted_status.to_tweet(raw_status["quoted_status"].to_string())?);
+        }
+        results.push(tweet);
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn fixture() -> Value {
+        serde_json::from_str(include_str!("../../tests/fixtures/twitter/user_timeline.json")).unwrap()
+    }
+
+    #[test]
+    fn test_parse_statuses() {
+        let statuses = fixture();
+        let tweets = parse_statuses(&statuses).unwrap();
+        assert_eq!(4, tweets.len());
+
+        let reply = &tweets[0];
+        assert_eq!(1259093094585434112, reply.id);
+        assert_eq!("tokushima_vortis", reply.screen_name);
+        assert_eq!("徳島ヴォルティス", reply.user_name);
+        assert_eq!("@vortis_pr 応援ありがとうございます！ https://t.co/abcdefghij", reply.text);
+        assert_eq!(1259093094585433999, reply.references.in_reply_to_status_id);
+        assert_eq!("vortis_pr", reply.references.in_reply_to_screen_name);
+        assert_eq!("https://www.vortis.jp/", reply.entities.urls[0].expanded_url);
+        assert_eq!("vortis_pr", reply.entities.user_mentions[0].screen_name);
+        assert_eq!(TweetKind::Reply, reply.kind);
+        assert_eq!(statuses[0], serde_json::from_str::<Value>(&reply.raw_json).unwrap());
+
+        let retweet = &tweets[1];
+        assert_eq!(1259093094585433000, retweet.retweeted_status_id);
+        assert_eq!(TweetKind::Retweet, retweet.kind);
+        assert_eq!("RT @jleaguejp: 明治安田生命J2リーグ 試合日程のお知らせ #Jリーグ", retweet.text);
+        assert_eq!(vec![String::from("Jリーグ")], retweet.entities.hashtags);
+
+        let quote = &tweets[2];
+        assert_eq!(1259093094585432000, quote.references.quoted_status_id);
+        assert_eq!(TweetKind::Quote, quote.kind);
+        assert_eq!(1, quote.referenced_tweets.len());
+        assert_eq!("jleaguejp", quote.referenced_tweets[0].screen_name);
+        assert_eq!(statuses[2]["quoted_status"], serde_json::from_str::<Value>(&quote.referenced_tweets[0].raw_json).unwrap());
+
+        assert_eq!(TweetKind::Original, tweets[3].kind);
+        let media = &tweets[3].media;
+        assert_eq!(2, media.len());
+        assert_eq!("photo", media[0].media_type);
+        assert_eq!("https://pbs.twimg.com/media/EXb3tIaU0AAuAe8.jpg", media[0].url);
+        assert_eq!("1259093094585431002", media[1].media_key);
+        assert_eq!("https://video.twimg.com/ext_tw_video/1/pu/vid/1280x720/b-2176.mp4", media[1].url);
+    }
+
+    #[test]
+    fn test_parse_kind() {
+        let mut statuses = fixture();
+        // the raw json stored with a tweet is classified like the parsed status.
+        for (status, tweet) in statuses.as_array().unwrap().iter().zip(parse_statuses(&statuses).unwrap()) {
+            assert_eq!(tweet.kind, TweetKind::from_raw_json(status));
+        }
+
+        statuses[0]["in_reply_to_user_id"] = statuses[0]["user"]["id"].clone();
+        statuses[0]["in_reply_to_screen_name"] = statuses[0]["user"]["screen_name"].clone();
+        assert_eq!(TweetKind::SelfThread, parse_statuses(&statuses).unwrap()[0].kind);
+        assert_eq!(TweetKind::SelfThread, TweetKind::from_raw_json(&statuses[0]));
+    }
+
+    #[test]
+    fn test_parse_statuses_with_missing_fields() {
+        let mut statuses = fixture();
+        statuses[1].as_object_mut().unwrap().remove("id");
+        let err = parse_statuses(&statuses).unwrap_err().to_string();
+        assert!(err.contains("missing field `id`"), "{}", err);
+
+        let mut statuses = fixture();
+        statuses[0]["user"].as_object_mut().unwrap().remove("screen_name");
+        let err = parse_statuses(&statuses).unwrap_err().to_string();
+        assert!(err.contains("status 1259093094585434112"), "{}", err);
+        assert!(err.contains("missing field `screen_name`"), "{}", err);
+
+        let mut statuses = fixture();
+        statuses[0].as_object_mut().unwrap().remove("full_text");
+        assert!(parse_statuses(&statuses).is_err());
+
+        assert!(parse_statuses(&json!({ "errors": [{ "code": 34, "message": "Sorry, that page does not exist." }] })).is_err());
+    }
+
+    #[test]
+    fn test_parse_user() {
+        let user = parse_user(&fixture()[0]["user"]).unwrap();
+        assert_eq!(260590436, user.id);
+        assert_eq!("tokushima_vortis", user.screen_name);
+        assert!(user.verified);
+        assert!(parse_user(&json!({ "screen_name": "tokushima_vortis" })).is_err());
+    }
+}
@@ -0,0 +1,506 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_derive::Deserialize;
+use serde_json::{self, Value};
+
+use crate::error::Error;
+use crate::source::TweetKind;
+use super::{Account, Auth, Entities, Media, References, Tweet, TwitterClient, UrlEntity, User, UserMention, USER_AGENT};
+
+pub const USER_BY_USERNAME_ENDPOINT: &'static str = "/2/users/by/username/:username";
+pub const USER_TWEETS_ENDPOINT: &'static str = "/2/users/:id/tweets";
+pub const SEARCH_RECENT_ENDPOINT: &'static str = "/2/tweets/search/recent";
+pub const TWEETS_LOOKUP_ENDPOINT: &'static str = "/2/tweets";
+
+// count of tweets user_timeline of v1.1 returns by default.
+const DEFAULT_COUNT: u32 = 20;
+const MIN_RESULTS: u32 = 5;
+const MIN_SEARCH_RESULTS: u32 = 10;
+const MAX_RESULTS: u32 = 100;
+const TWEET_FIELDS: &'static str = "created_at,author_id,referenced_tweets,in_reply_to_user_id,entities";
+const EXPANSIONS: &'static str = "author_id,referenced_tweets.id,referenced_tweets.id.author_id,attachments.media_keys";
+const USER_FIELDS: &'static str = "name,username";
+const LOOKUP_USER_FIELDS: &'static str = "name,username,protected,verified";
+const MEDIA_FIELDS: &'static str = "type,url,variants";
+
+// fields every tweet has are required, so a payload of another shape fails to parse.
+#[derive(Debug, Deserialize)]
+pub struct TweetData {
+    pub id: String,
+    pub author_id: String,
+    pub created_at: String,
+    pub text: String,
+    #[serde(default)]
+    pub referenced_tweets: Vec<ReferencedTweet>,
+    pub in_reply_to_user_id: Option<String>,
+    #[serde(default)]
+    pub entities: TweetEntities,
+    pub attachments: Option<Attachments>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReferencedTweet {
+    #[serde(rename = "type")]
+    pub reference_type: String,
+    pub id: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct TweetEntities {
+    #[serde(default)]
+    pub urls: Vec<UrlItem>,
+    #[serde(default)]
+    pub hashtags: Vec<TagItem>,
+    #[serde(default)]
+    pub mentions: Vec<MentionItem>,
+    #[serde(default)]
+    pub cashtags: Vec<TagItem>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UrlItem {
+    pub url: String,
+    pub expanded_url: Option<String>,
+    pub display_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TagItem {
+    pub tag: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MentionItem {
+    pub id: String,
+    pub username: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Attachments {
+    #[serde(default)]
+    pub media_keys: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UserData {
+    pub id: String,
+    pub name: String,
+    pub username: String,
+    pub protected: Option<bool>,
+    pub verified: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MediaData {
+    pub media_key: String,
+    #[serde(rename = "type")]
+    pub media_type: String,
+    pub url: Option<String>,
+    #[serde(default)]
+    pub variants: Vec<Variant>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Variant {
+    pub content_type: String,
+    pub bit_rate: Option<u64>,
+    pub url: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Includes {
+    #[serde(default)]
+    pub users: Vec<UserData>,
+    // kept as they are, since the quoted and replied-to tweets are stored with their raw JSON.
+    #[serde(default)]
+    pub tweets: Vec<Value>,
+    #[serde(default)]
+    pub media: Vec<MediaData>,
+}
+
+fn parse_id(id: &str) -> Result<u64, Error> {
+    id.parse().map_err(|_| Error::PayloadError(format!("invalid id: {}", id)))
+}
+
+impl TweetData {
+    fn referenced_id(&self, reference_type: &str) -> Result<u64, Error> {
+        match self.referenced_tweets.iter().find(|r| r.reference_type == reference_type) {
+            Some(r) => parse_id(&r.id),
+            None => Ok(0),
+        }
+    }
+
+    fn entities(&self) -> Result<Entities, Error> {
+        let entities = &self.entities;
+        Ok(Entities {
+            urls: entities.urls.iter().map(|item| UrlEntity {
+                url: item.url.clone(),
+                expanded_url: item.expanded_url.clone().unwrap_or_default(),
+                display_url: item.display_url.clone().unwrap_or_default(),
+            }).collect(),
+            hashtags: entities.hashtags.iter().map(|item| item.tag.clone()).collect(),
+            user_mentions: entities.mentions.iter().map(|item| Ok(UserMention {
+                id: parse_id(&item.id)?,
+                screen_name: item.username.clone(),
+            })).collect::<Result<_, Error>>()?,
+            symbols: entities.cashtags.iter().map(|item| item.tag.clone()).collect(),
+        })
+    }
+
+    // a v2 tweet only gives the id of the account replied to, not its screen name.
+    fn references(&self, includes: &Includes) -> Result<References, Error> {
+        let replied_user = self.in_reply_to_user_id.as_ref().and_then(|id| includes.user(id));
+        Ok(References {
+            quoted_status_id: self.referenced_id("quoted")?,
+            in_reply_to_status_id: self.referenced_id("replied_to")?,
+            in_reply_to_screen_name: replied_user.map(|user| user.username.clone()).unwrap_or_default(),
+        })
+    }
+
+    fn media(&self, includes: &Includes) -> Vec<Media> {
+        let media_keys = match self.attachments {
+            Some(ref attachments) => &attachments.media_keys[..],
+            None => &[],
+        };
+
+        media_keys.iter()
+            .filter_map(|key| includes.media.iter().find(|item| item.media_key == *key))
+            .filter_map(|item| {
+                let url = match item.media_type.as_str() {
+                    "photo" => item.url.clone(),
+                    // the MP4 variant of the highest bitrate.
+                    _ => item.variants.iter()
+                        .filter(|variant| variant.content_type == "video/mp4")
+                        .max_by_key(|variant| variant.bit_rate.unwrap_or(0))
+                        .map(|variant| variant.url.clone()),
+                };
+                url.map(|url| Media {
+                    media_key: item.media_key.clone(),
+                    media_type: item.media_type.clone(),
+                    url: url,
+                })
+            })
+            .collect()
+    }
+}
+
+impl Includes {
+    fn user(&self, id: &str) -> Option<&UserData> {
+        self.users.iter().find(|user| user.id == id)
+    }
+
+    fn tweet(&self, id: u64) -> Option<&Value> {
+        let id = id.to_string();
+        self.tweets.iter().find(|tweet| tweet["id"] == id.as_str())
+    }
+}
+
+fn parse_data(entry: &Value) -> Result<TweetData, Error> {
+    TweetData::deserialize(entry).map_err(|err| Error::PayloadError(format!("tweet {}: {}", entry["id"], err)))
+}
+
+fn parse_tweet(entry: &Value, includes: &Includes) -> Result<Tweet, Error> {
+    let data = parse_data(entry)?;
+    let author = includes.user(&data.author_id);
+    let retweeted_status_id = data.referenced_id("retweeted")?;
+
+    // text of a retweet is truncated, so it is rebuilt from the retweeted tweet when it is included.
+    let retweeted_status = match includes.tweet(retweeted_status_id) {
+        Some(entry) => Some(parse_data(entry)?),
+        None => None,
+    };
+    let retweeted_user = retweeted_status.as_ref().and_then(|tweet| includes.user(&tweet.author_id));
+    let text = match (&retweeted_status, retweeted_user) {
+        (Some(tweet), Some(user)) => format!("RT @{}: {}", user.username, tweet.text),
+        _ => data.text.clone(),
+    };
+
+    let status = retweeted_status.as_ref().unwrap_or(&data);
+    let references = status.references(includes)?;
+    let reply_to_self = data.in_reply_to_user_id.as_ref() == Some(&data.author_id);
+
+    Ok(Tweet {
+        id: parse_id(&data.id)?,
+        created_at: DateTime::parse_from_rfc3339(&data.created_at)?.with_timezone(&Utc),
+        user_id: parse_id(&data.author_id)?,
+        user_name: author.map(|user| user.name.clone()).unwrap_or_default(),
+        screen_name: author.map(|user| user.username.clone()).unwrap_or_default(),
+        text: text,
+        kind: TweetKind::classify(retweeted_status_id != 0, &data.references(includes)?, reply_to_self),
+        retweeted_status_id: retweeted_status_id,
+        entities: status.entities()?,
+        references: references,
+        referenced_tweets: Vec::new(),
+        media: status.media(includes),
+        raw_json: entry.to_string(),
+    })
+}
+
+// a stored tweet comes without the users and tweets it refers to.
+pub fn parse_raw_tweet(entry: &Value) -> Result<Tweet, Error> {
+    parse_tweet(entry, &Includes::default())
+}
+
+fn parse_tweets(body: &Value) -> Result<Vec<Tweet>, Error> {
+    let includes = match body.get("includes") {
+        Some(includes) => Includes::deserialize(includes).map_err(|err| Error::PayloadError(format!("includes: {}", err)))?,
+        None => Includes::default(),
+    };
+
+    let mut results: Vec<Tweet> = Vec::new();
+    if let Some(entries) = body["data"].as_array() {
+        for entry in entries {
+            let mut tweet = parse_tweet(entry, &includes)?;
+            for id in &[tweet.references.quoted_status_id, tweet.references.in_reply_to_status_id] {
+                if let Some(referenced) = includes.tweet(*id) {
+                    tweet.referenced_tweets.push(parse_tweet(referenced, &includes)?);
+                }
+            }
+            results.push(tweet);
+        }
+    }
+
+    Ok(results)
+}
+
+impl TwitterClient {
+    pub(super) fn lookup_user_v2(&self, auth: Auth, screen_name: &str) -> Result<User, Error> {
+        let req = self.client.get(&self.url(&format!("/2/users/by/username/{}", screen_name)))
+            .query(&[("user.fields", LOOKUP_USER_FIELDS)])
+            .header(reqwest::header::USER_AGENT, USER_AGENT);
+        let res = self.send_authorized(USER_BY_USERNAME_ENDPOINT, req, auth)?;
+
+        // v2 answers 200 with "errors" when the user does not exist or is suspended.
+        let body: Value = serde_json::from_reader(res)?;
+        let data = &body["data"];
+        if !data.is_object() {
+            return Err(Error::UserNotFoundError(String::from(screen_name)));
+        }
+        let user = UserData::deserialize(data).map_err(|err| Error::PayloadError(format!("user: {}", err)))?;
+        Ok(User {
+            id: parse_id(&user.id)?,
+            screen_name: user.username,
+            name: user.name,
+            protected: user.protected.unwrap_or(false),
+            verified: user.verified.unwrap_or(false),
+        })
+    }
+
+    pub(super) fn get_tweets_v2(&self, auth: Auth, account: Account, count: Option<u32>, since_id: Option<u64>, max_id: Option<u64>) -> Result<Vec<Tweet>, Error> {
+        let user_id = match account {
+            Account::Id(id) => id,
+            Account::ScreenName(screen_name) => self.lookup_user_v2(auth, screen_name)?.id,
+        };
+        let url = self.url(&format!("/2/users/{}/tweets", user_id));
+        let count = count.unwrap_or(DEFAULT_COUNT) as usize;
+
+        let mut results: Vec<Tweet> = Vec::new();
+        let mut pagination_token: Option<String> = None;
+        loop {
+            let max_results = ((count - results.len()) as u32).clamp(MIN_RESULTS, MAX_RESULTS);
+            let mut builder = self.client.get(&url);
+            builder = builder.query(&[
+                ("max_results", max_results.to_string().as_str()),
+                ("tweet.fields", TWEET_FIELDS),
+                ("expansions", EXPANSIONS),
+                ("user.fields", USER_FIELDS),
+                ("media.fields", MEDIA_FIELDS),
+            ]);
+            if let Some(id) = since_id {
+                builder = builder.query(&[("since_id", &format!("{}", id))]);
+            }
+            // until_id is exclusive while max_id of v1.1 is inclusive.
+            if let Some(id) = max_id {
+                builder = builder.query(&[("until_id", &format!("{}", id + 1))]);
+            }
+            if let Some(ref token) = pagination_token {
+                builder = builder.query(&[("pagination_token", token)]);
+            }
+
+            let req = builder.header(reqwest::header::USER_AGENT, USER_AGENT);
+            let res = self.send_authorized(USER_TWEETS_ENDPOINT, req, auth)?;
+
+            let body: Value = serde_json::from_reader(res)?;
+            results.extend(parse_tweets(&body)?);
+
+            pagination_token = body["meta"]["next_token"].as_str().map(String::from);
+            if pagination_token.is_none() || results.len() >= count {
+                break;
+            }
+        }
+
+        results.truncate(count);
+        Ok(results)
+    }
+
+    pub(super) fn lookup_tweets_v2(&self, auth: Auth, ids: &str) -> Result<Vec<Tweet>, Error> {
+        let req = self.client.get(&self.url(TWEETS_LOOKUP_ENDPOINT))
+            .query(&[
+                ("ids", ids),
+                ("tweet.fields", TWEET_FIELDS),
+                ("expansions", EXPANSIONS),
+                ("user.fields", USER_FIELDS),
+                ("media.fields", MEDIA_FIELDS),
+            ])
+            .header(reqwest::header::USER_AGENT, USER_AGENT);
+        let res = self.send_authorized(TWEETS_LOOKUP_ENDPOINT, req, auth)?;
+
+        // deleted tweets are reported in "errors" instead of "data".
+        let body: Value = serde_json::from_reader(res)?;
+        parse_tweets(&body)
+    }
+
+    pub(super) fn search_tweets_v2(&self, auth: Auth, query: &str, count: Option<u32>, since_id: Option<u64>) -> Result<Vec<Tweet>, Error> {
+        let count = count.unwrap_or(DEFAULT_COUNT);
+        let mut builder = self.client.get(&self.url(SEARCH_RECENT_ENDPOINT));
+        builder = builder.query(&[
+            ("query", query),
+            ("max_results", count.clamp(MIN_SEARCH_RESULTS, MAX_RESULTS).to_string().as_str()),
+            ("tweet.fields", TWEET_FIELDS),
+            ("expansions", EXPANSIONS),
+            ("user.fields", USER_FIELDS),
+            ("media.fields", MEDIA_FIELDS),
+        ]);
+        if let Some(id) = since_id {
+            builder = builder.query(&[("since_id", &format!("{}", id))]);
+        }
+
+        let req = builder.header(reqwest::header::USER_AGENT, USER_AGENT);
+        let res = self.send_authorized(SEARCH_RECENT_ENDPOINT, req, auth)?;
+
+        let body: Value = serde_json::from_reader(res)?;
+        let mut results = parse_tweets(&body)?;
+        results.truncate(count as usize);
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    use crate::source::TweetKind;
+    use super::*;
+
+    #[test]
+    fn test_parse_tweets() {
+        let body = json!({
+            "data": [
+                {
+                    "id": "1259093094585434112",
+                    "author_id": "2244994945",
+                    "created_at": "2020-05-09T12:34:56.000Z",
+                    "text": "RT @tokushima_vortis: テス…",
+                    "referenced_tweets": [{ "type": "retweeted", "id": "1259093094585434000" }],
+                },
+                {
+                    "id": "1259093094585433999",
+                    "author_id": "2244994945",
+                    "created_at": "2020-05-09T12:00:00.000Z",
+                    "text": "テスト",
+                },
+            ],
+            "includes": {
+                "users": [
+                    { "id": "2244994945", "name": "ヴォルティススタジアム", "username": "vortis_pr" },
+                    { "id": "2244994946", "name": "徳島ヴォルティス", "username": "tokushima_vortis" },
+                ],
+                "tweets": [
+                    { "id": "1259093094585434000", "author_id": "2244994946", "created_at": "2020-05-09T12:00:00.000Z", "text": "テストテスト" },
+                ],
+            },
+            "meta": { "result_count": 2 },
+        });
+
+        let tweets = parse_tweets(&body).unwrap();
+        assert_eq!(2, tweets.len());
+        assert_eq!(1259093094585434112, tweets[0].id);
+        assert_eq!(Utc.ymd(2020, 5, 9).and_hms(12, 34, 56), tweets[0].created_at);
+        assert_eq!("ヴォルティススタジアム", tweets[0].user_name);
+        assert_eq!("vortis_pr", tweets[0].screen_name);
+        assert_eq!(1259093094585434000, tweets[0].retweeted_status_id);
+        assert_eq!("RT @tokushima_vortis: テストテスト", tweets[0].text);
+        assert_eq!(TweetKind::Retweet, tweets[0].kind);
+        assert_eq!(0, tweets[1].retweeted_status_id);
+        assert_eq!(TweetKind::Original, tweets[1].kind);
+        assert_eq!("テスト", tweets[1].text);
+    }
+
+    #[test]
+    fn test_parse_tweet_entities_and_references() {
+        let body = json!({
+            "data": [{
+                "id": "1259093094585434112",
+                "author_id": "2244994945",
+                "created_at": "2020-05-09T12:34:56.000Z",
+                "text": "@tokushima_vortis #vortis $TWTR https://t.co/abc",
+                "in_reply_to_user_id": "2244994946",
+                "referenced_tweets": [
+                    { "type": "replied_to", "id": "1259093094585433999" },
+                    { "type": "quoted", "id": "1259093094585434000" },
+                ],
+                "entities": {
+                    "hashtags": [{ "start": 18, "end": 25, "tag": "vortis" }],
+                    "cashtags": [{ "start": 26, "end": 31, "tag": "TWTR" }],
+                    "mentions": [{ "start": 0, "end": 17, "id": "2244994946", "username": "tokushima_vortis" }],
+                    "urls": [{ "start": 32, "end": 55, "url": "https://t.co/abc", "expanded_url": "https://www.vortis.jp/", "display_url": "vortis.jp" }],
+                },
+                "attachments": { "media_keys": ["3_1", "7_2"] },
+            }],
+            "includes": {
+                "users": [
+                    { "id": "2244994945", "name": "ヴォルティススタジアム", "username": "vortis_pr" },
+                    { "id": "2244994946", "name": "徳島ヴォルティス", "username": "tokushima_vortis" },
+                ],
+                "tweets": [
+                    { "id": "1259093094585434000", "author_id": "2244994946", "created_at": "2020-05-09T12:00:00.000Z", "text": "引用元" },
+                ],
+                "media": [
+                    { "media_key": "3_1", "type": "photo", "url": "https://pbs.twimg.com/media/a.jpg" },
+                    { "media_key": "7_2", "type": "video", "variants": [
+                        { "content_type": "video/mp4", "bit_rate": 832000, "url": "https://video.twimg.com/low.mp4" },
+                        { "content_type": "video/mp4", "bit_rate": 2176000, "url": "https://video.twimg.com/high.mp4" },
+                        { "content_type": "application/x-mpegURL", "url": "https://video.twimg.com/pl.m3u8" },
+                    ] },
+                ],
+            },
+        });
+
+        let tweet = parse_tweets(&body).unwrap().remove(0);
+        assert_eq!(TweetKind::Reply, tweet.kind);
+        assert_eq!(vec![String::from("vortis")], tweet.entities.hashtags);
+        assert_eq!(vec![String::from("TWTR")], tweet.entities.symbols);
+        assert_eq!(2244994946, tweet.entities.user_mentions[0].id);
+        assert_eq!("tokushima_vortis", tweet.entities.user_mentions[0].screen_name);
+        assert_eq!("https://www.vortis.jp/", tweet.entities.urls[0].expanded_url);
+        assert_eq!(1259093094585434000, tweet.references.quoted_status_id);
+        assert_eq!(1259093094585433999, tweet.references.in_reply_to_status_id);
+        assert_eq!("tokushima_vortis", tweet.references.in_reply_to_screen_name);
+        assert_eq!(1, tweet.referenced_tweets.len());
+        assert_eq!("引用元", tweet.referenced_tweets[0].text);
+        assert_eq!(2, tweet.media.len());
+        assert_eq!("https://pbs.twimg.com/media/a.jpg", tweet.media[0].url);
+        assert_eq!("https://video.twimg.com/high.mp4", tweet.media[1].url);
+
+        // a stored tweet is parsed without the users it refers to.
+        let tweet = parse_raw_tweet(&body["data"][0]).unwrap();
+        assert_eq!(TweetKind::Reply, tweet.kind);
+        assert_eq!("", tweet.references.in_reply_to_screen_name);
+    }
+
+    #[test]
+    fn test_parse_tweets_with_missing_fields() {
+        for field in &["id", "author_id", "created_at"] {
+            let mut body = json!({
+                "data": [{ "id": "1259093094585434112", "author_id": "2244994945", "created_at": "2020-05-09T12:34:56.000Z", "text": "テスト" }],
+            });
+            body["data"][0].as_object_mut().unwrap().remove(*field);
+            let err = parse_tweets(&body).unwrap_err().to_string();
+            assert!(err.contains(&format!("missing field `{}`", field)), "{}", err);
+        }
+
+        let body = json!({ "data": [{ "id": "abc", "author_id": "2244994945", "created_at": "2020-05-09T12:34:56.000Z", "text": "テスト" }] });
+        assert!(parse_tweets(&body).is_err());
+    }
+}
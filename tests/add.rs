@@ -0,0 +1,44 @@
+mod common;
+
+use common::{tweet, FakeTwitter, TestEnv};
+
+#[test]
+fn test_add_stores_canonical_screen_name() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    let stdout = env.stdout(&["add", "Vortis_PR"]);
+    assert!(stdout.contains("vortis_pr: added"));
+    assert!(stdout.contains("imported 1 tweets"));
+
+    // adding again by another spelling resolves to the same account.
+    let stdout = env.stdout(&["add", "VORTIS_PR"]);
+    assert!(!stdout.contains("added"));
+
+    let stdout = env.stdout(&["list"]);
+    assert_eq!("vortis_pr\tヴォルティススタジアム\t\n", stdout);
+}
+
+#[test]
+fn test_add_fails_on_unknown_or_suspended_account() {
+    let twitter = FakeTwitter::start();
+    twitter.post("tokushima_vortis", tweet(100, "tokushima_vortis", "徳島ヴォルティス", "first"));
+    twitter.state.lock().unwrap().suspended.push(String::from("tokushima_vortis"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    for screen_name in &["vortis_pr_typo", "tokushima_vortis"] {
+        let output = env.run(&["add", screen_name]);
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains(&format!("{} does not exist or is suspended", screen_name)));
+    }
+
+    let stdout = env.stdout(&["list"]);
+    assert_eq!("", stdout);
+
+    env.set_config("api_version", serde_json::json!("2"));
+    let output = env.run(&["add", "vortis_pr_typo"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("vortis_pr_typo does not exist or is suspended"));
+}
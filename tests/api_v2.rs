@@ -0,0 +1,45 @@
+mod common;
+
+use serde_json::json;
+
+use common::{tweet, user_id, FakeTwitter, TestEnv};
+
+#[test]
+fn test_add_and_check_update_with_api_v2() {
+    let twitter = FakeTwitter::start();
+    for id in 100..=130 {
+        twitter.post("vortis_pr", tweet(id, "vortis_pr", "ヴォルティススタジアム", &format!("tweet {}", id)));
+    }
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.set_config("api_version", json!("2"));
+    let stdout = env.stdout(&["add", "vortis_pr"]);
+    assert!(stdout.contains("imported 20 tweets"));
+
+    twitter.post("vortis_pr", tweet(131, "vortis_pr", "ヴォルティススタジアム", "new tweet"));
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains("vortis_pr: imported 1 tweets and send 1 mails"));
+    assert!(env.mails().contains("Subject: 【更新通知】ヴォルティススタジアム"));
+
+    let requests = twitter.server.requests_to(&format!("/2/users/{}/tweets", user_id("vortis_pr")));
+    assert_eq!(Some("130"), requests.last().unwrap().param("since_id"));
+    assert!(twitter.server.requests_to("/1.1/statuses/user_timeline.json").is_empty());
+}
+
+#[test]
+fn test_switch_profile_from_api_v1_to_v2() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.stdout(&["add", "vortis_pr"]);
+
+    env.set_config("api_version", json!("2"));
+    twitter.post("vortis_pr", tweet(101, "vortis_pr", "ヴォルティススタジアム", "second"));
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains("vortis_pr: imported 1 tweets and send 1 mails"));
+    assert!(env.mails().contains("second"));
+
+    let stdout = env.stdout(&["list", "vortis_pr"]);
+    assert_eq!(2, stdout.lines().count());
+}
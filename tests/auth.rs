@@ -0,0 +1,54 @@
+mod common;
+
+use common::{tweet, FakeTwitter, TestEnv};
+
+#[test]
+fn test_access_token_is_cached_across_runs() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+
+    twitter.create_user("tokushima_vortis", "徳島ヴォルティス");
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.stdout(&["add", "vortis_pr"]);
+    env.stdout(&["add", "tokushima_vortis"]);
+    env.stdout(&["check_update"]);
+    assert_eq!(1, twitter.server.requests_to("/oauth2/token").len());
+
+    let stdout = env.stdout(&["auth"]);
+    assert!(stdout.starts_with("AAAA\t"));
+}
+
+#[test]
+fn test_access_token_is_refreshed_when_rejected() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.stdout(&["add", "vortis_pr"]);
+
+    twitter.state.lock().unwrap().access_token = String::from("BBBB");
+    twitter.post("vortis_pr", tweet(101, "vortis_pr", "ヴォルティススタジアム", "second"));
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains("vortis_pr: imported 1 tweets and send 1 mails"));
+    assert_eq!(2, twitter.server.requests_to("/oauth2/token").len());
+
+    let stdout = env.stdout(&["auth"]);
+    assert!(stdout.starts_with("BBBB\t"));
+}
+
+#[test]
+fn test_revoke_access_token() {
+    let twitter = FakeTwitter::start();
+    twitter.create_user("vortis_pr", "ヴォルティススタジアム");
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.stdout(&["add", "vortis_pr"]);
+
+    let stdout = env.stdout(&["auth", "--revoke"]);
+    assert!(stdout.contains("access token is revoked"));
+    let requests = twitter.server.requests_to("/oauth2/invalidate_token");
+    assert_eq!("access_token=AAAA", requests[0].body);
+
+    let stdout = env.stdout(&["auth"]);
+    assert!(stdout.contains("access token is not cached"));
+}
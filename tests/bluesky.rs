@@ -0,0 +1,133 @@
+mod common;
+
+use serde_json::json;
+
+use common::{bluesky_post, bluesky_profile, FakeBluesky, FakeTwitter, TestEnv};
+
+#[test]
+fn test_watch_bluesky_account() {
+    let twitter = FakeTwitter::start();
+    let bluesky = FakeBluesky::start();
+    let vortis = bluesky.create_profile("vortis.test", "徳島ヴォルティス");
+    let supporter = bluesky_profile("supporter.test", "サポーター");
+    let first = bluesky_post(&vortis, "3kfirst", "開幕戦のお知らせ", "2020-05-01T10:00:00.000Z");
+    bluesky.push(&vortis["did"], json!({ "post": first }));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.set_config("bluesky_api_base_url", json!(bluesky.server.base_url));
+    let stdout = env.stdout(&["add", "--bluesky", "vortis.test"]);
+    assert!(stdout.contains("vortis.test: added"));
+    assert!(stdout.contains("imported 1 tweets"));
+
+    let parent = bluesky_post(&supporter, "3kparent", "次も勝とう！", "2020-05-01T11:00:00.000Z");
+    let mut reply = bluesky_post(&vortis, "3kreply", "応援ありがとうございます！", "2020-05-01T12:00:00.000Z");
+    reply["record"]["reply"] = json!({
+        "root": { "uri": parent["uri"], "cid": parent["cid"] },
+        "parent": { "uri": parent["uri"], "cid": parent["cid"] },
+    });
+    bluesky.push(&vortis["did"], json!({ "post": reply, "reply": { "root": parent, "parent": parent } }));
+    // a repost of a stored post is imported without being notified again.
+    bluesky.push(&vortis["did"], json!({
+        "post": first,
+        "reason": { "$type": "app.bsky.feed.defs#reasonRepost", "by": vortis, "indexedAt": "2020-05-01T13:00:00.000Z" },
+    }));
+    bluesky.push(&vortis["did"], json!({ "post": bluesky_post(&vortis, "3kresult", "2-1で勝利しました", "2020-05-01T14:00:00.000Z") }));
+
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains("vortis.test: imported 3 tweets and send 2 mails"), "{}", stdout);
+    // the older page is followed by the cursor until the newest item seen.
+    let requests = bluesky.server.requests_to("/xrpc/app.bsky.feed.getAuthorFeed");
+    assert_eq!(Some("2"), requests.last().unwrap().param("cursor"));
+    assert_eq!(Some("did:plc:vortis"), requests.last().unwrap().param("actor"));
+
+    let mails = env.mails();
+    assert!(mails.contains("Subject: 【更新通知】徳島ヴォルティス"));
+    assert!(mails.contains("URL: https://bsky.app/profile/vortis.test/post/3kresult"));
+    assert!(mails.contains("応援ありがとうございます！\n\n返信先: サポーター (@supporter.test): 次も勝とう！"));
+    assert!(mails.contains("URL: https://bsky.app/profile/vortis.test/post/3kreply"));
+    assert!(!mails.contains("twitter.com"));
+
+    let request_count = requests.len();
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains("vortis.test: imported 0 tweets and send 0 mails"));
+    assert_eq!(request_count + 1, bluesky.server.requests_to("/xrpc/app.bsky.feed.getAuthorFeed").len());
+
+    let stdout = env.stdout(&["list"]);
+    assert!(stdout.contains("vortis.test\t徳島ヴォルティス\tbluesky"));
+    let stdout = env.stdout(&["list", "vortis.test"]);
+    assert!(stdout.contains("\treply to @supporter.test: 次も勝とう！"));
+}
+
+#[test]
+fn test_add_unknown_bluesky_handle() {
+    let twitter = FakeTwitter::start();
+    let bluesky = FakeBluesky::start();
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.set_config("bluesky_api_base_url", json!(bluesky.server.base_url));
+    let output = env.run(&["add", "--bluesky", "nobody.test"]);
+    assert!(!output.status.success());
+    assert!(env.stdout(&["list"]).is_empty());
+}
+
+#[test]
+fn test_check_update_continues_after_failing_bluesky_feed() {
+    let twitter = FakeTwitter::start();
+    let bluesky = FakeBluesky::start();
+    let vortis = bluesky.create_profile("vortis.test", "徳島ヴォルティス");
+    let stadium = bluesky.create_profile("stadium.test", "ヴォルティススタジアム");
+    bluesky.push(&vortis["did"], json!({ "post": bluesky_post(&vortis, "3kfirst", "開幕戦のお知らせ", "2020-05-01T10:00:00.000Z") }));
+    bluesky.push(&stadium["did"], json!({ "post": bluesky_post(&stadium, "3kgate", "開門時間のお知らせ", "2020-05-01T10:00:00.000Z") }));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.set_config("bluesky_api_base_url", json!(bluesky.server.base_url));
+    env.stdout(&["add", "--bluesky", "vortis.test"]);
+    env.stdout(&["add", "--bluesky", "stadium.test"]);
+
+    bluesky.fail(&vortis["did"]);
+    bluesky.push(&stadium["did"], json!({ "post": bluesky_post(&stadium, "3kparking", "駐車場のご案内", "2020-05-01T12:00:00.000Z") }));
+    let output = env.run(&["check_update"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("failed to check vortis.test"), "{}", stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("stadium.test: imported 1 tweets and send 1 mails"), "{}", stdout);
+    assert!(env.mails().contains("駐車場のご案内"));
+}
+
+#[test]
+fn test_check_update_follows_renamed_bluesky_handle() {
+    let twitter = FakeTwitter::start();
+    let bluesky = FakeBluesky::start();
+    let vortis = bluesky.create_profile("vortis.test", "徳島ヴォルティス");
+    bluesky.push(&vortis["did"], json!({ "post": bluesky_post(&vortis, "3kfirst", "開幕戦のお知らせ", "2020-05-01T10:00:00.000Z") }));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.set_config("bluesky_api_base_url", json!(bluesky.server.base_url));
+    env.stdout(&["add", "--bluesky", "vortis.test"]);
+
+    // the newest post tells the new handle, whatever the ids of the posts are.
+    let mut renamed = vortis.clone();
+    renamed["handle"] = json!("vortis.example");
+    bluesky.push(&vortis["did"], json!({ "post": bluesky_post(&vortis, "3kbefore", "試合前", "2020-05-01T11:00:00.000Z") }));
+    bluesky.push(&vortis["did"], json!({ "post": bluesky_post(&renamed, "3kafter", "試合後", "2020-05-01T12:00:00.000Z") }));
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains("vortis.test: renamed to vortis.example"), "{}", stdout);
+    assert!(stdout.contains("vortis.example: imported 2 tweets and send 2 mails"), "{}", stdout);
+}
+
+#[test]
+fn test_add_same_name_on_twitter_and_bluesky() {
+    let twitter = FakeTwitter::start();
+    twitter.create_user("vortis_pr", "ヴォルティススタジアム");
+    let bluesky = FakeBluesky::start();
+    let vortis = bluesky.create_profile("vortis_pr", "徳島ヴォルティス");
+    bluesky.push(&vortis["did"], json!({ "post": bluesky_post(&vortis, "3kfirst", "開幕戦のお知らせ", "2020-05-01T10:00:00.000Z") }));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.set_config("bluesky_api_base_url", json!(bluesky.server.base_url));
+    env.stdout(&["add", "vortis_pr"]);
+    let stdout = env.stdout(&["add", "--bluesky", "vortis_pr"]);
+    assert!(stdout.contains("vortis_pr: added"), "{}", stdout);
+    assert!(stdout.contains("imported 1 tweets"), "{}", stdout);
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use serde_json::json;
+
+use common::{tweet, FakeTwitter, TestEnv};
+
+#[test]
+fn test_add_and_check_update() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+    twitter.post("vortis_pr", tweet(101, "vortis_pr", "ヴォルティススタジアム", "second"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    let stdout = env.stdout(&["add", "vortis_pr"]);
+    assert!(stdout.contains("vortis_pr: added"));
+    assert!(stdout.contains("imported 2 tweets"));
+
+    twitter.post("vortis_pr", tweet(102, "vortis_pr", "ヴォルティススタジアム", "third"));
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains("vortis_pr: imported 1 tweets and send 1 mails"));
+
+    let timeline_requests = twitter.server.requests_to("/1.1/statuses/user_timeline.json");
+    assert_eq!(Some("101"), timeline_requests.last().unwrap().param("since_id"));
+
+    let mails = env.mails();
+    assert!(mails.contains("Subject: 【更新通知】ヴォルティススタジアム"));
+    assert!(mails.contains("third"));
+    assert!(!mails.contains("second"));
+
+    let stdout = env.stdout(&["list", "vortis_pr"]);
+    assert_eq!(3, stdout.lines().count());
+}
+
+#[test]
+fn test_backfill() {
+    let twitter = FakeTwitter::start();
+    for id in 1..=450 {
+        twitter.post("vortis_pr", tweet(id, "vortis_pr", "ヴォルティススタジアム", &format!("tweet {}", id)));
+    }
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.stdout(&["add", "vortis_pr"]);
+
+    let stdout = env.stdout(&["backfill", "vortis_pr", "--max", "200"]);
+    assert!(stdout.contains("vortis_pr: imported 200 tweets"));
+    let stdout = env.stdout(&["backfill", "vortis_pr"]);
+    assert!(stdout.contains("reached the end of timeline"));
+    assert!(stdout.contains("vortis_pr: imported 230 tweets"));
+    let stdout = env.stdout(&["backfill", "vortis_pr"]);
+    assert!(stdout.contains("already backfilled"));
+
+    let stdout = env.stdout(&["list", "vortis_pr", "--max", "1000"]);
+    assert_eq!(450, stdout.lines().count());
+}
+
+#[test]
+fn test_check_update_defers_users_on_rate_limit() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+    twitter.create_user("tokushima_vortis", "徳島ヴォルティス");
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.stdout(&["add", "vortis_pr"]);
+    env.stdout(&["add", "tokushima_vortis"]);
+
+    twitter.state.lock().unwrap().rate_limited = true;
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains("2 users are deferred to next run"));
+    assert!(env.mails().is_empty());
+}
+
+#[test]
+fn test_check_update_continues_after_failing_user() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+    twitter.post("tokushima_vortis", tweet(101, "tokushima_vortis", "徳島ヴォルティス", "first"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.stdout(&["add", "vortis_pr"]);
+    env.stdout(&["add", "tokushima_vortis"]);
+
+    twitter.state.lock().unwrap().suspended.push(String::from("vortis_pr"));
+    twitter.post("vortis_pr", tweet(102, "vortis_pr", "ヴォルティススタジアム", "parking"));
+    twitter.post("tokushima_vortis", tweet(103, "tokushima_vortis", "徳島ヴォルティス", "kickoff"));
+    let output = env.run(&["check_update"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("failed to check vortis_pr"), "{}", stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("tokushima_vortis: imported 1 tweets and send 1 mails"), "{}", stdout);
+    assert!(env.mails().contains("kickoff"));
+    assert!(!env.mails().contains("parking"));
+}
+
+#[test]
+fn test_check_update_expands_urls_in_mail() {
+    let twitter = FakeTwitter::start();
+    twitter.create_user("vortis_pr", "ヴォルティススタジアム");
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.stdout(&["add", "vortis_pr"]);
+
+    let mut status = tweet(100, "vortis_pr", "ヴォルティススタジアム", "試合日程 https://t.co/abc #vortis");
+    status["entities"]["urls"] = json!([{ "url": "https://t.co/abc", "expanded_url": "https://www.vortis.jp/schedule/", "display_url": "vortis.jp/schedule/" }]);
+    status["entities"]["hashtags"] = json!([{ "text": "vortis" }]);
+    twitter.post("vortis_pr", status);
+    env.stdout(&["check_update"]);
+
+    let mails = env.mails();
+    assert!(mails.contains("試合日程 https://www.vortis.jp/schedule/ #vortis"));
+    assert!(!mails.contains("https://t.co/abc"));
+}
@@ -0,0 +1,1028 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{self, json, Value};
+use tempfile::TempDir;
+
+const GMAIL_COMMAND: &'static str = r#"#!/bin/sh
+# gmail send <file> --list-id <list-id> --subject <subject> --to <to>... [--attach <file>...]
+subject="$6"
+file="$2"
+{
+  echo "Subject: $subject"
+  while [ $# -gt 0 ]; do
+    if [ "$1" = "--attach" ]; then echo "Attach: $2"; fi
+    shift
+  done
+  cat "$file"; echo; echo "----"
+} >> "$(dirname "$0")/mails.log"
+"#;
+
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    // host of an absolute URL requested through the server as a proxy
+    pub proxied_host: Option<String>,
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Request {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.query.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Response {
+    pub fn new(status: u16, body: &str) -> Response {
+        Response {
+            status: status,
+            headers: vec![(String::from("Content-Type"), String::from("application/json"))],
+            body: String::from(body),
+        }
+    }
+
+    pub fn json(body: &Value) -> Response {
+        Response::new(200, &body.to_string())
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Response {
+        self.headers.push((String::from(name), String::from(value)));
+        self
+    }
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap();
+                decoded.push(u8::from_str_radix(hex, 16).unwrap());
+                i += 2;
+            },
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8(decoded).unwrap()
+}
+
+pub fn parse_params(s: &str) -> Vec<(String, String)> {
+    s.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let mut kv = pair.splitn(2, '=');
+            let key = percent_decode(kv.next().unwrap());
+            let value = percent_decode(kv.next().unwrap_or(""));
+            (key, value)
+        })
+        .collect()
+}
+
+fn read_request(stream: &TcpStream) -> Option<Request> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).ok()?;
+    let mut parts = request_line.split_whitespace();
+    let method = String::from(parts.next()?);
+    let mut target = parts.next()?;
+    let mut proxied_host = None;
+    if let Some(url) = target.strip_prefix("http://") {
+        let index = url.find('/').unwrap_or(url.len());
+        proxied_host = Some(String::from(&url[..index]));
+        target = &url[index..];
+    }
+    let mut target_parts = target.splitn(2, '?');
+    let path = String::from(target_parts.next()?);
+    let query = parse_params(target_parts.next().unwrap_or(""));
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let mut kv = line.splitn(2, ':');
+        headers.push((String::from(kv.next()?.trim()), String::from(kv.next().unwrap_or("").trim())));
+    }
+
+    let content_length: usize = headers.iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).ok()?;
+
+    Some(Request {
+        method: method,
+        proxied_host: proxied_host,
+        path: path,
+        query: query,
+        headers: headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    })
+}
+
+fn write_response(mut stream: TcpStream, res: Response) {
+    let mut head = format!("HTTP/1.1 {} Fake\r\nContent-Length: {}\r\nConnection: close\r\n", res.status, res.body.len());
+    for (name, value) in &res.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes()).ok();
+    stream.write_all(res.body.as_bytes()).ok();
+}
+
+// HTTP server running in a background thread, which answers every request with the given handler.
+pub struct FakeServer {
+    pub base_url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl FakeServer {
+    pub fn start<F>(handler: F) -> FakeServer
+        where F: Fn(&Request) -> Response + Send + 'static
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => continue,
+                };
+                if let Some(req) = read_request(&stream) {
+                    let res = handler(&req);
+                    recorded.lock().unwrap().push(req);
+                    write_response(stream, res);
+                }
+            }
+        });
+
+        FakeServer { base_url: base_url, requests: requests }
+    }
+
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub fn requests_to(&self, path: &str) -> Vec<Request> {
+        self.requests().into_iter().filter(|req| req.path == path).collect()
+    }
+}
+
+// Numeric user id of the screen name, which is stable among tests.
+pub fn user_id(screen_name: &str) -> u64 {
+    screen_name.bytes().fold(1000, |id, b| (id * 31 + b as u64) % 1_000_000_007)
+}
+
+pub fn tweet(id: u64, screen_name: &str, name: &str, text: &str) -> Value {
+    json!({
+        "id": id,
+        "id_str": id.to_string(),
+        "created_at": "Sat May 09 12:34:56 +0000 2020",
+        "text": text,
+        "user": {
+            "id": user_id(screen_name),
+            "id_str": user_id(screen_name).to_string(),
+            "name": name,
+            "screen_name": screen_name,
+        },
+        "entities": { "hashtags": [], "symbols": [], "user_mentions": [], "urls": [] },
+    })
+}
+
+pub fn retweet(id: u64, screen_name: &str, name: &str, retweeted_status: Value) -> Value {
+    let mut status = tweet(id, screen_name, name, &format!("RT @{}: {}", retweeted_status["user"]["screen_name"].as_str().unwrap(), retweeted_status["text"].as_str().unwrap()));
+    status["retweeted_status"] = retweeted_status;
+    status
+}
+
+// Makes the tweet quote the status, which comes with it as v1.1 does.
+pub fn with_quote(mut tweet: Value, quoted_status: Value) -> Value {
+    tweet["is_quote_status"] = json!(true);
+    tweet["quoted_status_id"] = quoted_status["id"].clone();
+    tweet["quoted_status_id_str"] = quoted_status["id_str"].clone();
+    tweet["quoted_status"] = quoted_status;
+    tweet
+}
+
+// Makes the tweet a reply to the status, which does not come with it.
+pub fn with_reply(mut tweet: Value, in_reply_to: &Value) -> Value {
+    tweet["in_reply_to_status_id"] = in_reply_to["id"].clone();
+    tweet["in_reply_to_status_id_str"] = in_reply_to["id_str"].clone();
+    tweet["in_reply_to_user_id"] = in_reply_to["user"]["id"].clone();
+    tweet["in_reply_to_user_id_str"] = in_reply_to["user"]["id_str"].clone();
+    tweet["in_reply_to_screen_name"] = in_reply_to["user"]["screen_name"].clone();
+    tweet
+}
+
+// Attaches a photo served by the fake server at /media/<name>.
+pub fn with_photo(mut tweet: Value, base_url: &str, media_key: &str, name: &str) -> Value {
+    tweet["extended_entities"] = json!({
+        "media": [{
+            "id_str": media_key,
+            "type": "photo",
+            "media_url_https": format!("{}/media/{}", base_url, name),
+        }],
+    });
+    tweet
+}
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() <= 140 {
+        return String::from(text);
+    }
+    format!("{}…", text.chars().take(139).collect::<String>())
+}
+
+// Renders a status as v1.1 does: text is truncated at 140 characters unless tweet_mode is extended.
+fn status_v1(tweet: &Value, extended: bool) -> Value {
+    let mut status = tweet.clone();
+    let text = String::from(tweet["text"].as_str().unwrap());
+    if extended {
+        status.as_object_mut().unwrap().remove("text");
+        status["full_text"] = json!(text);
+    } else {
+        status["text"] = json!(truncate(&text));
+        status["truncated"] = json!(truncate(&text) != text);
+    }
+    if tweet["retweeted_status"].is_object() {
+        status["retweeted_status"] = status_v1(&tweet["retweeted_status"], extended);
+    }
+    if tweet["quoted_status"].is_object() {
+        status["quoted_status"] = status_v1(&tweet["quoted_status"], extended);
+    }
+    status
+}
+
+#[derive(Default)]
+pub struct TwitterState {
+    // the bearer token which is issued and accepted
+    pub access_token: String,
+    // tweets per screen name, newest first
+    pub timelines: HashMap<String, Vec<Value>>,
+    // user objects per screen name, registered by posting or `FakeTwitter::create_user`
+    pub users: HashMap<String, Value>,
+    pub rate_limited: bool,
+    // tweets liked per screen name, most recently liked first
+    pub favorites: HashMap<String, Vec<Value>>,
+    // screen names of members per list given as "owner/slug"
+    pub lists: HashMap<String, Vec<String>>,
+    // screen names of suspended accounts, which users lookup does not return
+    pub suspended: Vec<String>,
+    // tweets which exist but are not shown, as when their author is suspended
+    pub hidden: Vec<Value>,
+    // count of the next requests answered with 503 Service Unavailable
+    pub failures: u32,
+    // the user access token which is issued for the PIN and accepted in OAuth 1.0a signed requests
+    pub user_token: String,
+    pub pin: String,
+}
+
+impl TwitterState {
+    // Returns the user object of the screen name, which is case-insensitive.
+    fn find_user(&self, screen_name: &str) -> Option<Value> {
+        if self.suspended.iter().any(|name| name.eq_ignore_ascii_case(screen_name)) {
+            return None;
+        }
+        self.users.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(screen_name))
+            .map(|(_, user)| user.clone())
+    }
+
+    // Returns the posted tweet of the id given as a string, or null.
+    fn find_tweet(&self, id_str: &Value) -> Value {
+        self.timelines.values()
+            .flat_map(|timeline| timeline.iter())
+            .find(|tweet| tweet["id_str"] == *id_str)
+            .cloned()
+            .unwrap_or(Value::Null)
+    }
+}
+
+fn handle_twitter(state: &Mutex<TwitterState>, req: &Request) -> Response {
+    let mut state = state.lock().unwrap();
+    if state.failures > 0 {
+        state.failures -= 1;
+        return Response::new(503, r#"{"errors":[{"code":130,"message":"Over capacity"}]}"#);
+    }
+    // signatures are not verified; a signed request is told by the token it is signed with.
+    let authorization = req.header("Authorization").unwrap_or("");
+    let signed_with = |token: &str| authorization.starts_with("OAuth ") && authorization.contains(&format!("oauth_token=\"{}\"", token));
+    let user_context = signed_with(&state.user_token);
+    if req.path.starts_with("/1.1/") && authorization != format!("Bearer {}", state.access_token) && !user_context {
+        return Response::new(401, r#"{"errors":[{"code":89,"message":"Invalid or expired token."}]}"#);
+    }
+
+    match (req.method.as_str(), req.path.as_str()) {
+        ("POST", "/oauth2/token") => Response::json(&json!({ "token_type": "bearer", "access_token": state.access_token })),
+        ("POST", "/oauth/request_token") if authorization.contains("oauth_callback=\"oob\"") => {
+            Response::new(200, "oauth_token=request-token&oauth_token_secret=request-secret&oauth_callback_confirmed=true")
+        },
+        ("POST", "/oauth/access_token") if signed_with("request-token") && authorization.contains(&format!("oauth_verifier=\"{}\"", state.pin)) => {
+            Response::new(200, &format!("oauth_token={}&oauth_token_secret=user-secret&user_id=1&screen_name=reader", state.user_token))
+        },
+        ("POST", "/oauth/request_token") | ("POST", "/oauth/access_token") => Response::new(401, "Invalid request token"),
+        ("POST", "/oauth2/invalidate_token") => Response::json(&json!({ "access_token": req.body.trim_start_matches("access_token=") })),
+        ("GET", "/1.1/statuses/user_timeline.json") => {
+            if state.rate_limited {
+                return Response::new(429, r#"{"errors":[{"code":88,"message":"Rate limit exceeded"}]}"#)
+                    .header("x-rate-limit-limit", "900")
+                    .header("x-rate-limit-remaining", "0")
+                    .header("x-rate-limit-reset", "4102444800");
+            }
+
+            let id_param = |name: &str| req.param(name).map(|id| id.parse::<u64>().unwrap());
+            let since_id = id_param("since_id").unwrap_or(0);
+            let max_id = id_param("max_id").unwrap_or(u64::MAX);
+            let count = id_param("count").unwrap_or(20) as usize;
+            let extended = req.param("tweet_mode") == Some("extended");
+
+            let screen_name = match req.param("user_id") {
+                Some(id) => state.users.iter()
+                    .find(|(_, user)| user["id_str"] == id)
+                    .map(|(name, _)| name.clone())
+                    .unwrap_or_default(),
+                None => String::from(req.param("screen_name").unwrap_or("")),
+            };
+            if state.suspended.iter().any(|name| name.eq_ignore_ascii_case(&screen_name)) {
+                return Response::new(403, r#"{"errors":[{"code":63,"message":"User has been suspended."}]}"#);
+            }
+            let protected = state.find_user(&screen_name).map(|user| user["protected"] == true).unwrap_or(false);
+            if protected && !user_context {
+                return Response::new(401, r#"{"request":"/1.1/statuses/user_timeline.json","error":"Not authorized."}"#);
+            }
+
+            let timeline = state.timelines.get(&screen_name).cloned().unwrap_or_default();
+            let tweets: Vec<Value> = timeline.into_iter()
+                .filter(|tweet| tweet["id"].as_u64().unwrap() > since_id && tweet["id"].as_u64().unwrap() <= max_id)
+                .take(count)
+                .map(|tweet| status_v1(&tweet, extended))
+                .collect();
+            Response::json(&Value::Array(tweets))
+                .header("x-rate-limit-limit", "900")
+                .header("x-rate-limit-remaining", "899")
+                .header("x-rate-limit-reset", "4102444800")
+        },
+        ("GET", "/1.1/favorites/list.json") => {
+            let count = req.param("count").map(|count| count.parse::<usize>().unwrap()).unwrap_or(20);
+            let extended = req.param("tweet_mode") == Some("extended");
+            let screen_name = match req.param("user_id") {
+                Some(id) => state.users.iter()
+                    .find(|(_, user)| user["id_str"] == id)
+                    .map(|(name, _)| name.clone())
+                    .unwrap_or_default(),
+                None => String::from(req.param("screen_name").unwrap_or("")),
+            };
+
+            let favorites = state.favorites.get(&screen_name).cloned().unwrap_or_default();
+            let statuses: Vec<Value> = favorites.iter().take(count).map(|tweet| status_v1(tweet, extended)).collect();
+            Response::json(&Value::Array(statuses))
+                .header("x-rate-limit-limit", "75")
+                .header("x-rate-limit-remaining", "74")
+                .header("x-rate-limit-reset", "4102444800")
+        },
+        ("GET", "/1.1/search/tweets.json") => {
+            // matches tweets containing the query, ignoring case.
+            let query = req.param("q").unwrap_or("").to_lowercase();
+            let id_param = |name: &str| req.param(name).map(|id| id.parse::<u64>().unwrap());
+            let since_id = id_param("since_id").unwrap_or(0);
+            let count = id_param("count").unwrap_or(15) as usize;
+            let extended = req.param("tweet_mode") == Some("extended");
+
+            let mut tweets: Vec<Value> = state.timelines.values()
+                .flat_map(|timeline| timeline.iter().cloned())
+                .filter(|tweet| tweet["id"].as_u64().unwrap() > since_id)
+                .filter(|tweet| tweet["text"].as_str().unwrap().to_lowercase().contains(&query))
+                .collect();
+            tweets.sort_by_key(|tweet| std::cmp::Reverse(tweet["id"].as_u64().unwrap()));
+            tweets.truncate(count);
+            let statuses: Vec<Value> = tweets.iter().map(|tweet| status_v1(tweet, extended)).collect();
+            Response::json(&json!({ "statuses": statuses, "search_metadata": { "count": count } }))
+        },
+        ("GET", "/1.1/lists/members.json") => {
+            let name = format!("{}/{}", req.param("owner_screen_name").unwrap_or(""), req.param("slug").unwrap_or(""));
+            match state.lists.get(&name) {
+                Some(members) => {
+                    let users: Vec<Value> = members.iter().filter_map(|member| state.find_user(member)).collect();
+                    Response::json(&json!({ "users": users, "next_cursor": 0, "next_cursor_str": "0" }))
+                },
+                None => Response::new(404, r#"{"errors":[{"code":34,"message":"Sorry, that page does not exist."}]}"#),
+            }
+        },
+        ("GET", "/1.1/lists/statuses.json") => {
+            let name = format!("{}/{}", req.param("owner_screen_name").unwrap_or(""), req.param("slug").unwrap_or(""));
+            let members = match state.lists.get(&name) {
+                Some(members) => members,
+                None => return Response::new(404, r#"{"errors":[{"code":34,"message":"Sorry, that page does not exist."}]}"#),
+            };
+
+            let id_param = |name: &str| req.param(name).map(|id| id.parse::<u64>().unwrap());
+            let since_id = id_param("since_id").unwrap_or(0);
+            let count = id_param("count").unwrap_or(20) as usize;
+            let extended = req.param("tweet_mode") == Some("extended");
+
+            let mut tweets: Vec<Value> = members.iter()
+                .flat_map(|member| state.timelines.get(member).cloned().unwrap_or_default())
+                .filter(|tweet| tweet["id"].as_u64().unwrap() > since_id)
+                .collect();
+            tweets.sort_by_key(|tweet| std::cmp::Reverse(tweet["id"].as_u64().unwrap()));
+            tweets.truncate(count);
+            let tweets: Vec<Value> = tweets.iter().map(|tweet| status_v1(tweet, extended)).collect();
+            Response::json(&Value::Array(tweets))
+        },
+        ("GET", "/1.1/statuses/lookup.json") => {
+            // maps every requested id to its status or null when it does not exist.
+            let mut statuses = serde_json::Map::new();
+            for id in req.param("id").unwrap_or("").split(',') {
+                let status = Some(state.find_tweet(&json!(id)))
+                    .filter(Value::is_object)
+                    .map(|tweet| status_v1(&tweet, req.param("tweet_mode") == Some("extended")))
+                    .unwrap_or(Value::Null);
+                statuses.insert(String::from(id), status);
+            }
+            Response::json(&json!({ "id": statuses }))
+        },
+        ("GET", "/1.1/statuses/show.json") => {
+            let id = json!(req.param("id").unwrap_or(""));
+            let tweet = state.find_tweet(&id);
+            if tweet.is_object() {
+                Response::json(&status_v1(&tweet, false))
+            } else if state.hidden.iter().any(|tweet| tweet["id_str"] == id) {
+                Response::new(403, r#"{"errors":[{"code":63,"message":"User has been suspended."}]}"#)
+            } else {
+                Response::new(404, r#"{"errors":[{"code":144,"message":"No status found with that ID."}]}"#)
+            }
+        },
+        ("GET", "/1.1/users/lookup.json") => {
+            match state.find_user(req.param("screen_name").unwrap_or("")) {
+                Some(user) => Response::json(&json!([user])),
+                None => Response::new(404, r#"{"errors":[{"code":17,"message":"No user matches for specified terms."}]}"#),
+            }
+        },
+        ("GET", path) if path.starts_with("/2/users/by/username/") => {
+            let screen_name = path.trim_start_matches("/2/users/by/username/");
+            match state.find_user(screen_name) {
+                Some(user) => Response::json(&json!({ "data": user_v2(&user) })),
+                None => Response::json(&json!({ "errors": [{ "title": "Not Found Error", "detail": format!("Could not find user with username: [{}].", screen_name) }] })),
+            }
+        },
+        ("GET", path) if path.starts_with("/2/users/") && path.ends_with("/tweets") => {
+            let id = path.trim_start_matches("/2/users/").trim_end_matches("/tweets").parse::<u64>().unwrap();
+            let id_param = |name: &str| req.param(name).map(|id| id.parse::<u64>().unwrap());
+            let since_id = id_param("since_id").unwrap_or(0);
+            let until_id = id_param("until_id").unwrap_or(u64::MAX);
+            let below_id = id_param("pagination_token").unwrap_or(u64::MAX);
+            let max_results = id_param("max_results").unwrap_or(10) as usize;
+
+            let timeline: Vec<Value> = state.timelines.values()
+                .filter(|timeline| timeline.first().map(|tweet| tweet["user"]["id"] == id).unwrap_or(false))
+                .flat_map(|timeline| timeline.iter().cloned())
+                .filter(|tweet| {
+                    let id = tweet["id"].as_u64().unwrap();
+                    id > since_id && id < until_id && id < below_id
+                })
+                .collect();
+            let page: Vec<Value> = timeline.iter().take(max_results).cloned().collect();
+
+            let mut meta = json!({ "result_count": page.len() });
+            if timeline.len() > page.len() {
+                meta["next_token"] = page.last().unwrap()["id_str"].clone();
+            }
+            // retweeted, quoted and replied-to tweets are included with their authors.
+            let referenced: Vec<Value> = page.iter()
+                .flat_map(|tweet| vec![tweet["retweeted_status"].clone(), tweet["quoted_status"].clone(), state.find_tweet(&tweet["in_reply_to_status_id_str"])])
+                .filter(Value::is_object)
+                .collect();
+            let users: Vec<Value> = page.first().into_iter().chain(referenced.iter()).map(|tweet| user_v2(&tweet["user"])).collect();
+            Response::json(&json!({
+                "data": page.iter().map(tweet_v2).collect::<Vec<Value>>(),
+                "includes": { "users": users, "tweets": referenced.iter().map(tweet_v2).collect::<Vec<Value>>() },
+                "meta": meta,
+            }))
+        },
+        ("GET", path) if path.starts_with("/media/") => Response {
+            status: 200,
+            headers: vec![(String::from("Content-Type"), String::from("image/jpeg"))],
+            body: format!("image of {}", path.trim_start_matches("/media/")),
+        },
+        _ => Response::new(404, r#"{"errors":[{"code":34,"message":"Sorry, that page does not exist."}]}"#),
+    }
+}
+
+fn user_v2(user: &Value) -> Value {
+    json!({
+        "id": user["id_str"],
+        "name": user["name"],
+        "username": user["screen_name"],
+        "protected": user["protected"].as_bool().unwrap_or(false),
+        "verified": user["verified"].as_bool().unwrap_or(false),
+    })
+}
+
+// Renders a tweet as v2 does: only text of a retweet is truncated.
+fn tweet_v2(tweet: &Value) -> Value {
+    let created_at = DateTime::parse_from_str(tweet["created_at"].as_str().unwrap(), "%a %b %e %T %z %Y").unwrap();
+    let mut data = json!({
+        "id": tweet["id_str"],
+        "author_id": tweet["user"]["id_str"],
+        "created_at": created_at.with_timezone(&Utc).to_rfc3339_opts(SecondsFormat::Millis, true),
+        "text": tweet["text"],
+    });
+    if tweet["retweeted_status"].is_object() {
+        data["text"] = json!(truncate(tweet["text"].as_str().unwrap()));
+        data["referenced_tweets"] = json!([{ "type": "retweeted", "id": tweet["retweeted_status"]["id_str"] }]);
+    }
+    if tweet["quoted_status"].is_object() {
+        data["referenced_tweets"] = json!([{ "type": "quoted", "id": tweet["quoted_status"]["id_str"] }]);
+    }
+    if tweet["in_reply_to_status_id_str"].is_string() {
+        data["referenced_tweets"] = json!([{ "type": "replied_to", "id": tweet["in_reply_to_status_id_str"] }]);
+        data["in_reply_to_user_id"] = tweet["in_reply_to_user_id_str"].clone();
+    }
+    data
+}
+
+// Fake of Twitter API which serves timelines put into its state.
+pub struct FakeTwitter {
+    pub server: FakeServer,
+    pub state: Arc<Mutex<TwitterState>>,
+}
+
+impl FakeTwitter {
+    pub fn start() -> FakeTwitter {
+        let state = Arc::new(Mutex::new(TwitterState {
+            access_token: String::from("AAAA"),
+            user_token: String::from("user-token"),
+            pin: String::from("1234567"),
+            ..TwitterState::default()
+        }));
+        let handler_state = state.clone();
+        let server = FakeServer::start(move |req| handle_twitter(&handler_state, req));
+        FakeTwitter { server: server, state: state }
+    }
+
+    pub fn create_user(&self, screen_name: &str, name: &str) {
+        let user = tweet(0, screen_name, name, "")["user"].clone();
+        self.state.lock().unwrap().users.insert(String::from(screen_name), user);
+    }
+
+    // Makes the list given as "owner/slug" consist of the members.
+    pub fn set_list_members(&self, name: &str, members: &[&str]) {
+        let members = members.iter().map(|member| String::from(*member)).collect();
+        self.state.lock().unwrap().lists.insert(String::from(name), members);
+    }
+
+    // Changes the screen name of the account, keeping its id. Tweets posted before keep the old name.
+    pub fn rename(&self, old_screen_name: &str, new_screen_name: &str) {
+        let mut state = self.state.lock().unwrap();
+        let mut user = state.users.remove(old_screen_name).unwrap();
+        user["screen_name"] = json!(new_screen_name);
+        state.users.insert(String::from(new_screen_name), user);
+        if let Some(timeline) = state.timelines.remove(old_screen_name) {
+            state.timelines.insert(String::from(new_screen_name), timeline);
+        }
+    }
+
+    // Makes the tweets of the account readable only by the authorized user.
+    pub fn protect(&self, screen_name: &str) {
+        let mut state = self.state.lock().unwrap();
+        let user = state.users.get_mut(screen_name).unwrap();
+        user["protected"] = json!(true);
+        if let Some(timeline) = state.timelines.get_mut(screen_name) {
+            for tweet in timeline.iter_mut() {
+                tweet["user"]["protected"] = json!(true);
+            }
+        }
+    }
+
+    // Deletes the tweet from the timeline of the account.
+    pub fn delete(&self, screen_name: &str, id: u64) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(timeline) = state.timelines.get_mut(screen_name) {
+            timeline.retain(|tweet| tweet["id"] != id);
+        }
+    }
+
+    // Hides the tweet from the timeline of the account and lookups without deleting it.
+    pub fn hide(&self, screen_name: &str, id: u64) {
+        let mut state = self.state.lock().unwrap();
+        let timeline = state.timelines.get_mut(screen_name).unwrap();
+        let index = timeline.iter().position(|tweet| tweet["id"] == id).unwrap();
+        let tweet = timeline.remove(index);
+        state.hidden.push(tweet);
+    }
+
+    // Makes the account like the tweet.
+    pub fn like(&self, screen_name: &str, tweet: Value) {
+        self.state.lock().unwrap().favorites.entry(String::from(screen_name)).or_default().insert(0, tweet);
+    }
+
+    // Posts a tweet. The author keeps the id it was registered with, e.g. after being renamed.
+    pub fn post(&self, screen_name: &str, mut tweet: Value) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(user) = state.users.get(screen_name) {
+            tweet["user"]["id"] = user["id"].clone();
+            tweet["user"]["id_str"] = user["id_str"].clone();
+            if user["protected"] == true {
+                tweet["user"]["protected"] = json!(true);
+            }
+        }
+        state.users.insert(String::from(screen_name), tweet["user"].clone());
+        state.timelines.entry(String::from(screen_name)).or_default().insert(0, tweet);
+    }
+}
+
+// Returns the content of a file under tests/fixtures.
+pub fn fixture(name: &str) -> String {
+    fs::read_to_string(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)).unwrap()
+}
+
+// Fake of a web site which serves a feed with an ETag, answering 304 to a request with the current one.
+pub struct FakeFeed {
+    pub server: FakeServer,
+    // the feed and the count of its changes, which is its ETag
+    pub state: Arc<Mutex<(String, u32)>>,
+    // whether the site answers 500 Internal Server Error
+    pub failing: Arc<Mutex<bool>>,
+}
+
+impl FakeFeed {
+    pub fn start(body: &str) -> FakeFeed {
+        let state = Arc::new(Mutex::new((String::from(body), 1)));
+        let failing = Arc::new(Mutex::new(false));
+        let handler_state = state.clone();
+        let handler_failing = failing.clone();
+        let server = FakeServer::start(move |req| {
+            if *handler_failing.lock().unwrap() {
+                return Response::new(500, "Internal Server Error");
+            }
+            let state = handler_state.lock().unwrap();
+            let etag = format!("\"v{}\"", state.1);
+            if req.header("If-None-Match") == Some(etag.as_str()) {
+                return Response::new(304, "");
+            }
+            Response::new(200, &state.0)
+                .header("Content-Type", "application/xml; charset=utf-8")
+                .header("ETag", &etag)
+                .header("Last-Modified", "Fri, 01 May 2020 03:00:00 GMT")
+        });
+        FakeFeed { server: server, state: state, failing: failing }
+    }
+
+    pub fn url(&self) -> String {
+        format!("{}/feed.xml", self.server.base_url)
+    }
+
+    pub fn update(&self, body: &str) {
+        let mut state = self.state.lock().unwrap();
+        *state = (String::from(body), state.1 + 1);
+    }
+
+    pub fn fail(&self) {
+        *self.failing.lock().unwrap() = true;
+    }
+}
+
+// Returns a Mastodon status of the account, whose content is HTML.
+pub fn status(id: u64, account: &Value, content: &str) -> Value {
+    json!({
+        "id": id.to_string(),
+        "created_at": "2020-05-02T12:00:00.000Z",
+        "in_reply_to_id": null,
+        "in_reply_to_account_id": null,
+        "spoiler_text": "",
+        "url": format!("https://mstdn.example/@{}/{}", account["username"].as_str().unwrap(), id),
+        "content": content,
+        "reblog": null,
+        "account": account,
+        "media_attachments": [],
+        "mentions": [],
+        "tags": [],
+    })
+}
+
+// Returns a reblog of the status by the account.
+pub fn reblog(id: u64, account: &Value, status: Value) -> Value {
+    json!({
+        "id": id.to_string(),
+        "created_at": "2020-05-02T13:00:00.000Z",
+        "in_reply_to_id": null,
+        "in_reply_to_account_id": null,
+        "spoiler_text": "",
+        "url": null,
+        "content": "",
+        "reblog": status,
+        "account": account,
+        "media_attachments": [],
+        "mentions": [],
+        "tags": [],
+    })
+}
+
+// Fake of a Mastodon instance which serves WebFinger and the statuses of its accounts.
+pub struct FakeMastodon {
+    pub server: FakeServer,
+    pub state: Arc<Mutex<MastodonState>>,
+}
+
+#[derive(Debug, Default)]
+pub struct MastodonState {
+    // accounts by username
+    pub accounts: HashMap<String, Value>,
+    // statuses of each account id, newest first
+    pub statuses: HashMap<String, Vec<Value>>,
+    // account ids whose statuses are answered with 500 Internal Server Error
+    pub failing: Vec<String>,
+}
+
+fn handle_mastodon(state: &Mutex<MastodonState>, req: &Request) -> Response {
+    let state = state.lock().unwrap();
+    let accounts = &state.accounts;
+    let statuses = &state.statuses;
+    let not_found = Response::new(404, r#"{"error":"Record not found"}"#);
+
+    if req.path == "/.well-known/webfinger" {
+        let resource = req.param("resource").unwrap_or("");
+        let username = resource.trim_start_matches("acct:").split('@').next().unwrap_or("");
+        if !accounts.contains_key(username) {
+            return not_found;
+        }
+        let host = req.header("Host").unwrap();
+        return Response::json(&json!({
+            "subject": format!("acct:{}@{}", username, host),
+            "links": [
+                { "rel": "http://webfinger.net/rel/profile-page", "type": "text/html", "href": format!("http://{}/@{}", host, username) },
+                { "rel": "self", "type": "application/activity+json", "href": format!("http://{}/users/{}", host, username) },
+            ],
+        })).header("Content-Type", "application/jrd+json");
+    }
+    if req.path == "/api/v1/accounts/lookup" {
+        return match accounts.get(req.param("acct").unwrap_or("")) {
+            Some(account) => Response::json(account),
+            None => not_found,
+        };
+    }
+    if let Some(id) = req.path.strip_prefix("/api/v1/accounts/").and_then(|rest| rest.strip_suffix("/statuses")) {
+        if state.failing.iter().any(|failing| failing == id) {
+            return Response::new(500, r#"{"error":"Internal Server Error"}"#);
+        }
+        let since_id: u64 = req.param("since_id").and_then(|id| id.parse().ok()).unwrap_or(0);
+        let limit: usize = req.param("limit").and_then(|limit| limit.parse().ok()).unwrap_or(20);
+        let timeline: Vec<&Value> = statuses.get(id).map(|timeline| {
+            timeline.iter().filter(|status| status["id"].as_str().unwrap().parse::<u64>().unwrap() > since_id).take(limit).collect()
+        }).unwrap_or_default();
+        return Response::json(&json!(timeline));
+    }
+    not_found
+}
+
+impl FakeMastodon {
+    pub fn start() -> FakeMastodon {
+        let state = Arc::new(Mutex::new(MastodonState::default()));
+        let handler_state = state.clone();
+        let server = FakeServer::start(move |req| handle_mastodon(&handler_state, req));
+        FakeMastodon { server: server, state: state }
+    }
+
+    // Host of the instance, which is the domain of its accounts.
+    pub fn host(&self) -> &str {
+        self.server.base_url.trim_start_matches("http://")
+    }
+
+    // Returns "@username@host" of the account.
+    pub fn acct(&self, username: &str) -> String {
+        format!("@{}@{}", username, self.host())
+    }
+
+    pub fn create_account(&self, username: &str, display_name: &str) -> Value {
+        let mut state = self.state.lock().unwrap();
+        let account = json!({
+            "id": (state.accounts.len() + 1).to_string(),
+            "username": username,
+            "acct": username,
+            "display_name": display_name,
+            "locked": false,
+            "url": format!("https://mstdn.example/@{}", username),
+        });
+        state.accounts.insert(String::from(username), account.clone());
+        account
+    }
+
+    pub fn post(&self, status: Value) {
+        let mut state = self.state.lock().unwrap();
+        let account_id = String::from(status["account"]["id"].as_str().unwrap());
+        state.statuses.entry(account_id).or_default().insert(0, status);
+    }
+
+    pub fn fail(&self, account: &Value) {
+        self.state.lock().unwrap().failing.push(String::from(account["id"].as_str().unwrap()));
+    }
+}
+
+// Returns a Bluesky profile of the handle, whose DID is derived from it.
+pub fn bluesky_profile(handle: &str, display_name: &str) -> Value {
+    json!({
+        "did": format!("did:plc:{}", handle.split('.').next().unwrap()),
+        "handle": handle,
+        "displayName": display_name,
+    })
+}
+
+// Returns a post view of the author, indexed a second after it is created.
+pub fn bluesky_post(author: &Value, rkey: &str, text: &str, created_at: &str) -> Value {
+    json!({
+        "uri": format!("at://{}/app.bsky.feed.post/{}", author["did"].as_str().unwrap(), rkey),
+        "cid": format!("bafy{}", rkey),
+        "author": author,
+        "record": { "$type": "app.bsky.feed.post", "text": text, "createdAt": created_at },
+        "indexedAt": created_at.replace(":00.000Z", ":01.000Z"),
+    })
+}
+
+// Fake of the Bluesky AppView, which returns author feeds by pages of two items.
+pub struct FakeBluesky {
+    pub server: FakeServer,
+    pub state: Arc<Mutex<BlueskyState>>,
+}
+
+#[derive(Debug, Default)]
+pub struct BlueskyState {
+    // profiles by handle
+    pub profiles: HashMap<String, Value>,
+    // feed items of each DID, newest first
+    pub feeds: HashMap<String, Vec<Value>>,
+    // DIDs whose author feeds are answered with 500 Internal Server Error
+    pub failing: Vec<String>,
+}
+
+const BLUESKY_PAGE_SIZE: usize = 2;
+
+fn handle_bluesky(state: &Mutex<BlueskyState>, req: &Request) -> Response {
+    let state = state.lock().unwrap();
+    let invalid = Response::new(400, r#"{"error":"InvalidRequest","message":"Profile not found"}"#);
+    let find_profile = |actor: &str| state.profiles.values().find(|profile| profile["did"] == actor || profile["handle"] == actor).cloned();
+
+    match req.path.as_str() {
+        "/xrpc/com.atproto.identity.resolveHandle" => match find_profile(req.param("handle").unwrap_or("")) {
+            Some(profile) => Response::json(&json!({ "did": profile["did"] })),
+            None => invalid,
+        },
+        "/xrpc/app.bsky.actor.getProfile" => match find_profile(req.param("actor").unwrap_or("")) {
+            Some(profile) => Response::json(&profile),
+            None => invalid,
+        },
+        "/xrpc/app.bsky.feed.getAuthorFeed" => {
+            if state.failing.iter().any(|did| Some(did.as_str()) == req.param("actor")) {
+                return Response::new(500, r#"{"error":"InternalServerError"}"#);
+            }
+            let feed = state.feeds.get(req.param("actor").unwrap_or("")).cloned().unwrap_or_default();
+            let offset: usize = req.param("cursor").and_then(|cursor| cursor.parse().ok()).unwrap_or(0);
+            let limit: usize = req.param("limit").and_then(|limit| limit.parse().ok()).unwrap_or(50);
+            let end = feed.len().min(offset + limit.min(BLUESKY_PAGE_SIZE));
+            let mut body = json!({ "feed": feed[offset.min(end)..end] });
+            if end < feed.len() {
+                body["cursor"] = json!(end.to_string());
+            }
+            Response::json(&body)
+        },
+        _ => Response::new(404, r#"{"error":"MethodNotImplemented"}"#),
+    }
+}
+
+impl FakeBluesky {
+    pub fn start() -> FakeBluesky {
+        let state = Arc::new(Mutex::new(BlueskyState::default()));
+        let handler_state = state.clone();
+        let server = FakeServer::start(move |req| handle_bluesky(&handler_state, req));
+        FakeBluesky { server: server, state: state }
+    }
+
+    pub fn create_profile(&self, handle: &str, display_name: &str) -> Value {
+        let profile = bluesky_profile(handle, display_name);
+        self.state.lock().unwrap().profiles.insert(String::from(handle), profile.clone());
+        profile
+    }
+
+    // Adds the item to the author feed of the DID.
+    pub fn push(&self, did: &Value, item: Value) {
+        let did = String::from(did.as_str().unwrap());
+        self.state.lock().unwrap().feeds.entry(did).or_default().insert(0, item);
+    }
+
+    pub fn fail(&self, did: &Value) {
+        self.state.lock().unwrap().failing.push(String::from(did.as_str().unwrap()));
+    }
+}
+
+// Home directory of twitnot with the default profile, whose gmail command appends mails to a file.
+pub struct TestEnv {
+    pub home: TempDir,
+    pub api_base_url: String,
+}
+
+impl TestEnv {
+    pub fn new(api_base_url: &str) -> TestEnv {
+        let home = TempDir::new().unwrap();
+
+        let gmail_command = home.path().join("gmail");
+        fs::write(&gmail_command, GMAIL_COMMAND).unwrap();
+        fs::set_permissions(&gmail_command, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let config = json!({
+            "consumer_key": "consumer-key",
+            "consumer_secret": "consumer-secret",
+            "gmail_command": gmail_command.to_str().unwrap(),
+            "database_file": home.path().join("default.sqlite3").to_str().unwrap(),
+            "notification_from_email": "from@example.com",
+            "notification_tos": ["to@example.com"],
+        });
+        fs::write(home.path().join("default"), config.to_string()).unwrap();
+
+        TestEnv { home: home, api_base_url: String::from(api_base_url) }
+    }
+
+    // Overwrites a setting of the default profile.
+    pub fn set_config(&self, key: &str, value: Value) {
+        let mut config: Value = serde_json::from_str(&fs::read_to_string(self.path("default")).unwrap()).unwrap();
+        config[key] = value;
+        fs::write(self.path("default"), config.to_string()).unwrap();
+    }
+
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.home.path().join(name)
+    }
+
+    pub fn run(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_twitnot"))
+            .args(args)
+            .current_dir(self.home.path())
+            .env("TWITNOT_HOME", self.home.path())
+            .env("TWITNOT_API_BASE_URL", &self.api_base_url)
+            .env_remove("RUST_LOG")
+            .output()
+            .unwrap()
+    }
+
+    // Runs twitnot with the lines given to its standard input.
+    pub fn run_with_stdin(&self, args: &[&str], input: &str) -> Output {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_twitnot"))
+            .args(args)
+            .current_dir(self.home.path())
+            .env("TWITNOT_HOME", self.home.path())
+            .env("TWITNOT_API_BASE_URL", &self.api_base_url)
+            .env_remove("RUST_LOG")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+        child.wait_with_output().unwrap()
+    }
+
+    // Runs twitnot, expecting it to succeed, and returns its standard output.
+    pub fn stdout(&self, args: &[&str]) -> String {
+        let output = self.run(args);
+        assert!(output.status.success(), "twitnot {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    pub fn mails(&self) -> String {
+        fs::read_to_string(self.path("mails.log")).unwrap_or_default()
+    }
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use serde_json::json;
+
+use common::{tweet, FakeTwitter, TestEnv};
+
+#[test]
+fn test_check_update_detects_deleted_tweets() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+    twitter.post("vortis_pr", tweet(101, "vortis_pr", "ヴォルティススタジアム", "second"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.set_config("detect_deletions", json!(true));
+    env.set_config("notify_deletions", json!(true));
+    env.stdout(&["add", "vortis_pr"]);
+
+    twitter.delete("vortis_pr", 100);
+    twitter.post("vortis_pr", tweet(102, "vortis_pr", "ヴォルティススタジアム", "third"));
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains("vortis_pr: 1 tweets were deleted"));
+    assert!(stdout.contains("vortis_pr: imported 1 tweets and send 1 mails"));
+
+    let lookup_requests = twitter.server.requests_to("/1.1/statuses/lookup.json");
+    assert_eq!(1, lookup_requests.len());
+    assert_eq!(Some("100"), lookup_requests[0].param("id"));
+    let show_requests = twitter.server.requests_to("/1.1/statuses/show.json");
+    assert_eq!(1, show_requests.len());
+    assert_eq!(Some("100"), show_requests[0].param("id"));
+
+    let mails = env.mails();
+    assert!(mails.contains("Subject: 【削除通知】ヴォルティススタジアム"));
+    assert!(mails.contains("http://twitter.com/vortis_pr/status/100"));
+
+    let stdout = env.stdout(&["list", "vortis_pr", "--deleted"]);
+    assert_eq!(1, stdout.lines().count());
+    assert!(stdout.starts_with("100\t"));
+    assert!(stdout.contains("\tfirst\t"));
+
+    // a deleted tweet is not looked up again.
+    env.stdout(&["check_update"]);
+    assert_eq!(1, twitter.server.requests_to("/1.1/statuses/lookup.json").len());
+}
+
+#[test]
+fn test_check_update_does_not_delete_unavailable_tweets() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+    twitter.post("vortis_pr", tweet(101, "vortis_pr", "ヴォルティススタジアム", "second"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.set_config("detect_deletions", json!(true));
+    env.set_config("notify_deletions", json!(true));
+    env.stdout(&["add", "vortis_pr"]);
+
+    twitter.hide("vortis_pr", 100);
+    twitter.post("vortis_pr", tweet(102, "vortis_pr", "ヴォルティススタジアム", "third"));
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains("vortis_pr: 1 tweets were unavailable"), "{}", stdout);
+    assert!(!stdout.contains("were deleted"), "{}", stdout);
+
+    assert!(!env.mails().contains("【削除通知】"));
+    assert_eq!("", env.stdout(&["list", "vortis_pr", "--deleted"]));
+}
+
+#[test]
+fn test_check_update_ignores_deletions_by_default() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.stdout(&["add", "vortis_pr"]);
+
+    twitter.delete("vortis_pr", 100);
+    env.stdout(&["check_update"]);
+    assert!(twitter.server.requests_to("/1.1/statuses/lookup.json").is_empty());
+    assert_eq!("", env.stdout(&["list", "vortis_pr", "--deleted"]));
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use common::{fixture, FakeFeed, FakeTwitter, TestEnv};
+
+const NEW_RSS_ITEM: &'static str = r#"<item>
+      <title>試合結果</title>
+      <link>https://www.vortis.jp/news/3</link>
+      <guid>https://www.vortis.jp/news/3</guid>
+      <pubDate>Sat, 02 May 2020 18:00:00 +0900</pubDate>
+      <description>&lt;p&gt;2-1で勝利しました。&lt;/p&gt;</description>
+    </item>
+    <item>"#;
+
+const NEW_ATOM_ENTRY: &'static str = r#"<entry>
+    <title>新体制発表</title>
+    <link href="https://blog.vortis.jp/entry/3"/>
+    <id>tag:blog.vortis.jp,2020:entry-3</id>
+    <published>2020-05-03T09:00:00+09:00</published>
+    <summary>新体制を発表しました。</summary>
+  </entry>
+  <entry>"#;
+
+#[test]
+fn test_watch_rss_feed() {
+    let twitter = FakeTwitter::start();
+    let feed = FakeFeed::start(&fixture("feeds/rss.xml"));
+    let env = TestEnv::new(&twitter.server.base_url);
+
+    let stdout = env.stdout(&["add", "--feed", &feed.url()]);
+    assert!(stdout.contains(&format!("{}: added", feed.url())));
+    assert!(stdout.contains("imported 2 tweets"));
+
+    // not modified
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains(&format!("{}: imported 0 tweets and send 0 mails", feed.url())));
+    let requests = feed.server.requests();
+    assert_eq!(Some("\"v1\""), requests.last().unwrap().header("If-None-Match"));
+    assert_eq!(Some("Fri, 01 May 2020 03:00:00 GMT"), requests.last().unwrap().header("If-Modified-Since"));
+
+    // a new item is notified, while an edited one is not notified again.
+    let body = fixture("feeds/rss.xml")
+        .replacen("<item>", NEW_RSS_ITEM, 1)
+        .replace("ホームゲーム開催のお知らせ", "ホームゲーム開催のお知らせ（更新）");
+    feed.update(&body);
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains(&format!("{}: imported 1 tweets and send 1 mails", feed.url())));
+
+    let mails = env.mails();
+    assert!(mails.contains("Subject: 【更新通知】徳島ヴォルティス ニュース"));
+    assert!(mails.contains("試合結果\n\n2-1で勝利しました。"));
+    assert!(mails.contains("URL: https://www.vortis.jp/news/3"));
+    assert!(!mails.contains("（更新）"));
+
+    let stdout = env.stdout(&["list"]);
+    assert!(stdout.contains(&format!("{}\t徳島ヴォルティス ニュース\tfeed", feed.url())));
+    let stdout = env.stdout(&["list", &feed.url()]);
+    assert!(stdout.lines().next().unwrap().contains("2020-05-02 09:00:00 UTC\t試合結果"));
+    assert_eq!(1, stdout.matches("ホームゲーム開催のお知らせ").count());
+}
+
+#[test]
+fn test_watch_atom_feed() {
+    let twitter = FakeTwitter::start();
+    let feed = FakeFeed::start(&fixture("feeds/atom.xml"));
+    let env = TestEnv::new(&twitter.server.base_url);
+
+    let stdout = env.stdout(&["add", "--feed", &feed.url()]);
+    assert!(stdout.contains("imported 2 tweets"));
+
+    let body = fixture("feeds/atom.xml").replacen("<entry>", NEW_ATOM_ENTRY, 1);
+    feed.update(&body);
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains(&format!("{}: imported 1 tweets and send 1 mails", feed.url())));
+
+    let mails = env.mails();
+    assert!(mails.contains("Subject: 【更新通知】Vortis Blog"));
+    assert!(mails.contains("URL: https://blog.vortis.jp/entry/3"));
+}
+
+#[test]
+fn test_add_rejects_non_feed() {
+    let twitter = FakeTwitter::start();
+    let feed = FakeFeed::start("<html><body>not a feed</body></html>");
+    let env = TestEnv::new(&twitter.server.base_url);
+
+    let output = env.run(&["add", "--feed", &feed.url()]);
+    assert!(!output.status.success());
+    assert!(env.stdout(&["list"]).is_empty());
+}
+
+#[test]
+fn test_check_update_continues_after_failing_feed() {
+    let twitter = FakeTwitter::start();
+    let failing = FakeFeed::start(&fixture("feeds/rss.xml"));
+    let feed = FakeFeed::start(&fixture("feeds/atom.xml"));
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.stdout(&["add", "--feed", &failing.url()]);
+    env.stdout(&["add", "--feed", &feed.url()]);
+
+    failing.fail();
+    feed.update(&fixture("feeds/atom.xml").replacen("<entry>", NEW_ATOM_ENTRY, 1));
+    let output = env.run(&["check_update"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(&format!("failed to check {}", failing.url())), "{}", stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("{}: imported 1 tweets and send 1 mails", feed.url())), "{}", stdout);
+    assert!(env.mails().contains("URL: https://blog.vortis.jp/entry/3"));
+}
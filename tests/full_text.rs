@@ -0,0 +1,37 @@
+mod common;
+
+use serde_json::json;
+
+use common::{retweet, tweet, FakeTwitter, TestEnv};
+
+fn long_text(label: &str) -> String {
+    format!("{}{}", label, "徳島ヴォルティスの試合日程をお知らせします。".repeat(10))
+}
+
+fn assert_full_texts_are_stored(api_version: &str) {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", &long_text("first")));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.set_config("api_version", json!(api_version));
+    env.stdout(&["add", "vortis_pr"]);
+
+    let original = tweet(90, "tokushima_vortis", "徳島ヴォルティス", &long_text("second"));
+    twitter.post("vortis_pr", retweet(101, "vortis_pr", "ヴォルティススタジアム", original));
+    env.stdout(&["check_update"]);
+    assert!(env.mails().contains(&format!("RT @tokushima_vortis: {}", long_text("second"))));
+
+    let stdout = env.stdout(&["list", "vortis_pr"]);
+    assert!(stdout.contains(&long_text("first")));
+    assert!(stdout.contains(&format!("RT @tokushima_vortis: {}", long_text("second"))));
+}
+
+#[test]
+fn test_full_texts_are_stored_with_api_v1() {
+    assert_full_texts_are_stored("1.1");
+}
+
+#[test]
+fn test_full_texts_are_stored_with_api_v2() {
+    assert_full_texts_are_stored("2");
+}
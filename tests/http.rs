@@ -0,0 +1,54 @@
+mod common;
+
+use std::fs;
+
+use serde_json::json;
+
+use common::{tweet, FakeTwitter, TestEnv};
+
+#[test]
+fn test_requests_go_through_proxy() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+
+    // the fake server serves as the proxy of an API which can't be reached directly.
+    let env = TestEnv::new("http://api.twitter.invalid");
+    env.set_config("proxy", json!(twitter.server.base_url));
+    let stdout = env.stdout(&["add", "vortis_pr"]);
+    assert!(stdout.contains("imported 1 tweets"));
+
+    let requests = twitter.server.requests();
+    assert!(!requests.is_empty());
+    assert!(requests.iter().all(|req| req.proxied_host.as_deref() == Some("api.twitter.invalid")));
+}
+
+#[test]
+fn test_no_proxy_hosts_are_requested_directly() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.set_config("proxy", json!("http://proxy.invalid:8080"));
+    env.set_config("no_proxy", json!(["127.0.0.1", "localhost"]));
+    env.stdout(&["add", "vortis_pr"]);
+    assert!(twitter.server.requests().iter().all(|req| req.proxied_host.is_none()));
+}
+
+#[test]
+fn test_invalid_http_settings_are_config_errors() {
+    let twitter = FakeTwitter::start();
+    let env = TestEnv::new(&twitter.server.base_url);
+
+    env.set_config("proxy", json!("not a url"));
+    let output = env.run(&["add", "vortis_pr"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Config error: proxy"));
+
+    env.set_config("proxy", json!(null));
+    fs::write(env.path("ca.pem"), "no certificates").unwrap();
+    env.set_config("ca_bundle", json!(env.path("ca.pem")));
+    let output = env.run(&["add", "vortis_pr"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Config error: ca_bundle"));
+    assert!(twitter.server.requests().is_empty());
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use serde_json::json;
+
+use common::{retweet, tweet, with_quote, with_reply, FakeTwitter, TestEnv};
+
+// Posts an original tweet, a retweet, a reply, a reply to the original one and a quote, in this order.
+fn post_tweets(twitter: &FakeTwitter) {
+    let other = tweet(50, "tokushima_vortis", "徳島ヴォルティス", "試合開始です");
+    twitter.post("tokushima_vortis", other.clone());
+    let original = tweet(101, "vortis_pr", "ヴォルティススタジアム", "本日の試合");
+    twitter.post("vortis_pr", original.clone());
+    twitter.post("vortis_pr", retweet(102, "vortis_pr", "ヴォルティススタジアム", other.clone()));
+    twitter.post("vortis_pr", with_reply(tweet(103, "vortis_pr", "ヴォルティススタジアム", "頑張れ"), &other));
+    twitter.post("vortis_pr", with_reply(tweet(104, "vortis_pr", "ヴォルティススタジアム", "キックオフは14時"), &original));
+    twitter.post("vortis_pr", with_quote(tweet(105, "vortis_pr", "ヴォルティススタジアム", "応援よろしく"), other));
+}
+
+fn listed_ids(env: &TestEnv, kinds: &str) -> Vec<String> {
+    let stdout = env.stdout(&["list", "vortis_pr", "--kind", kinds]);
+    let mut ids: Vec<String> = stdout.lines()
+        .filter(|line| !line.starts_with('\t'))
+        .filter_map(|line| line.split('\t').next().map(String::from))
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn test_check_update_notifies_configured_kinds() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.set_config("notify_kinds", json!(["original", "self_thread"]));
+    env.stdout(&["add", "vortis_pr"]);
+
+    post_tweets(&twitter);
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains("vortis_pr: imported 5 tweets and send 2 mails"), "{}", stdout);
+    let mails = env.mails();
+    assert!(mails.contains("本日の試合"));
+    assert!(mails.contains("キックオフは14時"));
+    assert!(!mails.contains("頑張れ"));
+    assert!(!mails.contains("応援よろしく"));
+}
+
+#[test]
+fn test_list_filters_kinds() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.stdout(&["add", "vortis_pr"]);
+    post_tweets(&twitter);
+    env.stdout(&["check_update"]);
+
+    assert_eq!(vec!["102"], listed_ids(&env, "retweet"));
+    assert_eq!(vec!["103", "105"], listed_ids(&env, "reply,quote"));
+    assert_eq!(vec!["104"], listed_ids(&env, "self_thread"));
+    assert_eq!(vec!["100", "101"], listed_ids(&env, "original"));
+    assert!(!env.run(&["list", "vortis_pr", "--kind", "like"]).status.success());
+}
+
+#[test]
+fn test_list_filters_kinds_with_api_v2() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.set_config("api_version", json!("2"));
+    env.stdout(&["add", "vortis_pr"]);
+    post_tweets(&twitter);
+    env.stdout(&["check_update"]);
+
+    assert_eq!(vec!["102"], listed_ids(&env, "retweet"));
+    assert_eq!(vec!["103", "105"], listed_ids(&env, "reply,quote"));
+    assert_eq!(vec!["104"], listed_ids(&env, "self_thread"));
+}
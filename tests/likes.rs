@@ -0,0 +1,84 @@
+mod common;
+
+use serde_json::json;
+
+use common::{bluesky_post, tweet, FakeBluesky, FakeTwitter, TestEnv};
+
+#[test]
+fn test_check_update_notifies_likes() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+    twitter.like("vortis_pr", tweet(50, "tokushima_vortis", "徳島ヴォルティス", "試合開始です"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    let stdout = env.stdout(&["add", "vortis_pr", "--likes"]);
+    assert!(stdout.contains("imported 1 likes"), "{}", stdout);
+
+    twitter.like("vortis_pr", tweet(51, "jleaguejp", "Jリーグ", "試合日程のお知らせ"));
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains("vortis_pr: imported 0 tweets and send 0 mails"), "{}", stdout);
+    assert!(stdout.contains("vortis_pr: imported 1 likes and send 1 mails"), "{}", stdout);
+
+    // the likes imported by add are not notified.
+    let mails = env.mails();
+    assert!(mails.contains("Subject: 【いいね通知】ヴォルティススタジアム"));
+    assert!(mails.contains("ヴォルティススタジアム (@vortis_pr) がいいねしました\n\nJリーグ (@jleaguejp): 試合日程のお知らせ\n\nURL: http://twitter.com/jleaguejp/status/51"));
+    assert!(!mails.contains("試合開始です"));
+
+    // a like found again is not notified again.
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains("vortis_pr: imported 0 likes and send 0 mails"), "{}", stdout);
+
+    // liked tweets are not listed as tweets of the user.
+    let stdout = env.stdout(&["list", "vortis_pr"]);
+    assert!(stdout.contains("\tfirst"));
+    assert!(!stdout.contains("試合日程のお知らせ"));
+    let stdout = env.stdout(&["list", "vortis_pr", "--likes"]);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(2, lines.len());
+    assert!(lines[0].starts_with("51\t") && lines[0].ends_with("\t@jleaguejp: 試合日程のお知らせ"), "{}", stdout);
+    assert!(lines[1].starts_with("50\t") && lines[1].ends_with("\t@tokushima_vortis: 試合開始です"), "{}", stdout);
+}
+
+#[test]
+fn test_likes_are_opt_in() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+    twitter.like("vortis_pr", tweet(50, "tokushima_vortis", "徳島ヴォルティス", "試合開始です"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.stdout(&["add", "vortis_pr"]);
+    env.stdout(&["check_update"]);
+    assert!(twitter.server.requests_to("/1.1/favorites/list.json").is_empty());
+
+    env.stdout(&["add", "vortis_pr", "--likes"]);
+    assert!(env.stdout(&["list"]).contains("vortis_pr\tヴォルティススタジアム\tlikes\n"));
+    env.stdout(&["remove", "vortis_pr", "--likes"]);
+    twitter.like("vortis_pr", tweet(51, "jleaguejp", "Jリーグ", "試合日程のお知らせ"));
+    env.stdout(&["check_update"]);
+    assert_eq!(1, twitter.server.requests_to("/1.1/favorites/list.json").len());
+    assert!(env.mails().is_empty());
+
+    // the user is still watched.
+    let stdout = env.stdout(&["list"]);
+    assert!(stdout.contains("vortis_pr\t"));
+    assert!(env.stdout(&["list", "vortis_pr", "--likes"]).is_empty());
+}
+
+#[test]
+fn test_add_likes_of_twitter_user_sharing_name_with_bluesky() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+    twitter.like("vortis_pr", tweet(50, "tokushima_vortis", "徳島ヴォルティス", "試合開始です"));
+    let bluesky = FakeBluesky::start();
+    let vortis = bluesky.create_profile("vortis_pr", "徳島ヴォルティス");
+    bluesky.push(&vortis["did"], json!({ "post": bluesky_post(&vortis, "3kfirst", "開幕戦のお知らせ", "2020-05-01T10:00:00.000Z") }));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.set_config("bluesky_api_base_url", json!(bluesky.server.base_url));
+    env.stdout(&["add", "--bluesky", "vortis_pr"]);
+    // the likes are those of the Twitter account just added, not of the Bluesky one stored first.
+    let stdout = env.stdout(&["add", "vortis_pr", "--likes"]);
+    assert!(stdout.contains("imported 1 likes"), "{}", stdout);
+    assert_eq!(1, twitter.server.requests_to("/1.1/favorites/list.json").len());
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use common::{tweet, FakeTwitter, TestEnv};
+
+#[test]
+fn test_watch_list() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+    twitter.post("tokushima_vortis", tweet(101, "tokushima_vortis", "徳島ヴォルティス", "second"));
+    twitter.post("jleaguejp", tweet(102, "jleaguejp", "Jリーグ", "third"));
+    twitter.set_list_members("vortis_pr/vortis", &["vortis_pr", "tokushima_vortis"]);
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    let stdout = env.stdout(&["add", "--list", "vortis_pr/vortis"]);
+    assert!(stdout.contains("vortis_pr/vortis: added"));
+    assert!(stdout.contains("vortis_pr/vortis: 2 members added and 0 members removed"));
+    assert!(stdout.contains("imported 2 tweets"));
+
+    // membership changes upstream are followed on the next check.
+    twitter.set_list_members("vortis_pr/vortis", &["tokushima_vortis", "jleaguejp"]);
+    twitter.post("jleaguejp", tweet(103, "jleaguejp", "Jリーグ", "fourth"));
+    twitter.post("vortis_pr", tweet(104, "vortis_pr", "ヴォルティススタジアム", "fifth"));
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains("vortis_pr/vortis: 1 members added and 1 members removed"));
+    assert!(stdout.contains("vortis_pr/vortis: imported 2 tweets and send 2 mails"));
+
+    // tweets are attributed to their authors.
+    let stdout = env.stdout(&["list", "jleaguejp"]);
+    assert_eq!(2, stdout.lines().count());
+    let stdout = env.stdout(&["list", "tokushima_vortis"]);
+    assert_eq!(1, stdout.lines().count());
+
+    let stdout = env.stdout(&["list"]);
+    assert!(stdout.contains("vortis_pr/vortis\t2 members"));
+    assert!(!stdout.contains("jleaguejp\t"), "{}", stdout);
+
+    // members are not checked one by one.
+    assert!(twitter.server.requests_to("/1.1/statuses/user_timeline.json").is_empty());
+    let mails = env.mails();
+    assert!(mails.contains("http://twitter.com/jleaguejp/status/103"));
+
+    let stdout = env.stdout(&["remove", "--list", "vortis_pr/vortis"]);
+    assert!(stdout.contains("list is removed"));
+}
+
+#[test]
+fn test_add_fails_on_unknown_list() {
+    let twitter = FakeTwitter::start();
+    let env = TestEnv::new(&twitter.server.base_url);
+
+    let output = env.run(&["add", "--list", "vortis_pr/unknown"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("vortis_pr/unknown does not exist"));
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use common::{reblog, status, tweet, FakeMastodon, FakeTwitter, TestEnv};
+
+fn env_for(twitter: &FakeTwitter) -> TestEnv {
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.set_config("mastodon_scheme", serde_json::json!("http"));
+    env
+}
+
+#[test]
+fn test_watch_mastodon_account() {
+    let twitter = FakeTwitter::start();
+    let mastodon = FakeMastodon::start();
+    let vortis = mastodon.create_account("vortis", "徳島ヴォルティス");
+    let first = status(104139970427291000, &vortis, "<p>開幕戦のお知らせ</p>");
+    mastodon.post(first.clone());
+
+    let env = env_for(&twitter);
+    let screen_name = format!("vortis@{}", mastodon.host());
+    let stdout = env.stdout(&["add", "--mastodon", &mastodon.acct("vortis")]);
+    assert!(stdout.contains(&format!("{}: added", screen_name)));
+    assert!(stdout.contains("imported 1 tweets"));
+    let webfinger_count = mastodon.server.requests_to("/.well-known/webfinger").len();
+
+    mastodon.post(status(104139980427291100, &vortis, "<p>試合結果<br />2-1で勝利しました &amp; 首位浮上</p>"));
+    // a reblog of a stored status is imported without being notified again.
+    mastodon.post(reblog(104139990427291200, &vortis, first));
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains(&format!("{}: imported 2 tweets and send 1 mails", screen_name)));
+
+    let requests = mastodon.server.requests_to("/api/v1/accounts/1/statuses");
+    assert_eq!(Some("104139970427291000"), requests.last().unwrap().param("since_id"));
+    // the account is not resolved again once it is located.
+    assert_eq!(webfinger_count, mastodon.server.requests_to("/.well-known/webfinger").len());
+
+    let mails = env.mails();
+    assert!(mails.contains("Subject: 【更新通知】徳島ヴォルティス"));
+    assert!(mails.contains("試合結果\n2-1で勝利しました & 首位浮上"));
+    assert!(mails.contains("URL: https://mstdn.example/@vortis/104139980427291100"));
+    assert!(!mails.contains("開幕戦のお知らせ"));
+
+    let stdout = env.stdout(&["list"]);
+    assert!(stdout.contains(&format!("{}\t徳島ヴォルティス\tmastodon", screen_name)));
+}
+
+#[test]
+fn test_add_unknown_mastodon_account() {
+    let twitter = FakeTwitter::start();
+    let mastodon = FakeMastodon::start();
+
+    let env = env_for(&twitter);
+    let output = env.run(&["add", "--mastodon", &mastodon.acct("nobody")]);
+    assert!(!output.status.success());
+    assert!(env.stdout(&["list"]).is_empty());
+
+    let output = env.run(&["add", "--mastodon", "vortis"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_check_update_continues_after_failing_mastodon_account() {
+    let twitter = FakeTwitter::start();
+    let mastodon = FakeMastodon::start();
+    let vortis = mastodon.create_account("vortis", "徳島ヴォルティス");
+    let stadium = mastodon.create_account("stadium", "ヴォルティススタジアム");
+    mastodon.post(status(104139970427291000, &vortis, "<p>開幕戦のお知らせ</p>"));
+    mastodon.post(status(104139970427291001, &stadium, "<p>開門時間のお知らせ</p>"));
+
+    let env = env_for(&twitter);
+    env.stdout(&["add", "--mastodon", &mastodon.acct("vortis")]);
+    env.stdout(&["add", "--mastodon", &mastodon.acct("stadium")]);
+
+    mastodon.fail(&vortis);
+    mastodon.post(status(104139980427291100, &stadium, "<p>駐車場のご案内</p>"));
+    let output = env.run(&["check_update"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(&format!("failed to check vortis@{}", mastodon.host())), "{}", stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("stadium@{}: imported 1 tweets and send 1 mails", mastodon.host())), "{}", stdout);
+    assert!(env.mails().contains("駐車場のご案内"));
+}
+
+#[test]
+fn test_mastodon_status_does_not_collide_with_tweet() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(104139970427291000, "vortis_pr", "ヴォルティススタジアム", "スタジアムのお知らせ"));
+    let mastodon = FakeMastodon::start();
+    let vortis = mastodon.create_account("vortis", "徳島ヴォルティス");
+    mastodon.post(status(104139970427291000, &vortis, "<p>開幕戦のお知らせ</p>"));
+
+    let env = env_for(&twitter);
+    env.stdout(&["add", "vortis_pr"]);
+    let stdout = env.stdout(&["add", "--mastodon", &mastodon.acct("vortis")]);
+    assert!(stdout.contains("imported 1 tweets"), "{}", stdout);
+
+    let stdout = env.stdout(&["list", &format!("vortis@{}", mastodon.host())]);
+    assert!(stdout.contains("開幕戦のお知らせ"), "{}", stdout);
+    assert!(env.stdout(&["list", "vortis_pr"]).contains("スタジアムのお知らせ"));
+}
@@ -0,0 +1,30 @@
+mod common;
+
+use serde_json::json;
+
+use common::{tweet, with_photo, FakeTwitter, TestEnv};
+
+#[test]
+fn test_download_media() {
+    let twitter = FakeTwitter::start();
+    let base_url = twitter.server.base_url.clone();
+    twitter.post("vortis_pr", with_photo(tweet(100, "vortis_pr", "ヴォルティススタジアム", "schedule"), &base_url, "1", "schedule.jpg"));
+
+    let env = TestEnv::new(&base_url);
+    env.set_config("attach_media", json!(true));
+    env.stdout(&["add", "vortis_pr"]);
+
+    // the same photo attached to another tweet is stored only once.
+    twitter.post("vortis_pr", with_photo(tweet(101, "vortis_pr", "ヴォルティススタジアム", "flyer"), &base_url, "2", "schedule.jpg"));
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains("vortis_pr: imported 1 tweets and send 1 mails"));
+
+    let stdout = env.stdout(&["list", "vortis_pr"]);
+    let paths: Vec<&str> = stdout.lines().filter(|line| line.starts_with('\t')).map(|line| line.trim()).collect();
+    assert_eq!(2, paths.len());
+    assert_eq!(paths[0], paths[1]);
+    assert!(paths[0].ends_with(".jpg"));
+    assert_eq!("image of schedule.jpg", std::fs::read_to_string(paths[0]).unwrap());
+
+    assert!(env.mails().contains(&format!("Attach: {}", paths[0])));
+}
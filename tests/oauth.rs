@@ -0,0 +1,170 @@
+mod common;
+
+use std::fs;
+
+use serde_json::{json, Value};
+
+use common::{tweet, FakeTwitter, TestEnv};
+
+fn authorize(env: &TestEnv) {
+    env.set_config("user_access_token", json!("user-token"));
+    env.set_config("user_access_token_secret", json!("user-secret"));
+    env.set_config("user_screen_name", json!("reader"));
+}
+
+#[test]
+fn test_init_authorizes_user_by_pin() {
+    let twitter = FakeTwitter::start();
+    let env = TestEnv::new(&twitter.server.base_url);
+    let gmail_command = env.path("gmail");
+    let database_file = env.path("init.sqlite3");
+
+    let output = env.run_with_stdin(&[
+        "init", "consumer-key", "--secret", "consumer-secret",
+        "--gmail_command", gmail_command.to_str().unwrap(),
+        "--db", database_file.to_str().unwrap(),
+        "--api_base_url", &twitter.server.base_url,
+        "--user_auth",
+    ], "from@example.com\nto@example.com\n1234567\n");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(&format!("open {}/oauth/authorize?oauth_token=request-token", twitter.server.base_url)));
+    assert!(stdout.contains("authorized as reader"));
+
+    let config: Value = serde_json::from_str(&fs::read_to_string(env.path("default")).unwrap()).unwrap();
+    assert_eq!("user-token", config["user_access_token"]);
+    assert_eq!("user-secret", config["user_access_token_secret"]);
+    assert_eq!("reader", config["user_screen_name"]);
+
+    let requests = twitter.server.requests_to("/oauth/request_token");
+    assert!(requests[0].header("Authorization").unwrap().contains("oauth_consumer_key=\"consumer-key\""));
+}
+
+#[test]
+fn test_signed_request_is_signed_again_on_retry() {
+    let twitter = FakeTwitter::start();
+    twitter.state.lock().unwrap().failures = 1;
+    let env = TestEnv::new(&twitter.server.base_url);
+    let gmail_command = env.path("gmail");
+
+    let output = env.run_with_stdin(&[
+        "init", "consumer-key", "--secret", "consumer-secret",
+        "--gmail_command", gmail_command.to_str().unwrap(),
+        "--api_base_url", &twitter.server.base_url,
+        "--user_auth",
+    ], "from@example.com\nto@example.com\n1234567\n");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    // a nonce can't be used twice.
+    let requests = twitter.server.requests_to("/oauth/request_token");
+    assert_eq!(2, requests.len());
+    let nonce = |i: usize| requests[i].header("Authorization").unwrap().split(", ")
+        .find(|field| field.starts_with("oauth_nonce="))
+        .map(String::from);
+    assert!(nonce(0).is_some());
+    assert_ne!(nonce(0), nonce(1));
+}
+
+#[test]
+fn test_init_fails_with_wrong_pin() {
+    let twitter = FakeTwitter::start();
+    let env = TestEnv::new(&twitter.server.base_url);
+    let gmail_command = env.path("gmail");
+
+    let output = env.run_with_stdin(&[
+        "init", "consumer-key", "--secret", "consumer-secret",
+        "--gmail_command", gmail_command.to_str().unwrap(),
+        "--api_base_url", &twitter.server.base_url,
+        "--user_auth",
+    ], "from@example.com\nto@example.com\n0000000\n");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_protected_user_is_read_as_authorized_user() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "public"));
+    twitter.post("secret_account", tweet(200, "secret_account", "鍵アカウント", "protected"));
+    twitter.protect("secret_account");
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    authorize(&env);
+    env.stdout(&["add", "vortis_pr"]);
+    let stdout = env.stdout(&["add", "secret_account"]);
+    assert!(!stdout.contains("tweets are protected"));
+
+    twitter.post("vortis_pr", tweet(101, "vortis_pr", "ヴォルティススタジアム", "public again"));
+    twitter.post("secret_account", tweet(201, "secret_account", "鍵アカウント", "protected again"));
+    env.stdout(&["check_update"]);
+    let mails = env.mails();
+    assert!(mails.contains("public again"));
+    assert!(mails.contains("protected again"));
+
+    for req in twitter.server.requests_to("/1.1/statuses/user_timeline.json") {
+        let authorization = req.header("Authorization").unwrap();
+        if req.param("user_id") == Some(&common::user_id("secret_account").to_string()) || req.param("screen_name") == Some("secret_account") {
+            assert!(authorization.starts_with("OAuth "), "{}", authorization);
+        } else {
+            assert_eq!("Bearer AAAA", authorization);
+        }
+    }
+}
+
+#[test]
+fn test_user_protected_after_added_is_read_as_authorized_user() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    authorize(&env);
+    env.stdout(&["add", "vortis_pr"]);
+
+    twitter.post("tokushima_vortis", tweet(200, "tokushima_vortis", "徳島ヴォルティス", "first"));
+    env.stdout(&["add", "tokushima_vortis"]);
+
+    twitter.protect("vortis_pr");
+    twitter.protect("tokushima_vortis");
+    twitter.post("vortis_pr", tweet(101, "vortis_pr", "ヴォルティススタジアム", "second"));
+    twitter.post("tokushima_vortis", tweet(201, "tokushima_vortis", "徳島ヴォルティス", "second"));
+    let token_requests = twitter.server.requests_to("/oauth2/token").len();
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains("vortis_pr: imported 1 tweets and send 1 mails"));
+    assert!(stdout.contains("tokushima_vortis: imported 1 tweets and send 1 mails"));
+
+    // the bearer token is refreshed only once before falling back to the authorized user.
+    assert_eq!(token_requests + 1, twitter.server.requests_to("/oauth2/token").len());
+}
+
+#[test]
+fn test_rejected_access_token_is_refreshed_with_user_token() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    authorize(&env);
+    env.stdout(&["add", "vortis_pr"]);
+
+    twitter.state.lock().unwrap().access_token = String::from("BBBB");
+    twitter.post("vortis_pr", tweet(101, "vortis_pr", "ヴォルティススタジアム", "second"));
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains("vortis_pr: imported 1 tweets and send 1 mails"));
+    assert_eq!(2, twitter.server.requests_to("/oauth2/token").len());
+
+    // the public account is read with the new bearer token, not as the authorized user.
+    let requests = twitter.server.requests_to("/1.1/statuses/user_timeline.json");
+    assert_eq!(Some("Bearer BBBB"), requests.last().unwrap().header("Authorization"));
+    assert!(env.stdout(&["auth"]).starts_with("BBBB\t"));
+}
+
+#[test]
+fn test_protected_user_is_not_read_without_user_token() {
+    let twitter = FakeTwitter::start();
+    twitter.post("secret_account", tweet(200, "secret_account", "鍵アカウント", "protected"));
+    twitter.protect("secret_account");
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    let stdout = env.stdout(&["add", "secret_account"]);
+    assert!(stdout.contains("secret_account: tweets are protected"));
+    assert!(twitter.server.requests_to("/1.1/statuses/user_timeline.json").is_empty());
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use serde_json::json;
+
+use common::{tweet, with_quote, with_reply, FakeTwitter, TestEnv};
+
+#[test]
+fn test_check_update_renders_quoted_and_replied_tweets() {
+    let twitter = FakeTwitter::start();
+    let original = tweet(50, "tokushima_vortis", "徳島ヴォルティス", "試合開始です");
+    twitter.post("tokushima_vortis", original.clone());
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.stdout(&["add", "vortis_pr"]);
+
+    twitter.post("vortis_pr", with_quote(tweet(101, "vortis_pr", "ヴォルティススタジアム", "応援よろしく"), original.clone()));
+    twitter.post("vortis_pr", with_reply(tweet(102, "vortis_pr", "ヴォルティススタジアム", "頑張れ"), &original));
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains("vortis_pr: imported 2 tweets and send 2 mails"));
+
+    // the replied-to tweet is looked up while the quoted one comes with the timeline.
+    let lookup_requests = twitter.server.requests_to("/1.1/statuses/lookup.json");
+    assert_eq!(1, lookup_requests.len());
+    assert_eq!(Some("50"), lookup_requests[0].param("id"));
+
+    let mails = env.mails();
+    assert!(mails.contains("応援よろしく\n\n引用: 徳島ヴォルティス (@tokushima_vortis): 試合開始です\n\nURL: http://twitter.com/vortis_pr/status/101"));
+    assert!(mails.contains("頑張れ\n\n返信先: 徳島ヴォルティス (@tokushima_vortis): 試合開始です\n\nURL: http://twitter.com/vortis_pr/status/102"));
+
+    let stdout = env.stdout(&["list", "vortis_pr"]);
+    assert!(stdout.contains("\t頑張れ\n\treply to @tokushima_vortis: 試合開始です\n"));
+    assert!(stdout.contains("\t応援よろしく\n\tquote of @tokushima_vortis: 試合開始です\n"));
+}
+
+#[test]
+fn test_check_update_renders_reply_to_deleted_tweet() {
+    let twitter = FakeTwitter::start();
+    let original = tweet(50, "tokushima_vortis", "徳島ヴォルティス", "試合開始です");
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.stdout(&["add", "vortis_pr"]);
+
+    twitter.post("vortis_pr", with_reply(tweet(101, "vortis_pr", "ヴォルティススタジアム", "頑張れ"), &original));
+    env.stdout(&["check_update"]);
+    assert!(env.mails().contains("頑張れ\n\n返信先: @tokushima_vortis\n\nURL:"));
+
+    let stdout = env.stdout(&["list", "vortis_pr"]);
+    assert!(stdout.contains("\t頑張れ\n\treply to @tokushima_vortis\n"));
+}
+
+#[test]
+fn test_check_update_renders_referenced_tweets_with_api_v2() {
+    let twitter = FakeTwitter::start();
+    let original = tweet(50, "tokushima_vortis", "徳島ヴォルティス", "試合開始です");
+    twitter.post("tokushima_vortis", original.clone());
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.set_config("api_version", json!("2"));
+    env.stdout(&["add", "vortis_pr"]);
+
+    twitter.post("vortis_pr", with_quote(tweet(101, "vortis_pr", "ヴォルティススタジアム", "応援よろしく"), original.clone()));
+    twitter.post("vortis_pr", with_reply(tweet(102, "vortis_pr", "ヴォルティススタジアム", "頑張れ"), &original));
+    env.stdout(&["check_update"]);
+
+    // v2 includes the replied-to tweet, so no lookup is needed.
+    assert!(twitter.server.requests_to("/2/tweets").is_empty());
+    let mails = env.mails();
+    assert!(mails.contains("引用: 徳島ヴォルティス (@tokushima_vortis): 試合開始です"));
+    assert!(mails.contains("返信先: 徳島ヴォルティス (@tokushima_vortis): 試合開始です"));
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use serde_json::json;
+
+use common::{tweet, FakeTwitter, TestEnv};
+
+#[test]
+fn test_check_update_follows_renamed_account() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.set_config("notify_renames", json!(true));
+    env.stdout(&["add", "vortis_pr"]);
+
+    twitter.rename("vortis_pr", "vortis_stadium");
+    twitter.post("vortis_stadium", tweet(101, "vortis_stadium", "ヴォルティススタジアム", "second"));
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains("vortis_pr: renamed to vortis_stadium"));
+    assert!(stdout.contains("vortis_stadium: imported 1 tweets and send 1 mails"));
+
+    let timeline_requests = twitter.server.requests_to("/1.1/statuses/user_timeline.json");
+    assert_eq!(None, timeline_requests.last().unwrap().param("screen_name"));
+
+    let mails = env.mails();
+    assert!(mails.contains("Subject: 【名前変更通知】ヴォルティススタジアム"));
+    assert!(mails.contains("@vortis_pr → @vortis_stadium"));
+    assert!(mails.contains("http://twitter.com/vortis_stadium/status/101"));
+
+    let stdout = env.stdout(&["list", "vortis_stadium"]);
+    assert_eq!(2, stdout.lines().count());
+    let stdout = env.stdout(&["list", "vortis_stadium", "--renames"]);
+    assert!(stdout.trim_end().ends_with("\tvortis_pr\tvortis_stadium"));
+}
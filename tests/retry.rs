@@ -0,0 +1,59 @@
+mod common;
+
+use serde_json::json;
+
+use common::{tweet, FakeTwitter, TestEnv};
+
+#[test]
+fn test_check_update_retries_server_errors() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.stdout(&["add", "vortis_pr"]);
+
+    twitter.post("vortis_pr", tweet(101, "vortis_pr", "ヴォルティススタジアム", "second"));
+    twitter.state.lock().unwrap().failures = 1;
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains("vortis_pr: imported 1 tweets and send 1 mails"));
+    assert_eq!(3, twitter.server.requests_to("/1.1/statuses/user_timeline.json").len());
+}
+
+#[test]
+fn test_check_update_gives_up_after_max_attempts() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.set_config("retry_attempts", json!(2));
+    env.stdout(&["add", "vortis_pr"]);
+
+    twitter.state.lock().unwrap().failures = 2;
+    let output = env.run(&["check_update"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("503"));
+    assert_eq!(3, twitter.server.requests_to("/1.1/statuses/user_timeline.json").len());
+}
+
+#[test]
+fn test_retry_stops_at_deadline() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "first"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    env.set_config("retry_deadline", json!(0));
+    twitter.state.lock().unwrap().failures = 1;
+    let output = env.run(&["add", "vortis_pr"]);
+    assert!(!output.status.success());
+    assert_eq!(1, twitter.server.requests().len());
+}
+
+#[test]
+fn test_not_found_is_not_retried() {
+    let twitter = FakeTwitter::start();
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    let output = env.run(&["add", "no_such_user"]);
+    assert!(!output.status.success());
+    assert_eq!(1, twitter.server.requests_to("/1.1/users/lookup.json").len());
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use common::{tweet, FakeTwitter, TestEnv};
+
+#[test]
+fn test_watch_search() {
+    let twitter = FakeTwitter::start();
+    twitter.post("vortis_pr", tweet(100, "vortis_pr", "ヴォルティススタジアム", "ポカリスエットスタジアムへようこそ"));
+    twitter.post("jleaguejp", tweet(101, "jleaguejp", "Jリーグ", "ポカリスエットスタジアムで試合"));
+    twitter.post("jleaguejp", tweet(102, "jleaguejp", "Jリーグ", "unrelated"));
+
+    let env = TestEnv::new(&twitter.server.base_url);
+    let stdout = env.stdout(&["add-search", "ポカリスエットスタジアム"]);
+    assert!(stdout.contains("ポカリスエットスタジアム: added"));
+    assert!(stdout.contains("imported 2 tweets"));
+
+    twitter.post("tokushima_vortis", tweet(103, "tokushima_vortis", "徳島ヴォルティス", "明日はポカリスエットスタジアム"));
+    let stdout = env.stdout(&["check_update"]);
+    assert!(stdout.contains("ポカリスエットスタジアム: imported 1 tweets and send 1 mails"));
+
+    let requests = twitter.server.requests_to("/1.1/search/tweets.json");
+    assert_eq!(Some("101"), requests.last().unwrap().param("since_id"));
+
+    let mails = env.mails();
+    assert!(mails.contains("Subject: 【更新通知】徳島ヴォルティス"));
+    assert!(mails.contains("http://twitter.com/tokushima_vortis/status/103"));
+    assert!(mails.contains("検索: ポカリスエットスタジアム"));
+
+    let stdout = env.stdout(&["list", "--search", "ポカリスエットスタジアム"]);
+    assert_eq!(3, stdout.lines().count());
+    let stdout = env.stdout(&["list"]);
+    assert!(stdout.contains("ポカリスエットスタジアム\tsearch"));
+
+    let stdout = env.stdout(&["remove-search", "ポカリスエットスタジアム"]);
+    assert!(stdout.contains("search is removed"));
+    let stdout = env.stdout(&["list"]);
+    assert!(!stdout.contains("search"));
+}